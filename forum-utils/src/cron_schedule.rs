@@ -0,0 +1,213 @@
+use chrono::{Datelike, Days, NaiveDateTime, Timelike};
+
+/// 向后查找下一次运行时间的最大天数
+const MAX_SEARCH_DAYS: u64 = 366 * 5;
+
+/// 五段式cron表达式：分 时 日 月 周
+///
+/// 每段支持`*`、`5`、`1-5`、`*/15`、`10-50/20`及以逗号分隔的组合，周日为0或7；
+/// 日与周都不是`*`时满足其一即可。另支持`@hourly`、`@daily`、`@weekly`、`@monthly`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// 解析cron表达式，格式错误时返回错误说明
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expr => expr,
+        };
+        let fields: Vec<_> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("cron表达式应有5段，实际为{}段", fields.len()));
+        };
+
+        let mut weekdays = Self::parse_field(weekday, 0, 7)?;
+        // 7与0都表示周日
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: Self::parse_field(minute, 0, 59)?,
+            hours: Self::parse_field(hour, 0, 23)?,
+            days: Self::parse_field(day, 1, 31)?,
+            months: Self::parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// 解析一段，返回取值的位图
+    fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+        let mut bits = 0;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            let step = match step {
+                Some(step) => step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&s| s > 0)
+                    .ok_or_else(|| format!("无效的步长：{}", part))?,
+                None => 1,
+            };
+
+            let value = |v: &str| {
+                v.parse::<u32>()
+                    .ok()
+                    .filter(|v| (min..=max).contains(v))
+                    .ok_or_else(|| format!("{}超出范围{}-{}", part, min, max))
+            };
+            let (start, end) = match range.split_once('-') {
+                _ if range == "*" => (min, max),
+                Some((start, end)) => (value(start)?, value(end)?),
+                // 带步长的单个值表示从该值到最大值
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            };
+            if start > end {
+                return Err(format!("无效的范围：{}", part));
+            }
+
+            for v in (start..=end).step_by(step as usize) {
+                bits |= 1 << v;
+            }
+        }
+        Ok(bits)
+    }
+
+    fn matches_date(&self, date: NaiveDateTime) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// 晚于`after`的下一次运行时间（精确到分钟），五年内没有满足的时间时为空
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let first_day = start.date().and_hms_opt(0, 0, 0)?;
+
+        for offset in 0..MAX_SEARCH_DAYS {
+            let day = first_day.checked_add_days(Days::new(offset))?;
+            if !self.matches_date(day) {
+                continue;
+            }
+            let (from_hour, from_minute) = match offset {
+                0 => (start.hour(), start.minute()),
+                _ => (0, 0),
+            };
+            for hour in from_hour..24 {
+                if self.hours & (1 << hour) == 0 {
+                    continue;
+                }
+                let from_minute = if hour == from_hour { from_minute } else { 0 };
+                if let Some(minute) = (from_minute..60).find(|m| self.minutes & (1 << m) != 0) {
+                    return day.with_hour(hour)?.with_minute(minute);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_every_hour() {
+        let cron = CronSchedule::parse("@hourly").unwrap();
+        assert_eq!(
+            cron.next_after(at(2024, 3, 1, 10, 0)),
+            Some(at(2024, 3, 1, 11, 0))
+        );
+        assert_eq!(
+            cron.next_after(at(2024, 3, 1, 23, 30)),
+            Some(at(2024, 3, 2, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_steps_and_lists() {
+        let cron = CronSchedule::parse("*/20 9-17 * * *").unwrap();
+        assert_eq!(
+            cron.next_after(at(2024, 3, 1, 9, 5)),
+            Some(at(2024, 3, 1, 9, 20))
+        );
+        assert_eq!(
+            cron.next_after(at(2024, 3, 1, 17, 40)),
+            Some(at(2024, 3, 2, 9, 0))
+        );
+
+        let cron = CronSchedule::parse("5,35 4 * * *").unwrap();
+        assert_eq!(
+            cron.next_after(at(2024, 3, 1, 4, 5)),
+            Some(at(2024, 3, 1, 4, 35))
+        );
+    }
+
+    #[test]
+    fn test_day_and_weekday() {
+        // 2024-03-01是周五
+        let cron = CronSchedule::parse("0 8 * * 1-5").unwrap();
+        assert_eq!(
+            cron.next_after(at(2024, 3, 1, 9, 0)),
+            Some(at(2024, 3, 4, 8, 0))
+        );
+
+        let cron = CronSchedule::parse("0 0 * * 7").unwrap();
+        assert_eq!(
+            cron.next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 3, 3, 0, 0))
+        );
+
+        // 日与周满足其一即可
+        let cron = CronSchedule::parse("0 0 15 * 0").unwrap();
+        assert_eq!(
+            cron.next_after(at(2024, 3, 4, 0, 0)),
+            Some(at(2024, 3, 10, 0, 0))
+        );
+
+        let cron = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(cron.next_after(at(2024, 3, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 5-3 * * *").is_err());
+        assert!(CronSchedule::parse("a * * * *").is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use ammonia::Builder;
+
+/// 白名单策略：允许的标签、属性与链接协议，其余一律移除
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizePolicy {
+    /// 允许的标签
+    pub tags: HashSet<String>,
+
+    /// 各标签允许的属性
+    pub tag_attributes: HashMap<String, HashSet<String>>,
+
+    /// 所有标签都允许的属性
+    pub generic_attributes: HashSet<String>,
+
+    /// 链接与图片地址允许的协议，相对地址始终允许
+    pub url_schemes: HashSet<String>,
+}
+
+/// 内容连同标签一起移除的标签，不能出现在白名单中
+const CLEAN_CONTENT_TAGS: [&str; 2] = ["script", "style"];
+
+/// 代码块标注语言的class前缀，其余class一律移除
+const CODE_LANGUAGE_CLASS_PREFIX: &str = "language-";
+
+/// 只保留`class`中形如`language-xxx`的代码语言标注
+fn filter_attribute<'u>(_element: &str, attribute: &str, value: &'u str) -> Option<Cow<'u, str>> {
+    if attribute != "class" {
+        return Some(Cow::Borrowed(value));
+    }
+    let classes: Vec<_> = value
+        .split_ascii_whitespace()
+        .filter(|class| {
+            class
+                .strip_prefix(CODE_LANGUAGE_CLASS_PREFIX)
+                .is_some_and(|language| {
+                    !language.is_empty()
+                        && language.chars().all(|c| {
+                            c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '#')
+                        })
+                })
+        })
+        .collect();
+    match classes.is_empty() {
+        true => None,
+        false => Some(Cow::Owned(classes.join(" "))),
+    }
+}
+
+fn set(items: &[&str]) -> HashSet<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        Self {
+            tags: set(&[
+                "a",
+                "b",
+                "blockquote",
+                "br",
+                "code",
+                "del",
+                "div",
+                "em",
+                "h1",
+                "h2",
+                "h3",
+                "h4",
+                "h5",
+                "h6",
+                "hr",
+                "i",
+                "img",
+                "li",
+                "ol",
+                "p",
+                "pre",
+                "s",
+                "span",
+                "strong",
+                "sub",
+                "sup",
+                "table",
+                "tbody",
+                "td",
+                "th",
+                "thead",
+                "tr",
+                "u",
+                "ul",
+            ]),
+            tag_attributes: HashMap::from([
+                ("a".to_string(), set(&["href", "target"])),
+                ("code".to_string(), set(&["class"])),
+                ("img".to_string(), set(&["src", "alt", "width", "height"])),
+                ("td".to_string(), set(&["colspan", "rowspan"])),
+                ("th".to_string(), set(&["colspan", "rowspan"])),
+            ]),
+            generic_attributes: set(&["title"]),
+            url_schemes: set(&["http", "https", "mailto"]),
+        }
+    }
+}
+
+/// 按白名单清理用户提交的HTML，移除脚本、事件处理属性及不允许的链接协议
+pub struct HtmlSanitizer {
+    policy: SanitizePolicy,
+}
+
+impl Default for HtmlSanitizer {
+    fn default() -> Self {
+        Self::new(SanitizePolicy::default())
+    }
+}
+
+impl HtmlSanitizer {
+    pub fn new(mut policy: SanitizePolicy) -> Self {
+        // 这些标签的内容会被整体移除；链接的rel属性由清理器统一设置
+        for tag in CLEAN_CONTENT_TAGS {
+            policy.tags.remove(tag);
+        }
+        if let Some(attributes) = policy.tag_attributes.get_mut("a") {
+            attributes.remove("rel");
+        }
+        policy.generic_attributes.remove("rel");
+        Self { policy }
+    }
+
+    pub fn policy(&self) -> &SanitizePolicy {
+        &self.policy
+    }
+
+    pub fn sanitize(&self, html: &str) -> String {
+        let policy = &self.policy;
+        let tag_attributes = policy
+            .tag_attributes
+            .iter()
+            .map(|(tag, attributes)| {
+                (
+                    tag.as_str(),
+                    attributes.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+
+        Builder::default()
+            .tags(policy.tags.iter().map(String::as_str).collect())
+            .tag_attributes(tag_attributes)
+            .generic_attributes(
+                policy
+                    .generic_attributes
+                    .iter()
+                    .map(String::as_str)
+                    .collect(),
+            )
+            .url_schemes(policy.url_schemes.iter().map(String::as_str).collect())
+            .clean_content_tags(CLEAN_CONTENT_TAGS.into_iter().collect())
+            .link_rel(Some("noopener noreferrer"))
+            .attribute_filter(filter_attribute)
+            .clean(html)
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sanitize(html: &str) -> String {
+        HtmlSanitizer::default().sanitize(html)
+    }
+
+    #[test]
+    fn test_keeps_allowed_markup() {
+        let html = r#"<p>数组<strong>越界</strong>了</p><ul><li>第一步</li></ul>"#;
+        assert_eq!(sanitize(html), html);
+        assert_eq!(
+            sanitize(r#"<img src="https://example.com/a.png" alt="截图">"#),
+            r#"<img src="https://example.com/a.png" alt="截图">"#
+        );
+    }
+
+    #[test]
+    fn test_script_and_style_removed_with_content() {
+        assert_eq!(sanitize("<p>a</p><script>alert(1)</script>"), "<p>a</p>");
+        assert_eq!(sanitize("<style>body{display:none}</style>b"), "b");
+        assert_eq!(sanitize("<SCRIPT SRC=//evil.com/x.js></SCRIPT>c"), "c");
+    }
+
+    #[test]
+    fn test_event_handlers_removed() {
+        assert_eq!(
+            sanitize(r#"<img src="x.png" onerror="alert(1)">"#),
+            r#"<img src="x.png">"#
+        );
+        assert_eq!(
+            sanitize(r#"<p onclick="alert(1)" onmouseover=alert(1)>x</p>"#),
+            "<p>x</p>"
+        );
+        assert_eq!(sanitize(r#"<svg onload="alert(1)"><g></g></svg>"#), "");
+    }
+
+    #[test]
+    fn test_javascript_urls_removed() {
+        for href in [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            " javascript:alert(1)",
+            "java&#x09;script:alert(1)",
+            "&#106;avascript:alert(1)",
+            "vbscript:msgbox(1)",
+            "data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==",
+        ] {
+            let html = format!(r#"<a href="{}">x</a>"#, href);
+            assert_eq!(
+                sanitize(&html),
+                r#"<a rel="noopener noreferrer">x</a>"#,
+                "{}",
+                href
+            );
+        }
+        assert_eq!(sanitize(r#"<img src="javascript:alert(1)">"#), "<img>");
+    }
+
+    #[test]
+    fn test_disallowed_tags_removed() {
+        assert_eq!(
+            sanitize(r#"<iframe src="https://evil.com"></iframe>x"#),
+            "x"
+        );
+        assert_eq!(
+            sanitize(r#"<form action="https://evil.com"><input name="p"></form>"#),
+            ""
+        );
+        assert_eq!(
+            sanitize(r#"<object data="x.swf"></object><embed src="x.swf">"#),
+            ""
+        );
+        assert_eq!(
+            sanitize(r#"<meta http-equiv="refresh" content="0;url=https://evil.com">"#),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_malformed_markup() {
+        assert_eq!(sanitize("<<script>script>alert(1)</script>"), "&lt;");
+        assert_eq!(sanitize(r#"<img src=x onerror=alert(1)//"#), "");
+        assert_eq!(
+            sanitize(r#"<a href="https://example.com" rel="opener" target="_blank">x</a>"#),
+            r#"<a href="https://example.com" target="_blank" rel="noopener noreferrer">x</a>"#
+        );
+    }
+
+    #[test]
+    fn test_code_language_class() {
+        assert_eq!(
+            sanitize(r#"<pre><code class="language-rust evil">fn main() {}</code></pre>"#),
+            r#"<pre><code class="language-rust">fn main() {}</code></pre>"#
+        );
+        assert_eq!(
+            sanitize(r#"<code class="x language-&quot;onload">y</code>"#),
+            "<code>y</code>"
+        );
+        assert_eq!(sanitize(r#"<p class="language-rust">z</p>"#), "<p>z</p>");
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let sanitizer = HtmlSanitizer::new(SanitizePolicy {
+            tags: set(&["b", "script"]),
+            tag_attributes: HashMap::new(),
+            generic_attributes: HashSet::new(),
+            url_schemes: HashSet::new(),
+        });
+        assert!(!sanitizer.policy().tags.contains("script"));
+        assert_eq!(
+            sanitizer.sanitize("<p><b>x</b><script>y</script></p>"),
+            "<b>x</b>"
+        );
+    }
+}
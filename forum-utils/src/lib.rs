@@ -1,2 +1,7 @@
+pub mod cron_schedule;
 pub mod encoding_helper;
 pub mod html_cleaner;
+pub mod html_sanitizer;
+pub mod markdown_renderer;
+pub mod mention_parser;
+pub mod text_diff;
@@ -0,0 +1,58 @@
+use pulldown_cmark::{html, Options, Parser};
+
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+    /// 将Markdown渲染为HTML，支持表格与删除线
+    ///
+    /// 不启用任务列表：复选框`<input>`不在清理白名单中，`- [ ]`按普通列表项原样显示；
+    /// 带语言的代码块渲染为`<pre><code class="language-xxx">`；
+    /// Markdown中的原始HTML会原样输出，渲染结果必须经过`HtmlSanitizer`清理后才能保存
+    pub fn render(markdown: &str) -> String {
+        let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+        let parser = Parser::new_ext(markdown, options);
+
+        let mut output = String::with_capacity(markdown.len() * 3 / 2);
+        html::push_html(&mut output, parser);
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::html_sanitizer::HtmlSanitizer;
+
+    #[test]
+    fn test_fenced_code_keeps_language_and_indentation() {
+        let markdown = "```cpp\nint main() {\n    return 0;\n}\n```\n";
+        assert_eq!(
+            MarkdownRenderer::render(markdown),
+            "<pre><code class=\"language-cpp\">int main() {\n    return 0;\n}\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_markup() {
+        assert_eq!(
+            MarkdownRenderer::render("**数组**越界，见`a[10]`和~~旧代码~~"),
+            "<p><strong>数组</strong>越界，见<code>a[10]</code>和<del>旧代码</del></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_code_is_escaped() {
+        assert_eq!(
+            MarkdownRenderer::render("```\n<script>alert(1)</script>\n```"),
+            "<pre><code>&lt;script&gt;alert(1)&lt;/script&gt;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_task_list_survives_sanitizing() {
+        let html = MarkdownRenderer::render("- [x] 提交作业\n- [ ] 订正错题\n");
+        let expected = "<ul>\n<li>[x] 提交作业</li>\n<li>[ ] 订正错题</li>\n</ul>\n";
+        assert_eq!(html, expected);
+        assert_eq!(HtmlSanitizer::default().sanitize(&html), expected);
+    }
+}
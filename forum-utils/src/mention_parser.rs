@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+pub struct MentionParser;
+
+/// 提及名称的最大长度（字符数）
+const MAX_MENTION_CHARS: usize = 32;
+
+impl MentionParser {
+    /// 从纯文本中解析`@学号`或`@昵称`形式的提及，按出现顺序去重
+    ///
+    /// 名称由字母、数字、汉字、`_`、`-`和`.`组成，以空白或其他标点结束，末尾的`.`不计入名称；
+    /// `@`紧跟在英文字母或数字之后（如邮箱地址）时不视为提及
+    pub fn parse(text: &str) -> Vec<String> {
+        let mut mentions = vec![];
+        let mut seen = HashSet::new();
+
+        let mut prev: Option<char> = None;
+        let mut chars = text.char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            let is_mention = c == '@' && !prev.is_some_and(|p| p.is_ascii_alphanumeric());
+            prev = Some(c);
+            if !is_mention {
+                continue;
+            }
+
+            let start = offset + c.len_utf8();
+            let mut end = start;
+            while let Some(&(next_offset, next)) = chars.peek() {
+                if !Self::is_name_char(next) {
+                    break;
+                }
+                end = next_offset + next.len_utf8();
+                prev = Some(next);
+                chars.next();
+            }
+
+            let name = text[start..end].trim_end_matches('.');
+            if !name.is_empty() && name.chars().count() <= MAX_MENTION_CHARS && seen.insert(name) {
+                mentions.push(name.to_string());
+            }
+        }
+        mentions
+    }
+
+    fn is_name_char(c: char) -> bool {
+        c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_student_no_and_nickname() {
+        assert_eq!(
+            MentionParser::parse("@2151234 请看一下，@助教小王 谢谢"),
+            vec!["2151234", "助教小王"]
+        );
+    }
+
+    #[test]
+    fn test_punctuation_ends_mention() {
+        assert_eq!(
+            MentionParser::parse("问一下@alice_1，还有@bob. 以及@carol-x!"),
+            vec!["alice_1", "bob", "carol-x"]
+        );
+    }
+
+    #[test]
+    fn test_email_is_not_mention() {
+        assert!(MentionParser::parse("邮箱是test@tongji.edu.cn").is_empty());
+        assert_eq!(MentionParser::parse("(@alice)"), vec!["alice"]);
+    }
+
+    #[test]
+    fn test_dedup_and_empty() {
+        assert_eq!(MentionParser::parse("@a @a @b"), vec!["a", "b"]);
+        assert!(MentionParser::parse("@ @@ 末尾@").is_empty());
+    }
+
+    #[test]
+    fn test_too_long() {
+        let long = "x".repeat(MAX_MENTION_CHARS + 1);
+        assert!(MentionParser::parse(&format!("@{}", long)).is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+
+pub struct TextDiffer;
+
+/// 片段的变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// 比较结果中的一个连续片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSegment {
+    pub kind: ChangeKind,
+    pub text: String,
+}
+
+impl TextDiffer {
+    /// 比较两段纯文本，英文和数字按单词比较，其余字符（包括中文）逐字比较
+    ///
+    /// 返回的片段按原文顺序排列，依次拼接`Equal`与`Delete`片段得到旧文本，
+    /// 依次拼接`Equal`与`Insert`片段得到新文本
+    pub fn diff(old: &str, new: &str) -> Vec<DiffSegment> {
+        let old_tokens = Self::tokenize(old);
+        let new_tokens = Self::tokenize(new);
+
+        let mut segments: Vec<DiffSegment> = vec![];
+        let mut push = |kind: ChangeKind, tokens: &[&str]| {
+            if tokens.is_empty() {
+                return;
+            }
+            match segments.last_mut() {
+                Some(last) if last.kind == kind => last.text.push_str(&tokens.concat()),
+                _ => segments.push(DiffSegment {
+                    kind,
+                    text: tokens.concat(),
+                }),
+            }
+        };
+
+        for op in capture_diff_slices(Algorithm::Myers, &old_tokens, &new_tokens) {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => push(ChangeKind::Equal, &old_tokens[old_range]),
+                DiffTag::Delete => push(ChangeKind::Delete, &old_tokens[old_range]),
+                DiffTag::Insert => push(ChangeKind::Insert, &new_tokens[new_range]),
+                DiffTag::Replace => {
+                    push(ChangeKind::Delete, &old_tokens[old_range]);
+                    push(ChangeKind::Insert, &new_tokens[new_range]);
+                }
+            }
+        }
+
+        segments
+    }
+
+    fn tokenize(text: &str) -> Vec<&str> {
+        let mut tokens = vec![];
+        let mut word_start = None;
+        for (offset, c) in text.char_indices() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                word_start.get_or_insert(offset);
+                continue;
+            }
+            if let Some(start) = word_start.take() {
+                tokens.push(&text[start..offset]);
+            }
+            tokens.push(&text[offset..offset + c.len_utf8()]);
+        }
+        if let Some(start) = word_start {
+            tokens.push(&text[start..]);
+        }
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rebuild(segments: &[DiffSegment], skip: ChangeKind) -> String {
+        segments
+            .iter()
+            .filter(|s| s.kind != skip)
+            .map(|s| s.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_identical() {
+        let segments = TextDiffer::diff("数组越界", "数组越界");
+        assert_eq!(
+            segments,
+            vec![DiffSegment {
+                kind: ChangeKind::Equal,
+                text: "数组越界".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(TextDiffer::diff("", "").is_empty());
+        assert_eq!(
+            TextDiffer::diff("", "新增"),
+            vec![DiffSegment {
+                kind: ChangeKind::Insert,
+                text: "新增".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_chinese_by_char() {
+        let segments = TextDiffer::diff("我的程序报错了", "我的代码报错了");
+        assert_eq!(
+            segments,
+            vec![
+                DiffSegment {
+                    kind: ChangeKind::Equal,
+                    text: "我的".into()
+                },
+                DiffSegment {
+                    kind: ChangeKind::Delete,
+                    text: "程序".into()
+                },
+                DiffSegment {
+                    kind: ChangeKind::Insert,
+                    text: "代码".into()
+                },
+                DiffSegment {
+                    kind: ChangeKind::Equal,
+                    text: "报错了".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_english_by_word() {
+        let segments = TextDiffer::diff(
+            "segmentation fault at line 3",
+            "segmentation fault at line 42",
+        );
+        assert_eq!(
+            segments.last(),
+            Some(&DiffSegment {
+                kind: ChangeKind::Insert,
+                text: "42".into()
+            })
+        );
+        assert!(segments.contains(&DiffSegment {
+            kind: ChangeKind::Delete,
+            text: "3".into()
+        }));
+    }
+
+    #[test]
+    fn test_rebuild_both_sides() {
+        let old = "如何提交作业？hw3的deadline是几号";
+        let new = "请问hw4的deadline是几号？谢谢";
+        let segments = TextDiffer::diff(old, new);
+        assert_eq!(rebuild(&segments, ChangeKind::Insert), old);
+        assert_eq!(rebuild(&segments, ChangeKind::Delete), new);
+    }
+}
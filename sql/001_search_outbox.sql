@@ -0,0 +1,13 @@
+-- 搜索引擎同步队列
+create table if not exists search_outbox
+(
+    outbox_id              bigint unsigned auto_increment primary key,
+    outbox_post_id         int           not null,
+    outbox_attempts        int default 0 not null,
+    outbox_next_attempt_at datetime      not null,
+    outbox_last_error      text          null,
+    outbox_created_at      datetime      not null,
+    outbox_locked_by       varchar(64)   null,
+    outbox_locked_until    datetime      null,
+    index idx_search_outbox_next_attempt (outbox_attempts, outbox_next_attempt_at)
+);
@@ -0,0 +1,5 @@
+-- 帖子软删除：记录删除时间与删除批次，用于恢复同一次删除的帖子及超期彻底删除
+alter table post
+    add column post_del_date datetime null after post_is_del,
+    add column post_del_batch varchar(48) null after post_del_date,
+    add index idx_post_del_date (post_is_del, post_del_date);
@@ -0,0 +1,13 @@
+-- 帖子修改历史：每次编辑前保存原标题与内容
+create table if not exists post_revision
+(
+    rev_id      int auto_increment primary key,
+    rev_post_id int          not null,
+    rev_title   varchar(255) null,
+    rev_content longtext     null,
+    rev_opno    varchar(20)  not null,
+    rev_ipaddr  varchar(64)  not null,
+    rev_date    datetime     not null,
+    rev_comment varchar(255) not null,
+    index idx_post_revision_post_id (rev_post_id, rev_id)
+);
@@ -0,0 +1,14 @@
+-- 主题帖状态与采纳的回帖
+alter table post
+    add column post_status      varchar(16) null after post_del_date,
+    add column post_accepted_id int         null after post_status,
+    add index idx_post_status (post_term, post_ccode, post_status);
+
+-- 已有的主题帖：有他人回帖的视为已回答，否则为待回答
+update post p
+set p.post_status = if(exists(select 1
+                              from (select post_answer_id, post_sno, post_is_del from post) r
+                              where r.post_answer_id = p.post_id
+                                and r.post_sno <> p.post_sno
+                                and r.post_is_del = '0'), 'ANSWERED', 'OPEN')
+where p.post_answer_id is null;
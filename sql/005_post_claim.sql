@@ -0,0 +1,7 @@
+-- 助教认领待回答的提问，避免多人同时回答同一帖子
+create table if not exists post_claim
+(
+    claim_post_id int         not null primary key,
+    claim_sno     varchar(20) not null,
+    claim_date    datetime    not null
+);
@@ -0,0 +1,24 @@
+-- 主题帖订阅：关注的帖子有任何回帖时通知，屏蔽的帖子不再通知
+create table if not exists post_subscription
+(
+    sub_post_id int         not null,
+    sub_sno     varchar(20) not null,
+    sub_mode    varchar(8)  not null,
+    sub_date    datetime    not null,
+    primary key (sub_post_id, sub_sno),
+    index idx_post_subscription_sno (sub_sno, sub_date)
+);
+
+-- 已有帖子的参与者自动关注所在的主题帖
+insert ignore into post_subscription (sub_post_id, sub_sno, sub_mode, sub_date)
+with recursive thread as (select post_id as root_id, post_id, post_sno, post_date
+                          from post
+                          where post_answer_id is null
+                          union all
+                          select thread.root_id, post.post_id, post.post_sno, post.post_date
+                          from post
+                                   join thread on post.post_answer_id = thread.post_id)
+select root_id, post_sno, 'WATCH', min(post_date)
+from thread
+where post_sno is not null
+group by root_id, post_sno;
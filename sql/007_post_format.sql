@@ -0,0 +1,8 @@
+-- 帖子内容格式：Markdown帖子保存原文，post_content为渲染并清理后的HTML
+alter table post
+    add column post_format varchar(16) null after post_content,
+    add column post_source longtext    null after post_format;
+
+alter table post_revision
+    add column rev_format varchar(16) null after rev_content,
+    add column rev_source longtext    null after rev_format;
@@ -0,0 +1,28 @@
+-- 主题帖的回帖数与最后活跃时间，用于板块列表排序与游标分页
+alter table post
+    add column post_reply_count int      not null default 0 after post_accepted_id,
+    add column post_active_date datetime null after post_reply_count,
+    add index idx_post_active (post_term, post_ccode, post_active_date, post_id),
+    add index idx_post_replies (post_term, post_ccode, post_reply_count, post_id),
+    add index idx_post_priority (post_term, post_ccode, post_priority, post_date, post_id);
+
+update post
+set post_active_date = post_date;
+
+-- 已有主题帖：统计整棵回帖树中未删除的回帖
+update post p
+    join (with recursive thread as (select post_id as root_id, post_id
+                                    from post
+                                    where post_answer_id is null
+                                    union all
+                                    select thread.root_id, post.post_id
+                                    from post
+                                             join thread on post.post_answer_id = thread.post_id)
+          select thread.root_id,
+                 sum(reply.post_id <> thread.root_id and reply.post_is_del = '0') as reply_count,
+                 max(if(reply.post_is_del = '0', reply.post_date, null))           as active_date
+          from thread
+                   join post reply on reply.post_id = thread.post_id
+          group by thread.root_id) stats on stats.root_id = p.post_id
+set p.post_reply_count = stats.reply_count,
+    p.post_active_date = coalesce(stats.active_date, p.post_date);
@@ -0,0 +1,28 @@
+-- 主题帖中最新的未删除帖子id，用于判断是否有未读回帖
+alter table post
+    add column post_last_id int null after post_active_date;
+
+update post p
+    join (with recursive thread as (select post_id as root_id, post_id
+                                    from post
+                                    where post_answer_id is null
+                                    union all
+                                    select thread.root_id, post.post_id
+                                    from post
+                                             join thread on post.post_answer_id = thread.post_id)
+          select thread.root_id,
+                 max(if(reply.post_is_del = '0', reply.post_id, null)) as last_id
+          from thread
+                   join post reply on reply.post_id = thread.post_id
+          group by thread.root_id) stats on stats.root_id = p.post_id
+set p.post_last_id = coalesce(stats.last_id, p.post_id);
+
+-- 用户阅读记录：Redis中的阅读进度定期写入此表
+create table if not exists post_read
+(
+    read_sno     varchar(20) not null,
+    read_post_id int         not null,
+    read_last_id int         not null,
+    read_date    datetime    not null,
+    primary key (read_sno, read_post_id)
+);
@@ -0,0 +1,4 @@
+-- 匿名发帖：发帖人学号照常保存，对其他学生只显示主题帖内固定的匿名称呼
+alter table post
+    add column post_anonymous char(1)     not null default '0' after post_sno,
+    add column post_alias     varchar(32) null after post_anonymous;
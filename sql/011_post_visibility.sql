@@ -0,0 +1,5 @@
+-- 私密提问：主题帖及其所有回帖只对提问者与助教可见
+alter table post
+    add column post_visibility  varchar(16) not null default 'PUBLIC' after post_status,
+    add column post_private_sno varchar(32) null after post_visibility,
+    add index idx_post_private (post_private_sno);
@@ -0,0 +1,8 @@
+-- 通知指向的帖子、主题帖、板块与触发通知的用户
+alter table notification
+    add column ntf_post_id  int         null after ntf_content,
+    add column ntf_root_id  int         null after ntf_post_id,
+    add column ntf_board_id varchar(64) null after ntf_root_id,
+    add column ntf_actor    varchar(20) null after ntf_board_id,
+    add index idx_ntf_receiver_read (ntf_receiver, ntf_read),
+    add index idx_ntf_receiver_root (ntf_receiver, ntf_root_id);
@@ -0,0 +1,27 @@
+-- 用户对各类通知的接收方式，没有记录的类型即时接收
+create table if not exists notification_preference
+(
+    pref_sno  varchar(20) not null,
+    pref_type varchar(16) not null,
+    pref_mode varchar(16) not null,
+    pref_date datetime    not null,
+    primary key (pref_sno, pref_type)
+);
+
+-- 等待合并为摘要发送的通知
+create table if not exists notification_pending
+(
+    pend_id       bigint unsigned not null auto_increment,
+    pend_receiver varchar(20)     not null,
+    pend_type     varchar(16)     not null,
+    pend_title    varchar(255)    not null,
+    pend_content  text            not null,
+    pend_post_id  int             null,
+    pend_root_id  int             null,
+    pend_board_id varchar(64)     null,
+    pend_actor    varchar(20)     null,
+    pend_datetime datetime        not null,
+    pend_due      datetime        not null,
+    primary key (pend_id),
+    index idx_pending_due (pend_due, pend_receiver)
+);
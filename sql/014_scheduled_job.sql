@@ -0,0 +1,11 @@
+-- 定时任务的运行记录，任务本身在配置文件中声明
+create table if not exists scheduled_job
+(
+    job_name        varchar(64) not null,
+    job_status      varchar(16) not null,
+    job_last_start  datetime    null,
+    job_last_finish datetime    null,
+    job_next_run    datetime    null,
+    job_last_error  text        null,
+    primary key (job_name)
+);
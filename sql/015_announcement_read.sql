@@ -0,0 +1,8 @@
+-- 课程公告的阅读回执：用户第一次打开公告的时间
+create table if not exists announcement_read
+(
+    ar_post_id int         not null,
+    ar_sno     varchar(20) not null,
+    ar_date    datetime    not null,
+    primary key (ar_post_id, ar_sno)
+);
@@ -0,0 +1,14 @@
+-- 搜索索引重建的时间窗口，重建期间任一实例同步过的帖子在替换索引后需要重新同步
+create table if not exists search_rebuild
+(
+    rebuild_id          bigint unsigned auto_increment primary key,
+    rebuild_started_at  datetime not null,
+    rebuild_finished_at datetime null
+);
+
+create table if not exists search_rebuild_dirty
+(
+    dirty_rebuild_id bigint unsigned not null,
+    dirty_post_id    int             not null,
+    primary key (dirty_rebuild_id, dirty_post_id)
+);
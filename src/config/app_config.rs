@@ -3,8 +3,11 @@
 use std::sync::{Arc, RwLock};
 
 use crate::config::database::DatabaseConfig;
+use crate::config::notification::NotificationConfig;
 use crate::config::permission::PermissionConfig;
+use crate::config::post::PostConfig;
 use crate::config::redis::RedisAppConfig;
+use crate::config::scheduler::SchedulerConfig;
 use config::Config;
 use lazy_static::lazy_static;
 use log::{error, info};
@@ -14,6 +17,8 @@ use crate::panic;
 
 use super::meili::MeiliSearchConfig;
 use super::s3::S3Config;
+use super::sanitize::SanitizeConfig;
+use super::search::SearchConfig;
 
 #[derive(Default, Debug, Clone, Deserialize, Eq, PartialEq)]
 pub struct AppConfig {
@@ -22,7 +27,18 @@ pub struct AppConfig {
     pub redis: RedisAppConfig,
     pub permission: PermissionConfig,
     pub s3: S3Config,
+    #[serde(default)]
     pub meili: MeiliSearchConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub post: PostConfig,
+    #[serde(default)]
+    pub sanitize: SanitizeConfig,
+    #[serde(default)]
+    pub notification: NotificationConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
 }
 
 pub type AppConf = Arc<RwLock<AppConfig>>;
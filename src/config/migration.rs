@@ -0,0 +1,185 @@
+//! 数据库迁移
+//!
+//! `sql`目录下的脚本在编译时嵌入，启动时按编号顺序执行尚未执行过的脚本，
+//! 并在`schema_migration`表中记录，已执行的脚本不会再次执行。
+//! 脚本只包含在原有表结构之上的修改，原有的表需要事先建好。
+
+use chrono::Local;
+use log::info;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, Statement, TransactionTrait};
+
+use crate::config::database::{DatabaseTrait, Db};
+
+/// 多个实例同时启动时只有一个执行迁移
+const MIGRATION_LOCK: &str = "forum_schema_migration";
+
+/// 等待迁移锁的超时时间（秒）
+const MIGRATION_LOCK_TIMEOUT_SECS: i32 = 300;
+
+/// 迁移脚本的版本号与内容
+macro_rules! migration {
+    ($version:literal) => {
+        (
+            $version,
+            include_str!(concat!("../../sql/", $version, ".sql")),
+        )
+    };
+}
+
+/// 迁移脚本，按编号顺序执行，已发布的脚本不能修改，修改表结构需要添加新的脚本
+const MIGRATIONS: &[(&str, &str)] = &[
+    migration!("001_search_outbox"),
+    migration!("002_post_soft_delete"),
+    migration!("003_post_revision"),
+    migration!("004_post_status"),
+    migration!("005_post_claim"),
+    migration!("006_post_subscription"),
+    migration!("007_post_format"),
+    migration!("008_post_thread_stats"),
+    migration!("009_post_read"),
+    migration!("010_post_anonymous"),
+    migration!("011_post_visibility"),
+    migration!("012_notification_target"),
+    migration!("013_notification_preference"),
+    migration!("014_scheduled_job"),
+    migration!("015_announcement_read"),
+    migration!("016_search_rebuild"),
+];
+
+/// 为帖子新增了搜索过滤字段的迁移，执行后已有的索引文档缺少该字段，需要全部重新同步
+const SEARCH_BACKFILL_MIGRATIONS: &[&str] = &["010_post_anonymous", "011_post_visibility"];
+
+/// 本次执行的迁移是否需要将所有帖子重新加入搜索同步队列
+fn needs_search_backfill(executed: &[&str]) -> bool {
+    executed
+        .iter()
+        .any(|version| SEARCH_BACKFILL_MIGRATIONS.contains(version))
+}
+
+/// 将脚本拆分为单条语句，去掉注释行
+fn statements(script: &str) -> impl Iterator<Item = String> + '_ {
+    script
+        .split(';')
+        .map(|statement| {
+            statement
+                .lines()
+                .filter(|line| !line.trim_start().starts_with("--"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .filter(|statement| !statement.trim().is_empty())
+}
+
+/// 执行尚未执行过的迁移脚本，返回本次执行的脚本
+pub async fn run(db_conn: &Db) -> Result<Vec<&'static str>, DbErr> {
+    // MySQL的锁与连接绑定，在事务中执行以使用同一个连接；DDL语句会隐式提交，事务不保证原子性
+    let txn = db_conn.get_db().begin().await?;
+    txn.execute_unprepared(
+        "create table if not exists schema_migration
+         (
+             migration_version    varchar(64) not null primary key,
+             migration_applied_at datetime    not null
+         )",
+    )
+    .await?;
+
+    let locked = txn
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            "select get_lock(?, ?) as locked",
+            [MIGRATION_LOCK.into(), MIGRATION_LOCK_TIMEOUT_SECS.into()],
+        ))
+        .await?
+        .and_then(|row| row.try_get::<Option<i64>>("", "locked").ok().flatten());
+    if locked != Some(1) {
+        return Err(DbErr::Custom("等待数据库迁移锁超时".to_string()));
+    }
+
+    let result = apply_pending(&txn).await;
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::MySql,
+        "select release_lock(?)",
+        [MIGRATION_LOCK.into()],
+    ))
+    .await?;
+    txn.commit().await?;
+
+    result
+}
+
+async fn apply_pending<C: ConnectionTrait>(conn: &C) -> Result<Vec<&'static str>, DbErr> {
+    let applied: Vec<String> = conn
+        .query_all(Statement::from_string(
+            DbBackend::MySql,
+            "select migration_version from schema_migration",
+        ))
+        .await?
+        .iter()
+        .filter_map(|row| row.try_get("", "migration_version").ok())
+        .collect();
+
+    let mut executed = vec![];
+    for &(version, script) in MIGRATIONS {
+        if applied.iter().any(|v| v == version) {
+            continue;
+        }
+
+        info!("执行数据库迁移：{}", version);
+        for statement in statements(script) {
+            conn.execute_unprepared(&statement).await.map_err(|e| {
+                DbErr::Custom(format!("数据库迁移{}失败：{}\n{}", version, e, statement))
+            })?;
+        }
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            "insert into schema_migration (migration_version, migration_applied_at) values (?, ?)",
+            [version.into(), Local::now().naive_local().into()],
+        ))
+        .await?;
+        executed.push(version);
+    }
+
+    if needs_search_backfill(&executed) {
+        let now = Local::now().naive_local();
+        let result = conn
+            .execute(Statement::from_sql_and_values(
+                DbBackend::MySql,
+                r#"
+            insert into search_outbox (outbox_post_id, outbox_attempts, outbox_next_attempt_at, outbox_created_at)
+            select post_id, 0, ?, ?
+            from post
+            "#,
+                [now.into(), now.into()],
+            ))
+            .await?;
+        info!(
+            "搜索索引新增了过滤字段，已将{}个帖子加入同步队列",
+            result.rows_affected()
+        );
+    }
+
+    Ok(executed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backfill_migrations_exist() {
+        for version in SEARCH_BACKFILL_MIGRATIONS {
+            assert!(MIGRATIONS.iter().any(|(v, _)| v == version), "{}", version);
+        }
+    }
+
+    #[test]
+    fn test_needs_search_backfill() {
+        assert!(!needs_search_backfill(&[]));
+        assert!(!needs_search_backfill(&["009_post_read"]));
+        assert!(needs_search_backfill(&[
+            "009_post_read",
+            "010_post_anonymous"
+        ]));
+        assert!(needs_search_backfill(&["011_post_visibility"]));
+    }
+}
@@ -1,9 +1,15 @@
 mod app_config;
 pub mod database;
 pub mod meili;
+pub mod migration;
+pub mod notification;
 pub mod permission;
+pub mod post;
 pub mod redis;
 pub mod s3;
+pub mod sanitize;
+pub mod scheduler;
+pub mod search;
 pub mod session;
 
 pub use crate::config::app_config::{AppConfig, APP_CONFIG};
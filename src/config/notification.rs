@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// 每日摘要的发送时间（0-23点）
+    pub digest_hour: u32,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self { digest_hour: 8 }
+    }
+}
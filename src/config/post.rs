@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct PostConfig {
+    /// 已删除帖子的保留天数，超过后可被彻底删除
+    pub deleted_retention_days: i64,
+
+    /// 板块帖子数量的缓存秒数
+    pub count_cache_secs: u64,
+
+    /// 阅读记录从Redis写入数据库的间隔秒数
+    pub read_flush_secs: u64,
+
+    /// 阅读记录在Redis中的保留天数
+    pub read_cache_days: i64,
+
+    /// 允许匿名发帖的课程或板块id，课程id为`学期_课程代码`，作用于其下所有板块
+    pub anonymous_boards: Vec<String>,
+}
+
+impl Default for PostConfig {
+    fn default() -> Self {
+        Self {
+            deleted_retention_days: 30,
+            count_cache_secs: 30,
+            read_flush_secs: 60,
+            read_cache_days: 7,
+            anonymous_boards: vec![],
+        }
+    }
+}
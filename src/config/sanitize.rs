@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use forum_utils::html_sanitizer::SanitizePolicy;
+use serde::Deserialize;
+
+/// 帖子内容的HTML白名单，未配置的项使用默认白名单
+#[derive(Default, Debug, Clone, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct SanitizeConfig {
+    /// 允许的标签
+    pub tags: Option<Vec<String>>,
+
+    /// 各标签允许的属性
+    pub tag_attributes: Option<HashMap<String, Vec<String>>>,
+
+    /// 所有标签都允许的属性
+    pub generic_attributes: Option<Vec<String>>,
+
+    /// 链接与图片地址允许的协议
+    pub url_schemes: Option<Vec<String>>,
+}
+
+impl SanitizeConfig {
+    pub fn to_policy(&self) -> SanitizePolicy {
+        let default = SanitizePolicy::default();
+        SanitizePolicy {
+            tags: self
+                .tags
+                .as_ref()
+                .map_or(default.tags, |tags| tags.iter().cloned().collect()),
+            tag_attributes: self
+                .tag_attributes
+                .as_ref()
+                .map_or(default.tag_attributes, |map| {
+                    map.iter()
+                        .map(|(tag, attributes)| {
+                            (tag.clone(), attributes.iter().cloned().collect())
+                        })
+                        .collect()
+                }),
+            generic_attributes: self
+                .generic_attributes
+                .as_ref()
+                .map_or(default.generic_attributes, |attributes| {
+                    attributes.iter().cloned().collect()
+                }),
+            url_schemes: self
+                .url_schemes
+                .as_ref()
+                .map_or(default.url_schemes, |schemes| {
+                    schemes.iter().cloned().collect()
+                }),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 定时任务的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JobKind {
+    /// 清理过期通知
+    NotificationRetention,
+    /// 清理过期的登录与发帖日志
+    LogRetention,
+    /// 比对并修复搜索索引
+    SearchReconcile,
+    /// 发送到期的通知摘要
+    NotificationDigest,
+}
+
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
+pub struct JobConfig {
+    /// 任务名称，唯一
+    pub name: String,
+
+    pub kind: JobKind,
+
+    /// cron表达式（分 时 日 月 周），与`interval_secs`二选一
+    #[serde(default)]
+    pub cron: Option<String>,
+
+    /// 运行间隔秒数
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+
+    /// 每次运行随机推迟的最大秒数，避免多个实例或任务同时运行
+    #[serde(default)]
+    pub jitter_secs: u64,
+
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// 保留天数：通知为所有通知，日志为登录与发帖日志
+    #[serde(default)]
+    pub retention_days: Option<u64>,
+
+    /// 已读通知的保留天数
+    #[serde(default)]
+    pub read_retention_days: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// 是否在本实例运行定时任务
+    pub enabled: bool,
+
+    /// 任务运行锁的过期秒数，应长于任务的最长运行时间
+    pub lock_secs: u64,
+
+    pub jobs: Vec<JobConfig>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lock_secs: 60 * 60,
+            jobs: vec![
+                JobConfig {
+                    name: "notification-retention".into(),
+                    kind: JobKind::NotificationRetention,
+                    cron: Some("@hourly".into()),
+                    interval_secs: None,
+                    jitter_secs: 60,
+                    enabled: true,
+                    retention_days: Some(7),
+                    read_retention_days: Some(1),
+                },
+                JobConfig {
+                    name: "log-retention".into(),
+                    kind: JobKind::LogRetention,
+                    cron: Some("30 3 * * *".into()),
+                    interval_secs: None,
+                    jitter_secs: 600,
+                    enabled: false,
+                    retention_days: Some(180),
+                    read_retention_days: None,
+                },
+                JobConfig {
+                    name: "notification-digest".into(),
+                    kind: JobKind::NotificationDigest,
+                    cron: Some("*/5 * * * *".into()),
+                    interval_secs: None,
+                    jitter_secs: 30,
+                    enabled: true,
+                    retention_days: None,
+                    read_retention_days: None,
+                },
+                JobConfig {
+                    name: "search-reconcile".into(),
+                    kind: JobKind::SearchReconcile,
+                    cron: Some("0 4 * * *".into()),
+                    interval_secs: None,
+                    jitter_secs: 600,
+                    enabled: true,
+                    retention_days: None,
+                    read_retention_days: None,
+                },
+            ],
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+/// 搜索后端类型
+#[derive(Default, Debug, Clone, Copy, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackendKind {
+    /// 外部的Meilisearch服务，使用`meili`配置
+    #[default]
+    Meili,
+    /// 进程内的磁盘全文索引，无需外部服务
+    Embedded,
+}
+
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct SearchConfig {
+    pub backend: SearchBackendKind,
+    /// 内嵌索引的存放目录
+    pub embedded_path: String,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            backend: SearchBackendKind::Meili,
+            embedded_path: String::from("data/search"),
+        }
+    }
+}
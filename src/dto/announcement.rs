@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 选修课程的学生及其阅读公告的时间
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementReader {
+    pub stu_no: String,
+
+    pub stu_name: Option<String>,
+
+    /// 第一次打开公告的时间，未读时为空
+    pub read_date: Option<NaiveDateTime>,
+}
+
+/// 课程公告的阅读回执
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementReceipt {
+    /// 公告的主题帖id
+    pub post_id: i32,
+
+    /// 选修该课程的学生数
+    pub enrolled_count: u64,
+
+    /// 已读的学生数
+    pub read_count: u64,
+
+    /// 已读的学生，按阅读时间排列
+    pub read: Vec<AnnouncementReader>,
+
+    /// 未读的学生，按学号排列
+    pub unread: Vec<AnnouncementReader>,
+}
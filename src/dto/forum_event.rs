@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::entity::{notification, post};
+
+/// 实时推送的事件
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ForumEvent {
+    /// 收到新通知，只推送给接收者
+    Notification { notification: notification::Model },
+
+    /// 板块中有新的主题帖（不含内容）
+    #[serde(rename_all = "camelCase")]
+    NewPost {
+        /// 主题帖所在的所有板块id，包括汇总板块
+        board_ids: Vec<String>,
+        post: post::Model,
+    },
+
+    /// 主题帖中有新的回帖（不含内容）
+    #[serde(rename_all = "camelCase")]
+    NewReply {
+        /// 主题帖所在的所有板块id，包括汇总板块
+        board_ids: Vec<String>,
+        root_id: i32,
+        post: post::Model,
+    },
+}
+
+impl ForumEvent {
+    /// SSE中的事件名
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForumEvent::Notification { .. } => "NOTIFICATION",
+            ForumEvent::NewPost { .. } => "NEW_POST",
+            ForumEvent::NewReply { .. } => "NEW_REPLY",
+        }
+    }
+}
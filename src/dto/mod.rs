@@ -1,3 +1,19 @@
+pub mod announcement;
 pub mod board;
 pub mod course_tree;
+pub mod forum_event;
+pub mod notification_page;
+pub mod notification_preference;
+pub mod post_anonymity;
+pub mod post_claim;
+pub mod post_format;
+pub mod post_list;
+pub mod post_read;
+pub mod post_revision;
+pub mod post_search;
+pub mod post_status;
+pub mod post_subscription;
+pub mod post_tree;
+pub mod post_visibility;
+pub mod scheduled_job;
 pub mod student_short_info;
@@ -0,0 +1,14 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::entity::notification;
+
+/// 一页通知，按时间从新到旧排列
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPage {
+    pub notifications: Vec<notification::Model>,
+
+    /// 下一页的游标，没有更多通知时为空
+    pub next_cursor: Option<u64>,
+}
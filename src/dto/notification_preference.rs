@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 可以设置接收方式的通知类型，以及在摘要中的名称
+pub const NOTIFICATION_TYPES: &[(&str, &str)] = &[
+    ("REPLY", "新回复"),
+    ("WATCH", "关注帖子的新回复"),
+    ("MENTION", "提及"),
+    ("MOVE", "帖子移动通知"),
+    ("ACCEPT", "回帖采纳通知"),
+    ("ANNOUNCEMENT", "课程公告"),
+];
+
+/// 通知的接收方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NotificationMode {
+    /// 即时接收
+    #[default]
+    Instant,
+    /// 每小时合并为一条摘要
+    HourlyDigest,
+    /// 每天合并为一条摘要
+    DailyDigest,
+    /// 不接收
+    Off,
+}
+
+impl NotificationMode {
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationMode::Instant => "INSTANT",
+            NotificationMode::HourlyDigest => "HOURLY_DIGEST",
+            NotificationMode::DailyDigest => "DAILY_DIGEST",
+            NotificationMode::Off => "OFF",
+        }
+    }
+
+    /// 解析数据库中的值，无法识别时视为即时接收
+    pub fn from_column(mode: Option<&str>) -> Self {
+        match mode {
+            Some("HOURLY_DIGEST") => NotificationMode::HourlyDigest,
+            Some("DAILY_DIGEST") => NotificationMode::DailyDigest,
+            Some("OFF") => NotificationMode::Off,
+            _ => NotificationMode::Instant,
+        }
+    }
+}
+
+/// 用户对一类通知的接收方式
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPreference {
+    /// 通知类型
+    pub ntf_type: String,
+
+    /// 通知类型的名称
+    pub name: String,
+
+    pub mode: NotificationMode,
+}
@@ -0,0 +1,239 @@
+use crate::{
+    dto::{
+        forum_event::ForumEvent,
+        post_list::{PostPage, ThreadSummary},
+        post_search::PostSearchResult,
+        post_subscription::SubscribedPosts,
+        post_tree::{PostTreeContext, PostTreeNode},
+        post_visibility::PostVisibility,
+    },
+    entity::post,
+};
+
+/// 匿名称呼的前缀，后接该主题帖内匿名发帖人的序号
+pub const ALIAS_PREFIX: &str = "匿名同学";
+
+/// 查看帖子的用户
+#[derive(Debug, Clone, Copy)]
+pub struct Viewer<'a> {
+    pub user_id: &'a str,
+
+    /// 是否为助教及以上用户
+    pub is_staff: bool,
+}
+
+impl Viewer<'_> {
+    /// 能否看到帖子的真实发帖人：非匿名帖子、助教或发帖人本人
+    pub fn can_see_sender(&self, anonymous: bool, sender_no: Option<&str>) -> bool {
+        !anonymous || self.is_staff || sender_no == Some(self.user_id)
+    }
+
+    /// 能否看到帖子：公开帖子、助教或私密提问的提问者
+    pub fn can_see_post(&self, post: &post::Model) -> bool {
+        PostVisibility::from_column(post.post_visibility.as_deref()) == PostVisibility::Public
+            || self.is_staff
+            || post.post_private_sno.as_deref() == Some(self.user_id)
+    }
+
+    /// 列出帖子时私密帖子的过滤条件，助教不过滤
+    pub fn visible_to(&self) -> Option<&str> {
+        (!self.is_staff).then_some(self.user_id)
+    }
+}
+
+/// 对无权查看的用户隐去匿名帖子的发帖人，返回帖子前统一调用
+pub trait Redact {
+    fn redact(&mut self, viewer: &Viewer);
+}
+
+impl Redact for post::Model {
+    fn redact(&mut self, viewer: &Viewer) {
+        let anonymous = self.post_anonymous.as_deref() == Some("1");
+        if !viewer.can_see_sender(anonymous, self.post_sender_no.as_deref()) {
+            self.post_sender_no = None;
+        }
+    }
+}
+
+impl<T: Redact> Redact for Vec<T> {
+    fn redact(&mut self, viewer: &Viewer) {
+        self.iter_mut().for_each(|item| item.redact(viewer));
+    }
+}
+
+impl<T: Redact> Redact for Option<T> {
+    fn redact(&mut self, viewer: &Viewer) {
+        if let Some(item) = self {
+            item.redact(viewer);
+        }
+    }
+}
+
+impl Redact for PostPage {
+    fn redact(&mut self, viewer: &Viewer) {
+        self.posts.redact(viewer);
+    }
+}
+
+impl Redact for ThreadSummary {
+    fn redact(&mut self, viewer: &Viewer) {
+        let anonymous = self.last_replier_alias.is_some();
+        let sender_no = self.last_replier.as_ref().map(|s| s.stu_no.as_str());
+        if !viewer.can_see_sender(anonymous, sender_no) {
+            self.last_replier = None;
+        }
+    }
+}
+
+impl Redact for PostTreeNode {
+    fn redact(&mut self, viewer: &Viewer) {
+        self.post.redact(viewer);
+        self.children.redact(viewer);
+    }
+}
+
+impl Redact for PostTreeContext {
+    fn redact(&mut self, viewer: &Viewer) {
+        self.root.redact(viewer);
+    }
+}
+
+impl Redact for PostSearchResult {
+    fn redact(&mut self, viewer: &Viewer) {
+        self.hits.iter_mut().for_each(|hit| hit.post.redact(viewer));
+        // 按发帖人的分面统计包含匿名帖子，只对助教返回
+        if !viewer.is_staff {
+            self.facets.remove("postSno");
+        }
+    }
+}
+
+impl Redact for SubscribedPosts {
+    fn redact(&mut self, viewer: &Viewer) {
+        self.subscriptions
+            .iter_mut()
+            .for_each(|subscription| subscription.post.redact(viewer));
+    }
+}
+
+impl Redact for ForumEvent {
+    fn redact(&mut self, viewer: &Viewer) {
+        match self {
+            ForumEvent::Notification { .. } => {}
+            ForumEvent::NewPost { post, .. } | ForumEvent::NewReply { post, .. } => {
+                post.redact(viewer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::dto::post_search::PostSearchHit;
+
+    const SENDER: &str = "2150001";
+
+    fn anonymous_post(post_id: i32) -> post::Model {
+        post::Model {
+            post_id,
+            post_sender_no: Some(SENDER.into()),
+            post_anonymous: Some("1".into()),
+            post_alias: Some(format!("{}1", ALIAS_PREFIX)),
+            ..Default::default()
+        }
+    }
+
+    fn viewers() -> [(Viewer<'static>, bool); 3] {
+        [
+            (
+                Viewer {
+                    user_id: "2150002",
+                    is_staff: false,
+                },
+                false,
+            ),
+            (
+                Viewer {
+                    user_id: SENDER,
+                    is_staff: false,
+                },
+                true,
+            ),
+            (
+                Viewer {
+                    user_id: "1000001",
+                    is_staff: true,
+                },
+                true,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_redact_list() {
+        for (viewer, can_see) in viewers() {
+            let mut page = PostPage {
+                posts: vec![
+                    anonymous_post(1),
+                    post::Model {
+                        post_anonymous: Some("0".into()),
+                        ..anonymous_post(2)
+                    },
+                ],
+                next_cursor: None,
+            };
+            page.redact(&viewer);
+            assert_eq!(page.posts[0].post_sender_no.is_some(), can_see);
+            assert_eq!(page.posts[1].post_sender_no.as_deref(), Some(SENDER));
+        }
+    }
+
+    #[test]
+    fn test_redact_search() {
+        for (viewer, can_see) in viewers() {
+            let mut result = PostSearchResult {
+                hits: vec![PostSearchHit {
+                    post: anonymous_post(1),
+                    highlighted_title: None,
+                    snippet: None,
+                }],
+                facets: HashMap::from([(
+                    "postSno".to_string(),
+                    HashMap::from([(SENDER.to_string(), 1)]),
+                )]),
+                ..PostSearchResult::empty(10, 1)
+            };
+            result.redact(&viewer);
+            assert_eq!(result.hits[0].post.post_sender_no.is_some(), can_see);
+            assert_eq!(result.facets.contains_key("postSno"), viewer.is_staff);
+        }
+    }
+
+    #[test]
+    fn test_redact_events() {
+        for (viewer, can_see) in viewers() {
+            let mut events = [
+                ForumEvent::NewPost {
+                    board_ids: vec![],
+                    post: anonymous_post(1),
+                },
+                ForumEvent::NewReply {
+                    board_ids: vec![],
+                    root_id: 1,
+                    post: anonymous_post(2),
+                },
+            ];
+            for event in &mut events {
+                event.redact(&viewer);
+                let (ForumEvent::NewPost { post, .. } | ForumEvent::NewReply { post, .. }) = event
+                else {
+                    unreachable!()
+                };
+                assert_eq!(post.post_sender_no.is_some(), can_see);
+            }
+        }
+    }
+}
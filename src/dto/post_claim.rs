@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::entity::{post, post_claim};
+
+/// 待回答的提问
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnansweredQuestion {
+    /// 帖子信息（不含正文）
+    pub post: post::Model,
+
+    /// 认领记录，为空表示无人认领
+    pub claim: Option<post_claim::Model>,
+}
+
+/// 待回答提问的列表
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnansweredQuestions {
+    /// 总数
+    pub total_count: u64,
+
+    /// 按发帖时间先后排列的提问
+    pub questions: Vec<UnansweredQuestion>,
+}
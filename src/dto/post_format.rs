@@ -0,0 +1,42 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 帖子内容的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ContentFormat {
+    /// 富文本编辑器提交的HTML
+    #[default]
+    Html,
+    /// Markdown，服务端渲染为HTML
+    Markdown,
+}
+
+impl ContentFormat {
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentFormat::Html => "HTML",
+            ContentFormat::Markdown => "MARKDOWN",
+        }
+    }
+
+    /// 数据库中的值，为空视为HTML
+    pub fn from_column(value: Option<&str>) -> Self {
+        value.and_then(|v| v.parse().ok()).unwrap_or_default()
+    }
+}
+
+impl FromStr for ContentFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "HTML" => Ok(ContentFormat::Html),
+            "MARKDOWN" => Ok(ContentFormat::Markdown),
+            _ => Err(()),
+        }
+    }
+}
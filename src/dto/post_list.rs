@@ -0,0 +1,121 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{dto::student_short_info::StudentShortInfo, entity::post};
+
+/// 板块帖子列表的排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PostSort {
+    /// 置顶优先，其次按发帖时间从新到旧
+    #[default]
+    PriorityFirst,
+    /// 按发帖时间从新到旧
+    Newest,
+    /// 按发帖时间从旧到新
+    Oldest,
+    /// 按最后回帖时间从新到旧
+    LastActivity,
+    /// 按回帖数从多到少
+    MostReplies,
+}
+
+/// 分页游标，记录上一页最后一个帖子在排序中的位置
+///
+/// 对客户端不透明，编码为URL安全的Base64字符串
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostCursor {
+    /// 生成游标时的排序方式，不能用于其他排序
+    #[serde(rename = "s")]
+    pub sort: PostSort,
+
+    /// 置顶级别
+    #[serde(rename = "p", default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+
+    /// 发帖时间或最后回帖时间
+    #[serde(rename = "d", default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDateTime>,
+
+    /// 回帖数
+    #[serde(rename = "r", default, skip_serializing_if = "Option::is_none")]
+    pub reply_count: Option<i32>,
+
+    /// 帖子id
+    #[serde(rename = "i")]
+    pub post_id: i32,
+}
+
+impl PostCursor {
+    /// 以帖子在该排序中的位置生成游标
+    pub fn of(sort: PostSort, post: &post::Model) -> Self {
+        let mut cursor = Self {
+            sort,
+            priority: None,
+            date: None,
+            reply_count: None,
+            post_id: post.post_id,
+        };
+        match sort {
+            PostSort::PriorityFirst => {
+                cursor.priority = post.post_priority.clone();
+                cursor.date = post.post_date;
+            }
+            PostSort::Newest | PostSort::Oldest => cursor.date = post.post_date,
+            PostSort::LastActivity => cursor.date = post.post_active_date,
+            PostSort::MostReplies => cursor.reply_count = post.post_reply_count,
+        }
+        cursor
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap())
+    }
+
+    /// 解析游标，格式错误返回None
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// 板块内的一页帖子
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostPage {
+    pub posts: Vec<post::Model>,
+
+    /// 下一页的游标，没有更多帖子时为空
+    pub next_cursor: Option<String>,
+}
+
+/// 主题帖的回帖概况
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadSummary {
+    /// 主题帖id
+    pub post_id: i32,
+
+    /// 未删除的回帖数
+    pub reply_count: i64,
+
+    /// 最后回帖时间，没有回帖时为空
+    pub last_reply_date: Option<NaiveDateTime>,
+
+    /// 最后回帖人，没有回帖或无权查看匿名回帖的发帖人时为空
+    pub last_replier: Option<StudentShortInfo>,
+
+    /// 最后一条回帖为匿名回帖时的匿名称呼
+    pub last_replier_alias: Option<String>,
+
+    /// 是否有助教及以上用户回帖
+    pub staff_replied: bool,
+
+    /// 是否有未读的帖子（从未打开过的主题帖也视为未读）
+    pub unread: bool,
+
+    /// 未读的回帖数
+    pub unread_count: i64,
+}
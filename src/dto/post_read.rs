@@ -0,0 +1,13 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 板块中有未读帖子的主题帖数
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardUnread {
+    /// 板块id
+    pub board_id: String,
+
+    /// 有未读帖子的主题帖数
+    pub unread_threads: u64,
+}
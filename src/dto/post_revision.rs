@@ -0,0 +1,53 @@
+use forum_utils::text_diff::{self, ChangeKind};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 片段的变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DiffKind {
+    /// 未变化
+    Equal,
+    /// 被删除
+    Delete,
+    /// 新增
+    Insert,
+}
+
+/// 比较结果中的一个连续片段
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSegment {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+impl From<text_diff::DiffSegment> for DiffSegment {
+    fn from(segment: text_diff::DiffSegment) -> Self {
+        Self {
+            kind: match segment.kind {
+                ChangeKind::Equal => DiffKind::Equal,
+                ChangeKind::Delete => DiffKind::Delete,
+                ChangeKind::Insert => DiffKind::Insert,
+            },
+            text: segment.text,
+        }
+    }
+}
+
+/// 帖子两个版本之间的差异
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostRevisionDiff {
+    /// 较早的版本
+    pub from_rev_id: i32,
+
+    /// 较新的版本，为空表示当前版本
+    pub to_rev_id: Option<i32>,
+
+    /// 标题的差异
+    pub title_diff: Vec<DiffSegment>,
+
+    /// 正文（转换为纯文本后）的差异
+    pub content_diff: Vec<DiffSegment>,
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{dto::post_status::PostStatus, entity::post};
+
+/// 搜索命中的帖子
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostSearchHit {
+    /// 帖子信息（不含正文）
+    pub post: post::Model,
+
+    /// 高亮后的标题
+    pub highlighted_title: Option<String>,
+
+    /// 高亮并截取后的正文片段
+    pub snippet: Option<String>,
+}
+
+/// 搜索结果的排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PostSearchSort {
+    /// 按相关度
+    #[default]
+    Relevance,
+    /// 按发帖时间从新到旧
+    Newest,
+    /// 按发帖时间从旧到新
+    Oldest,
+}
+
+/// 结构化的搜索过滤条件，各条件之间为“且”的关系
+#[derive(Debug, Clone, Default)]
+pub struct PostSearchFilters {
+    /// 同时带有这些标签（标签序号的JSON数组，与列出帖子时相同）
+    pub tags: Option<String>,
+
+    /// 布置周
+    pub week: Option<i8>,
+
+    /// 作业序号
+    pub hw_id: Option<i16>,
+
+    /// 发帖人学号
+    pub author: Option<String>,
+
+    /// 帖子类型
+    pub post_type: Option<String>,
+
+    /// 主题帖状态
+    pub status: Option<PostStatus>,
+
+    /// 发帖时间不早于
+    pub date_from: Option<NaiveDateTime>,
+
+    /// 发帖时间不晚于
+    pub date_to: Option<NaiveDateTime>,
+}
+
+/// 帖子搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostSearchResult {
+    /// 命中的帖子
+    pub hits: Vec<PostSearchHit>,
+
+    /// 命中总数
+    pub total_hits: usize,
+
+    /// 总页数
+    pub total_pages: usize,
+
+    /// 分页: 页面大小
+    pub page_size: usize,
+
+    /// 分页: 页面编号
+    pub page_index: usize,
+
+    /// 搜索引擎处理耗时（毫秒）
+    pub processing_time_ms: usize,
+
+    /// 分面统计: 字段名 -> (字段值 -> 命中数)
+    pub facets: HashMap<String, HashMap<String, usize>>,
+}
+
+impl PostSearchResult {
+    /// 空结果，用于用户没有任何可见课程的情况
+    pub fn empty(page_size: usize, page_index: usize) -> Self {
+        Self {
+            hits: vec![],
+            total_hits: 0,
+            total_pages: 0,
+            page_size,
+            page_index,
+            processing_time_ms: 0,
+            facets: HashMap::new(),
+        }
+    }
+}
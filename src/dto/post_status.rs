@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 主题帖的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PostStatus {
+    /// 待回答
+    Open,
+    /// 已有他人回帖
+    Answered,
+    /// 已采纳回帖
+    Resolved,
+    /// 已关闭，不能再回帖
+    Closed,
+}
+
+impl PostStatus {
+    /// 数据库及搜索索引中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostStatus::Open => "OPEN",
+            PostStatus::Answered => "ANSWERED",
+            PostStatus::Resolved => "RESOLVED",
+            PostStatus::Closed => "CLOSED",
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::entity::{post, post_subscription};
+
+/// 主题帖的订阅方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SubscriptionMode {
+    /// 关注，接收所有回帖的通知
+    Watch,
+    /// 屏蔽，不接收任何通知
+    Mute,
+}
+
+impl SubscriptionMode {
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionMode::Watch => "WATCH",
+            SubscriptionMode::Mute => "MUTE",
+        }
+    }
+}
+
+/// 用户订阅的主题帖
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribedPost {
+    /// 订阅记录
+    pub subscription: post_subscription::Model,
+
+    /// 主题帖信息（不含正文），帖子已被彻底删除时为空
+    pub post: Option<post::Model>,
+}
+
+/// 用户订阅的主题帖列表
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribedPosts {
+    /// 订阅总数
+    pub total_count: u64,
+
+    /// 按订阅时间从新到旧排列的主题帖
+    pub subscriptions: Vec<SubscribedPost>,
+}
@@ -0,0 +1,36 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::entity::post;
+
+/// 帖子树中的一个帖子及其子回帖
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostTreeNode {
+    pub post: post::Model,
+
+    /// 相对于请求的帖子的深度，请求的帖子为0
+    pub depth: u32,
+
+    /// 直接回复本帖的回帖总数
+    pub total_children: u64,
+
+    /// 本次返回的子回帖，按发帖时间排列
+    ///
+    /// 为空但`totalChildren`大于0时，以本帖id重新请求获取子回帖
+    pub children: Vec<PostTreeNode>,
+
+    /// 获取后续子回帖的游标，以本帖id与该游标再次请求，没有更多时为空
+    pub next_cursor: Option<i32>,
+}
+
+/// 从主题帖到某个回帖的路径，以及该回帖前后相邻的回帖
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostTreeContext {
+    /// 定位的回帖id
+    pub focus_id: i32,
+
+    /// 以主题帖为根的树，只包含路径上的帖子、定位回帖的相邻回帖及其第一页子回帖
+    pub root: PostTreeNode,
+}
@@ -0,0 +1,42 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 主题帖的可见范围，回帖与主题帖相同
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PostVisibility {
+    /// 课程内所有用户可见
+    #[default]
+    Public,
+    /// 仅提问者与助教可见
+    Private,
+}
+
+impl PostVisibility {
+    /// 数据库及搜索索引中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostVisibility::Public => "PUBLIC",
+            PostVisibility::Private => "PRIVATE",
+        }
+    }
+
+    /// 数据库中的值，为空视为公开
+    pub fn from_column(value: Option<&str>) -> Self {
+        value.and_then(|v| v.parse().ok()).unwrap_or_default()
+    }
+}
+
+impl FromStr for PostVisibility {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "PUBLIC" => Ok(PostVisibility::Public),
+            "PRIVATE" => Ok(PostVisibility::Private),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::scheduler::JobKind;
+
+/// 定时任务的运行状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JobStatus {
+    /// 尚未运行过
+    #[default]
+    Idle,
+    /// 正在运行
+    Running,
+    /// 上次运行成功
+    Succeeded,
+    /// 上次运行失败
+    Failed,
+}
+
+impl JobStatus {
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Idle => "IDLE",
+            JobStatus::Running => "RUNNING",
+            JobStatus::Succeeded => "SUCCEEDED",
+            JobStatus::Failed => "FAILED",
+        }
+    }
+
+    pub fn from_column(status: Option<&str>) -> Self {
+        match status {
+            Some("RUNNING") => JobStatus::Running,
+            Some("SUCCEEDED") => JobStatus::Succeeded,
+            Some("FAILED") => JobStatus::Failed,
+            _ => JobStatus::Idle,
+        }
+    }
+}
+
+/// 配置中的定时任务及其运行记录
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJobInfo {
+    pub name: String,
+
+    pub kind: JobKind,
+
+    /// 运行周期，cron表达式或`every {n}s`
+    pub schedule: String,
+
+    pub enabled: bool,
+
+    pub status: JobStatus,
+
+    pub last_start: Option<NaiveDateTime>,
+
+    pub last_finish: Option<NaiveDateTime>,
+
+    /// 下次计划运行的时间（含随机推迟）
+    pub next_run: Option<NaiveDateTime>,
+
+    /// 上次失败的原因
+    pub last_error: Option<String>,
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 课程公告阅读回执表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "announcement_read")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 公告的主题帖id
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub ar_post_id: i32,
+
+    /// 阅读人学号
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub ar_sno: String,
+
+    /// 第一次打开的时间
+    pub ar_date: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
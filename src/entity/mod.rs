@@ -1,10 +1,21 @@
+pub mod announcement_read;
 pub mod course;
 pub mod homework;
 pub mod homework_uploaded;
 pub mod log_login;
 pub mod log_post;
 pub mod notification;
+pub mod notification_pending;
+pub mod notification_preference;
 pub mod post;
+pub mod post_claim;
+pub mod post_read;
+pub mod post_revision;
+pub mod post_subscription;
+pub mod scheduled_job;
+pub mod search_outbox;
+pub mod search_rebuild;
+pub mod search_rebuild_dirty;
 pub mod student;
 pub mod student_info;
 pub mod tag;
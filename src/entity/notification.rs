@@ -17,6 +17,18 @@ pub struct Model {
 
     pub ntf_content: String,
 
+    /// 通知指向的帖子id
+    pub ntf_post_id: Option<i32>,
+
+    /// 通知指向的帖子所在的主题帖id
+    pub ntf_root_id: Option<i32>,
+
+    /// 主题帖所在的板块id
+    pub ntf_board_id: Option<String>,
+
+    /// 触发通知的用户学号，由匿名帖子触发时为空
+    pub ntf_actor: Option<String>,
+
     pub ntf_receiver: String,
 
     pub ntf_datetime: NaiveDateTime,
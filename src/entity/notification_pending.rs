@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 待合并为摘要的通知表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "notification_pending")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 序号(主键,自动增长)
+    #[sea_orm(primary_key)]
+    pub pend_id: u64,
+
+    /// 接收者学号
+    pub pend_receiver: String,
+
+    pub pend_type: String,
+
+    pub pend_title: String,
+
+    pub pend_content: String,
+
+    pub pend_post_id: Option<i32>,
+
+    pub pend_root_id: Option<i32>,
+
+    pub pend_board_id: Option<String>,
+
+    pub pend_actor: Option<String>,
+
+    /// 产生通知的时间
+    pub pend_datetime: NaiveDateTime,
+
+    /// 随摘要发送的时间
+    pub pend_due: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 通知接收方式表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "notification_preference")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 用户学号
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub pref_sno: String,
+
+    /// 通知类型
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub pref_type: String,
+
+    /// 接收方式('INSTANT':即时 'HOURLY_DIGEST':每小时摘要 'DAILY_DIGEST':每日摘要 'OFF':不接收)
+    pub pref_mode: String,
+
+    /// 设置时间
+    pub pref_date: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -51,6 +51,12 @@ pub struct Model {
     #[serde(rename = "postSno")]
     pub post_sender_no: Option<String>,
 
+    /// 是否匿名发帖('0':否 '1':是 匿名时只有发帖人和助教能看到发帖人学号)
+    pub post_anonymous: Option<String>,
+
+    /// 匿名称呼(仅匿名帖子,同一主题帖内同一发帖人的称呼相同)
+    pub post_alias: Option<String>,
+
     /// 优先级(从'0'~'9' 依次递增,帖子显示是按优先级顺序,相同优先级按发帖时间,可由管理员手工置位进行调整)
     pub post_priority: Option<String>,
 
@@ -90,12 +96,45 @@ pub struct Model {
     /// 发帖具体内容(允许贴图,Richtext?)
     pub post_content: Option<String>,
 
+    /// 内容格式('HTML' 'MARKDOWN' 为空视为HTML)
+    pub post_format: Option<String>,
+
+    /// Markdown原文(仅MARKDOWN格式,post_content为渲染后的HTML)
+    pub post_source: Option<String>,
+
     /// 发帖时间
     pub post_date: Option<NaiveDateTime>,
 
     /// 帖子是否已删除('0':正常显示 '1':不显示,包括所有的回帖 注意:enum不要当int处理)
     pub post_is_del: Option<String>,
 
+    /// 删除时间(软删除时置位,恢复时清空)
+    pub post_del_date: Option<NaiveDateTime>,
+
+    /// 删除批次(软删除时置位,恢复时清空;同一次删除的帖子及其回帖批次相同)
+    pub post_del_batch: Option<String>,
+
+    /// 主题帖状态('OPEN':待回答 'ANSWERED':已回答 'RESOLVED':已解决 'CLOSED':已关闭 回帖为空)
+    pub post_status: Option<String>,
+
+    /// 可见范围('PUBLIC':课程内公开 'PRIVATE':仅提问者与助教可见 回帖与主题帖相同)
+    pub post_visibility: Option<String>,
+
+    /// 私密帖子的提问者学号(回帖与主题帖相同,公开帖子为空)
+    pub post_private_sno: Option<String>,
+
+    /// 主题帖采纳的回帖id
+    pub post_accepted_id: Option<i32>,
+
+    /// 主题帖下未删除的回帖数(回帖为0)
+    pub post_reply_count: Option<i32>,
+
+    /// 最后活跃时间(主题帖为最后一条未删除回帖的时间,没有回帖时为发帖时间;回帖为发帖时间)
+    pub post_active_date: Option<NaiveDateTime>,
+
+    /// 主题帖中最新的未删除帖子id(主题帖自身也计入;回帖为空)
+    pub post_last_id: Option<i32>,
+
     /// 备注(预留)
     pub post_comment: Option<String>,
 }
@@ -112,6 +151,8 @@ impl Default for Model {
             post_answer_id: Default::default(),
             post_type: Default::default(),
             post_sender_no: Default::default(),
+            post_anonymous: Some("0".into()),
+            post_alias: Default::default(),
             post_priority: Some("0".into()),
             post_tag_01: Some("0".into()),
             post_tag_02: Some("0".into()),
@@ -125,8 +166,19 @@ impl Default for Model {
             post_tag_10: Some("0".into()),
             post_title: Default::default(),
             post_content: Default::default(),
+            post_format: Default::default(),
+            post_source: Default::default(),
             post_date: Default::default(),
             post_is_del: Some("0".into()),
+            post_del_date: Default::default(),
+            post_del_batch: Default::default(),
+            post_status: Default::default(),
+            post_visibility: Some("PUBLIC".into()),
+            post_private_sno: Default::default(),
+            post_accepted_id: Default::default(),
+            post_reply_count: Some(0),
+            post_active_date: Default::default(),
+            post_last_id: Default::default(),
             post_comment: Default::default(),
         }
     }
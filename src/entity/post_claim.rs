@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 助教认领待回答提问的记录表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "post_claim")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 被认领的主题帖id(主键,同一帖子只能被一人认领)
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub claim_post_id: i32,
+
+    /// 认领人学号
+    pub claim_sno: String,
+
+    /// 认领时间
+    pub claim_date: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
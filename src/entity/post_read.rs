@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 主题帖阅读记录表(由Redis中的阅读记录定期写入)
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "post_read")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 阅读人学号
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub read_sno: String,
+
+    /// 主题帖id
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub read_post_id: i32,
+
+    /// 已读到的最新帖子id(主题帖及回帖中id不大于该值的均视为已读)
+    pub read_last_id: i32,
+
+    /// 写入时间
+    pub read_date: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,47 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 帖子修改历史表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "post_revision")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 序号(主键,自动增长)
+    #[sea_orm(primary_key)]
+    pub rev_id: i32,
+
+    /// 帖子id
+    pub rev_post_id: i32,
+
+    /// 修改前的标题
+    pub rev_title: Option<String>,
+
+    /// 修改前的内容
+    pub rev_content: Option<String>,
+
+    /// 修改前的内容格式(为空视为HTML)
+    pub rev_format: Option<String>,
+
+    /// 修改前的Markdown原文
+    pub rev_source: Option<String>,
+
+    /// 修改人学号
+    #[sea_orm(column_name = "rev_opno")]
+    #[serde(rename = "revOpno")]
+    pub rev_op_no: String,
+
+    /// 修改人IP
+    pub rev_ipaddr: String,
+
+    /// 修改时间
+    pub rev_date: NaiveDateTime,
+
+    /// 备注(EDIT:编辑 ROLLBACK:回滚)
+    pub rev_comment: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
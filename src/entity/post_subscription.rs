@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 主题帖订阅表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "post_subscription")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 主题帖id
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sub_post_id: i32,
+
+    /// 订阅人学号
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sub_sno: String,
+
+    /// 订阅方式('WATCH':接收所有回帖的通知 'MUTE':不接收任何通知)
+    pub sub_mode: String,
+
+    /// 订阅时间
+    pub sub_date: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 定时任务运行记录表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "scheduled_job")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 任务名称
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub job_name: String,
+
+    /// 状态('IDLE':未运行过 'RUNNING':运行中 'SUCCEEDED':上次成功 'FAILED':上次失败)
+    pub job_status: String,
+
+    /// 上次开始运行的时间
+    pub job_last_start: Option<NaiveDateTime>,
+
+    /// 上次结束运行的时间
+    pub job_last_finish: Option<NaiveDateTime>,
+
+    /// 下次计划运行的时间
+    pub job_next_run: Option<NaiveDateTime>,
+
+    /// 上次失败的原因
+    pub job_last_error: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
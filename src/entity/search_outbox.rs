@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 搜索引擎同步队列表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "search_outbox")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 序号(主键,自动增长)
+    #[sea_orm(primary_key)]
+    pub outbox_id: u64,
+
+    /// 待同步的帖子id(帖子不存在时从索引中删除)
+    pub outbox_post_id: i32,
+
+    /// 已尝试同步的次数
+    pub outbox_attempts: i32,
+
+    /// 下次尝试同步的时间
+    pub outbox_next_attempt_at: NaiveDateTime,
+
+    /// 最近一次同步失败的原因
+    pub outbox_last_error: Option<String>,
+
+    /// 入队时间
+    pub outbox_created_at: NaiveDateTime,
+
+    /// 正在处理该条目的服务实例
+    pub outbox_locked_by: Option<String>,
+
+    /// 处理租约的到期时间，到期后其他实例可以重新领取
+    pub outbox_locked_until: Option<NaiveDateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 搜索索引重建记录表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "search_rebuild")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 序号(主键,自动增长)
+    #[sea_orm(primary_key)]
+    pub rebuild_id: u64,
+
+    /// 开始重建的时间
+    pub rebuild_started_at: NaiveDateTime,
+
+    /// 重建结束的时间，为空表示正在重建
+    pub rebuild_finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
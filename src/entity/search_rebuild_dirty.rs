@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 重建索引期间同步过的帖子表
+#[derive(Debug, Clone, Default, Deserialize, Serialize, DeriveEntityModel, utoipa::ToSchema)]
+#[sea_orm(table_name = "search_rebuild_dirty")]
+#[serde(default, rename_all = "camelCase")]
+pub struct Model {
+    /// 重建记录id
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub dirty_rebuild_id: u64,
+
+    /// 帖子id
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub dirty_post_id: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
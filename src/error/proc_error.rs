@@ -1,8 +1,11 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use fred::error::RedisError;
 use log::error;
+use meilisearch_sdk::errors::Error as MeiliErr;
 use minio::s3::error::Error as MinioErr;
 use sea_orm::DbErr;
+use tantivy::TantivyError;
 
 use thiserror::Error;
 
@@ -14,6 +17,12 @@ pub enum ProcessError {
     SeaOrmDatabaseError(DbErr),
     #[error("Minio执行错误:{0}")]
     MinioError(MinioErr),
+    #[error("搜索引擎执行错误:{0}")]
+    MeiliError(MeiliErr),
+    #[error("内嵌搜索引擎执行错误:{0}")]
+    EmbeddedSearchError(TantivyError),
+    #[error("Redis执行错误:{0}")]
+    RedisError(RedisError),
     #[error("{0}")]
     GeneralError(&'static str),
 }
@@ -30,6 +39,24 @@ impl From<MinioErr> for ProcessError {
     }
 }
 
+impl From<MeiliErr> for ProcessError {
+    fn from(value: MeiliErr) -> Self {
+        ProcessError::MeiliError(value)
+    }
+}
+
+impl From<TantivyError> for ProcessError {
+    fn from(value: TantivyError) -> Self {
+        ProcessError::EmbeddedSearchError(value)
+    }
+}
+
+impl From<RedisError> for ProcessError {
+    fn from(value: RedisError) -> Self {
+        ProcessError::RedisError(value)
+    }
+}
+
 impl IntoResponse for ProcessError {
     fn into_response(self) -> Response {
         ApiResponse::err_with_code(self, StatusCode::INTERNAL_SERVER_ERROR).into_response()
@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum_login::{AuthUser, AuthzBackend};
+use futures::{stream, Stream};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::IntoParams;
+
+use crate::config::permission::Permission;
+use crate::dto::forum_event::ForumEvent;
+use crate::dto::post_anonymity::{Redact, Viewer};
+use crate::error::api_error::ApiError;
+use crate::error::auth_error::AuthError;
+use crate::error::param_error::ParameterError::InvalidParameter;
+use crate::error::proc_error::ProcessError;
+use crate::service::event_service::EventServiceTrait;
+use crate::service::post_service::PostServiceTrait;
+use crate::state::event_state::EventState;
+
+use super::AuthSession;
+
+/// 一个连接最多关注的板块数
+const MAX_STREAM_BOARDS: usize = 20;
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamEventsParams {
+    /// 关注新帖与新回帖的板块id，以逗号分隔（为空则只推送通知）
+    pub boards: Option<String>,
+}
+
+/// 该用户应当收到的事件，匿名帖子已按用户隐去发帖人
+fn visible_event(
+    event: &ForumEvent,
+    viewer: &Viewer,
+    board_ids: &HashSet<String>,
+) -> Option<ForumEvent> {
+    let visible = match event {
+        ForumEvent::Notification { notification } => notification.ntf_receiver == viewer.user_id,
+        ForumEvent::NewPost {
+            board_ids: post_board_ids,
+            post,
+        }
+        | ForumEvent::NewReply {
+            board_ids: post_board_ids,
+            post,
+            ..
+        } => post_board_ids.iter().any(|id| board_ids.contains(id)) && viewer.can_see_post(post),
+    };
+    visible.then(|| {
+        let mut event = event.clone();
+        event.redact(viewer);
+        event
+    })
+}
+
+/// 订阅实时事件
+///
+/// 以SSE推送当前用户的新通知，以及关注板块中的新帖与新回帖，事件名为事件类型；
+/// 连接处理过慢丢失事件时推送`LAGGED`事件，客户端应重新拉取
+#[utoipa::path(
+    get,
+    path = "/event/stream",
+    tag = "Event",
+    responses(
+        (status = 200, content_type = "text/event-stream", body = ForumEvent)
+    ),
+    params(StreamEventsParams)
+)]
+pub async fn stream_events(
+    State(state): State<EventState>,
+    auth_session: AuthSession,
+    Query(params): Query<StreamEventsParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let user = auth_session.user.as_ref().unwrap();
+    let user_id = user.id();
+    let is_staff = auth_session
+        .backend
+        .has_perm(user, Permission::TA)
+        .await
+        .map_err(|_| ProcessError::GeneralError("验证权限失败"))?;
+
+    let board_ids: HashSet<String> = params
+        .boards
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(ToString::to_string)
+        .collect();
+    if board_ids.len() > MAX_STREAM_BOARDS {
+        return Err(InvalidParameter("关注的板块过多").into());
+    }
+    for board_id in &board_ids {
+        if !state
+            .post_service
+            .ensure_query_board_permission(&user_id, board_id)
+            .await?
+        {
+            return Err(AuthError::PermissionDenied("您无权查看本板块").into());
+        }
+    }
+
+    let receiver = state.event_service.subscribe();
+    let events = stream::unfold(
+        (receiver, user_id, board_ids),
+        move |(mut receiver, user_id, board_ids)| async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => {
+                        let event = Event::default().event("LAGGED").data("");
+                        return Some((Ok(event), (receiver, user_id, board_ids)));
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+
+                let viewer = Viewer {
+                    user_id: &user_id,
+                    is_staff,
+                };
+                let Some(event) = visible_event(&event, &viewer, &board_ids) else {
+                    continue;
+                };
+                if let Ok(event) = Event::default().event(event.name()).json_data(&event) {
+                    return Some((Ok(event), (receiver, user_id, board_ids)));
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dto::post_visibility::PostVisibility;
+    use crate::entity::{notification, post};
+
+    const STUDENT: &str = "2150001";
+    const ASKER: &str = "2150002";
+    const COURSE: &str = "2024_100718";
+    const BOARD: &str = "2024_100718_general";
+
+    fn student() -> Viewer<'static> {
+        Viewer {
+            user_id: STUDENT,
+            is_staff: false,
+        }
+    }
+
+    fn staff() -> Viewer<'static> {
+        Viewer {
+            user_id: "1000001",
+            is_staff: true,
+        }
+    }
+
+    fn boards(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(ToString::to_string).collect()
+    }
+
+    fn new_post(post: post::Model) -> ForumEvent {
+        ForumEvent::NewPost {
+            board_ids: vec![BOARD.to_string(), COURSE.to_string()],
+            post,
+        }
+    }
+
+    fn private_post() -> post::Model {
+        post::Model {
+            post_id: 1,
+            post_sender_no: Some(ASKER.into()),
+            post_visibility: Some(PostVisibility::Private.as_str().into()),
+            post_private_sno: Some(ASKER.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_notification_only_for_receiver() {
+        let event = |receiver: &str| ForumEvent::Notification {
+            notification: notification::Model {
+                ntf_receiver: receiver.to_string(),
+                ..Default::default()
+            },
+        };
+        let no_boards = HashSet::new();
+        assert!(visible_event(&event(STUDENT), &student(), &no_boards).is_some());
+        assert!(visible_event(&event(ASKER), &student(), &no_boards).is_none());
+        assert!(visible_event(&event(ASKER), &staff(), &no_boards).is_none());
+    }
+
+    #[test]
+    fn test_post_only_in_followed_boards() {
+        let event = new_post(post::Model {
+            post_id: 1,
+            ..Default::default()
+        });
+        assert!(visible_event(&event, &student(), &boards(&[BOARD])).is_some());
+        assert!(visible_event(&event, &student(), &boards(&[COURSE])).is_some());
+        assert!(visible_event(&event, &student(), &boards(&["2024_100719_general"])).is_none());
+        assert!(visible_event(&event, &student(), &HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_private_post_only_for_asker_and_staff() {
+        let followed = boards(&[BOARD]);
+        let asker = Viewer {
+            user_id: ASKER,
+            is_staff: false,
+        };
+        for event in [
+            new_post(private_post()),
+            ForumEvent::NewReply {
+                board_ids: vec![BOARD.to_string()],
+                root_id: 1,
+                post: post::Model {
+                    post_id: 2,
+                    ..private_post()
+                },
+            },
+        ] {
+            assert!(visible_event(&event, &student(), &followed).is_none());
+            assert!(visible_event(&event, &asker, &followed).is_some());
+            assert!(visible_event(&event, &staff(), &followed).is_some());
+        }
+    }
+
+    #[test]
+    fn test_anonymous_post_is_redacted() {
+        let followed = boards(&[BOARD]);
+        let event = new_post(post::Model {
+            post_id: 1,
+            post_sender_no: Some(ASKER.into()),
+            post_anonymous: Some("1".into()),
+            ..Default::default()
+        });
+        let sender = |viewer: &Viewer| match visible_event(&event, viewer, &followed) {
+            Some(ForumEvent::NewPost { post, .. }) => post.post_sender_no,
+            _ => unreachable!(),
+        };
+        assert_eq!(sender(&student()), None);
+        assert_eq!(sender(&staff()).as_deref(), Some(ASKER));
+    }
+}
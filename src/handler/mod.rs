@@ -1,10 +1,13 @@
 pub mod auth_handler;
 pub mod board_handler;
 pub mod course_handler;
+pub mod event_handler;
 pub mod homework_handler;
 pub mod metadata_handler;
 pub mod notification_handler;
 pub mod post_handler;
+pub mod scheduler_handler;
+pub mod search_handler;
 pub mod swagger_handler;
 pub mod upload_handler;
 pub mod user_handler;
@@ -1,33 +1,85 @@
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum_login::AuthUser;
 use axum_typed_multipart::{TryFromMultipart, TypedMultipart};
 use forum_macros::forum_handler;
+use serde::Deserialize;
 use utoipa::IntoParams;
 
 use crate::{
-    entity::notification::Model as Notification,
+    dto::notification_page::NotificationPage,
+    dto::notification_preference::{NotificationMode, NotificationPreference},
+    error::param_error::ParameterError::InvalidParameter,
     service::notification_service::NotificationServiceTrait,
     state::notification_state::NotificationState,
 };
 
 use super::AuthSession;
 
-/// 获取用户的通知
+/// 每页最多返回的通知数
+const MAX_NOTIFICATION_PAGE_SIZE: u64 = 100;
+
+fn default_notification_page_size() -> u64 {
+    20
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GetMyNotificationsParams {
+    /// 是否只返回未读通知
+    #[serde(default)]
+    pub unread_only: bool,
+
+    /// 上一页返回的游标，为空时从最新的通知开始
+    pub cursor: Option<u64>,
+
+    /// 每页通知数（默认20，最大100）
+    #[serde(default = "default_notification_page_size")]
+    pub page_size: u64,
+}
+
+/// 分页获取用户的通知
 #[utoipa::path(
     get,
     path = "/notification",
     tag = "Notification",
     responses(
-        (status = 200, description = "获取通知成功", body = inline(Vec<Notification>))
+        (status = 200, description = "获取通知成功", body = NotificationPage)
     ),
+    params(GetMyNotificationsParams)
 )]
 #[forum_handler]
 pub async fn get_my_notifications(
     State(state): State<NotificationState>,
     auth_session: AuthSession,
-) -> Vec<Notification> {
+    Query(params): Query<GetMyNotificationsParams>,
+) -> NotificationPage {
+    if !(1..=MAX_NOTIFICATION_PAGE_SIZE).contains(&params.page_size) {
+        return Err(InvalidParameter("分页参数无效").into());
+    }
+
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .notification_service
+        .get_notifications(user_id, params.unread_only, params.cursor, params.page_size)
+        .await
+}
+
+/// 获取用户的未读通知数
+#[utoipa::path(
+    get,
+    path = "/notification/unreadCount",
+    tag = "Notification",
+    responses(
+        (status = 200, description = "获取未读通知数成功", body = u64)
+    ),
+)]
+#[forum_handler]
+pub async fn get_my_unread_count(
+    State(state): State<NotificationState>,
+    auth_session: AuthSession,
+) -> u64 {
     let user_id = &auth_session.user.as_ref().unwrap().id();
-    state.notification_service.get_notifications(user_id).await
+    state.notification_service.get_unread_count(user_id).await
 }
 
 #[derive(Debug, Clone, TryFromMultipart, IntoParams)]
@@ -56,6 +108,95 @@ pub async fn read_my_notifications(
         .await
 }
 
+#[derive(Debug, Clone, TryFromMultipart, IntoParams)]
+#[try_from_multipart(rename_all = "camelCase")]
+pub struct ReadTargetParams {
+    /// 帖子id，已读指向该帖子的通知
+    pub post_id: Option<i32>,
+
+    /// 主题帖id，已读指向该主题帖中任意帖子的通知
+    pub root_id: Option<i32>,
+}
+
+/// 用户已读指向某个帖子或主题帖的通知
+///
+/// 返回标为已读的通知数
+#[utoipa::path(
+    post,
+    path = "/notification/readTarget",
+    tag = "Notification",
+    responses(
+        (status = 200, description = "已读通知成功", body = u64)
+    ),
+    params(ReadTargetParams)
+)]
+#[forum_handler]
+pub async fn read_target_notifications(
+    State(state): State<NotificationState>,
+    auth_session: AuthSession,
+    TypedMultipart(params): TypedMultipart<ReadTargetParams>,
+) -> u64 {
+    if params.post_id.is_none() && params.root_id.is_none() {
+        return Err(InvalidParameter("请指定帖子或主题帖").into());
+    }
+
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .notification_service
+        .user_read_target_notification(user_id, params.post_id, params.root_id)
+        .await
+}
+
+/// 获取用户对各类通知的接收方式
+#[utoipa::path(
+    get,
+    path = "/notification/preference",
+    tag = "Notification",
+    responses(
+        (status = 200, description = "获取设置成功", body = inline(Vec<NotificationPreference>))
+    ),
+)]
+#[forum_handler]
+pub async fn get_my_preferences(
+    State(state): State<NotificationState>,
+    auth_session: AuthSession,
+) -> Vec<NotificationPreference> {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state.notification_service.get_preferences(user_id).await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SetPreferenceParams {
+    /// 通知类型，如`REPLY`、`MENTION`
+    pub ntf_type: String,
+
+    /// 接收方式
+    pub mode: NotificationMode,
+}
+
+/// 设置用户对一类通知的接收方式
+///
+/// 设为摘要后，该类通知会在下一个整点或每日摘要时间合并为一条通知发送
+#[utoipa::path(
+    put,
+    path = "/notification/preference",
+    tag = "Notification",
+    params(SetPreferenceParams)
+)]
+#[forum_handler]
+pub async fn set_my_preference(
+    State(state): State<NotificationState>,
+    auth_session: AuthSession,
+    Query(params): Query<SetPreferenceParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .notification_service
+        .set_preference(user_id, &params.ntf_type, params.mode)
+        .await
+}
+
 /// 用户已读所有通知
 #[utoipa::path(post, path = "/notification/readAll", tag = "Notification")]
 #[forum_handler]
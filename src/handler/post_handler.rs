@@ -1,6 +1,16 @@
 use crate::config::permission::Permission;
-use crate::entity::post;
-use crate::error::param_error::ParameterError::InvalidParameter;
+use crate::dto::announcement::AnnouncementReceipt;
+use crate::dto::post_claim::UnansweredQuestions;
+use crate::dto::post_format::ContentFormat;
+use crate::dto::post_list::{PostSort, ThreadSummary};
+use crate::dto::post_read::BoardUnread;
+use crate::dto::post_revision::PostRevisionDiff;
+use crate::dto::post_search::{PostSearchFilters, PostSearchResult, PostSearchSort};
+use crate::dto::post_status::PostStatus;
+use crate::dto::post_subscription::{SubscribedPosts, SubscriptionMode};
+use crate::dto::post_tree::{PostTreeContext, PostTreeNode};
+use crate::entity::{post, post_revision};
+use crate::error::param_error::ParameterError::{self, InvalidParameter};
 use crate::error::proc_error::ProcessError;
 use crate::service::post_service::GetPostsResult;
 use crate::{error::auth_error::AuthError, service::post_service::PostServiceTrait};
@@ -10,6 +20,7 @@ use axum_client_ip::SecureClientIp;
 use axum_extra::extract::Query as ExQuery;
 use axum_login::{AuthUser, AuthzBackend};
 use axum_typed_multipart::{TryFromMultipart, TypedMultipart};
+use chrono::NaiveDateTime;
 use forum_macros::forum_handler;
 use forum_utils::encoding_helper::EncodingHelper;
 use serde::{Deserialize, Serialize};
@@ -19,6 +30,17 @@ use crate::state::post_state::PostState;
 
 use super::AuthSession;
 
+/// 解析帖子内容格式，为空返回None
+fn parse_format(format: Option<&str>) -> Result<Option<ContentFormat>, ParameterError> {
+    format
+        .map(|format| {
+            format
+                .parse()
+                .map_err(|_| InvalidParameter("不支持的帖子格式"))
+        })
+        .transpose()
+}
+
 #[derive(Debug, Clone, TryFromMultipart, IntoParams)]
 #[try_from_multipart(rename_all = "camelCase")]
 pub struct AddPostParams {
@@ -30,6 +52,15 @@ pub struct AddPostParams {
 
     /// 帖子内容
     pub content: String,
+
+    /// 内容格式（HTML或MARKDOWN，默认为HTML）
+    pub format: Option<String>,
+
+    /// 是否匿名发帖（默认否，需板块允许）
+    pub anonymous: Option<bool>,
+
+    /// 可见范围（PUBLIC或PRIVATE，默认为PUBLIC，私密提问仅提问者与助教可见）
+    pub visibility: Option<String>,
 }
 
 /// 发布帖子
@@ -53,6 +84,18 @@ pub async fn add_post(
         return Err(InvalidParameter("帖子内容包含非GBK字符").into());
     }
 
+    let format = parse_format(params.format.as_deref())?.unwrap_or_default();
+    let visibility = params
+        .visibility
+        .as_deref()
+        .map(|visibility| {
+            visibility
+                .parse()
+                .map_err(|_| InvalidParameter("不支持的可见范围"))
+        })
+        .transpose()?
+        .unwrap_or_default();
+
     let user_id = auth_session.user.unwrap().id();
     state
         .post_service
@@ -62,6 +105,9 @@ pub async fn add_post(
             &params.board_id,
             &params.title,
             &params.content,
+            format,
+            params.anonymous.unwrap_or_default(),
+            visibility,
         )
         .await
 }
@@ -74,6 +120,12 @@ pub struct AddReplyParams {
 
     /// 回复内容
     pub reply_content: String,
+
+    /// 内容格式（HTML或MARKDOWN，默认为HTML）
+    pub format: Option<String>,
+
+    /// 是否匿名回帖（默认否，需板块允许）
+    pub anonymous: Option<bool>,
 }
 
 /// 发送回帖
@@ -89,10 +141,19 @@ pub async fn add_reply(
         return Err(InvalidParameter("帖子内容包含非GBK字符").into());
     }
 
+    let format = parse_format(params.format.as_deref())?.unwrap_or_default();
+
     let user_id = auth_session.user.unwrap().id();
     state
         .post_service
-        .add_reply(&user_id, &ip_addr, params.post_id, &params.reply_content)
+        .add_reply(
+            &user_id,
+            &ip_addr,
+            params.post_id,
+            &params.reply_content,
+            format,
+            params.anonymous.unwrap_or_default(),
+        )
         .await
 }
 
@@ -102,12 +163,18 @@ pub struct EditPostParams {
     /// 帖子Id
     pub post_id: i32,
 
-    /// 编辑内容
-    pub content: String,
+    /// 新标题（为空则不修改，仅主题帖可修改）
+    pub title: Option<String>,
+
+    /// 编辑内容（为空则不修改）
+    pub content: Option<String>,
+
+    /// 内容格式（HTML或MARKDOWN，为空则沿用原格式）
+    pub format: Option<String>,
 }
 
 /// 编辑帖子或回复
-#[utoipa::path(put, path = "/post", tag = "Post", params(AddReplyParams))]
+#[utoipa::path(put, path = "/post", tag = "Post", params(EditPostParams))]
 #[forum_handler]
 pub async fn edit_post(
     State(state): State<PostState>,
@@ -115,9 +182,22 @@ pub async fn edit_post(
     SecureClientIp(ip_addr): SecureClientIp,
     TypedMultipart(params): TypedMultipart<EditPostParams>,
 ) {
-    if !EncodingHelper::gbk_guard(&params.content) {
+    if let Some(title) = &params.title {
+        if title.trim().is_empty() {
+            return Err(InvalidParameter("帖子标题不能为空").into());
+        }
+        if !EncodingHelper::gbk_guard(title) {
+            return Err(InvalidParameter("帖子标题包含非GBK字符").into());
+        }
+    }
+    if params
+        .content
+        .as_ref()
+        .is_some_and(|content| !EncodingHelper::gbk_guard(content))
+    {
         return Err(InvalidParameter("帖子内容包含非GBK字符").into());
     }
+    let format = parse_format(params.format.as_deref())?;
 
     let user_id = auth_session.user.unwrap().id();
     if state
@@ -127,7 +207,14 @@ pub async fn edit_post(
     {
         state
             .post_service
-            .edit_post(&user_id, &ip_addr, params.post_id, &params.content)
+            .edit_post(
+                &user_id,
+                &ip_addr,
+                params.post_id,
+                params.title.as_deref(),
+                params.content.as_deref(),
+                format,
+            )
             .await
     } else {
         Err(AuthError::PermissionDenied("您无权编辑此帖子").into())
@@ -258,115 +345,266 @@ pub fn delete_posts(
 
 #[derive(Debug, Clone, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
-pub struct ListPostsParams {
-    /// 板块id
+pub struct RestorePostParams {
+    /// 帖子Id
+    pub post_id: i32,
+}
+
+/// 恢复被删除的帖子或回复
+///
+/// 同一次删除的回帖会一并恢复
+#[utoipa::path(put, path = "/post/restore", tag = "Post", params(RestorePostParams))]
+#[forum_handler]
+pub async fn restore_post(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<RestorePostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+
+    if state
+        .post_service
+        .ensure_edit_posts_permission(user_id, &vec![params.post_id])
+        .await?
+    {
+        state
+            .post_service
+            .restore_post(user_id, &ip_addr, params.post_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权恢复该帖子").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct MovePostParams {
+    /// 主题帖Id
+    pub post_id: i32,
+
+    /// 目标板块id
     pub board_id: String,
+}
 
-    /// 标签序号
-    pub tags: String,
+/// 将主题帖移动到另一个板块
+///
+/// 所有回帖随主题帖一起移动，并通知发帖人
+#[utoipa::path(put, path = "/post/move", tag = "Post", params(MovePostParams))]
+#[forum_handler]
+pub async fn move_post(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<MovePostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
 
-    /// 是否显示隐藏帖子
-    pub show_hidden: bool,
+    if !state
+        .post_service
+        .ensure_query_board_permission(user_id, &params.board_id)
+        .await?
+    {
+        return Err(AuthError::PermissionDenied("您无权向该板块移动帖子").into());
+    }
 
-    /// 分页: 页面大小
-    pub page_size: u64,
+    if state
+        .post_service
+        .ensure_edit_posts_permission(user_id, &vec![params.post_id])
+        .await?
+    {
+        state
+            .post_service
+            .move_post(user_id, &ip_addr, params.post_id, &params.board_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权移动该帖子").into())
+    }
+}
 
-    /// 分页: 页面编号
-    pub page_index: u64,
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptAnswerParams {
+    /// 回帖Id
+    pub answer_id: i32,
 }
 
-#[derive(Debug, Clone, Serialize, ToResponse, ToSchema)]
+/// 采纳回帖作为答案
+///
+/// 仅提问者或助教可以操作，主题帖状态变为已解决
+#[utoipa::path(put, path = "/post/accept", tag = "Post", params(AcceptAnswerParams))]
+#[forum_handler]
+pub async fn accept_answer(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<AcceptAnswerParams>,
+) {
+    let user = auth_session.user.as_ref().unwrap();
+    let is_staff = auth_session
+        .backend
+        .has_perm(user, Permission::TA)
+        .await
+        .map_err(|_| ProcessError::GeneralError("验证权限失败"))?;
+
+    let user_id = &user.id();
+    if !state
+        .post_service
+        .ensure_query_post_permission(user_id, params.answer_id)
+        .await?
+    {
+        return Err(AuthError::PermissionDenied("您无权查看此帖子").into());
+    }
+    state
+        .post_service
+        .accept_answer(user_id, &ip_addr, params.answer_id, is_staff)
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
-pub struct ListPostsResult {
-    pub total_count: u64,
-    pub posts: Vec<post::Model>,
+pub struct UnacceptAnswerParams {
+    /// 主题帖Id
+    pub post_id: i32,
 }
 
-/// 列出帖子
+/// 取消采纳的答案
 #[utoipa::path(
-    get,
-    path = "/post/list",
+    delete,
+    path = "/post/accept",
     tag = "Post",
-    responses(
-        (status = 200, body = inline(ListPostsResult))
-    ),
-    params(ListPostsParams)
+    params(UnacceptAnswerParams)
 )]
 #[forum_handler]
-pub async fn list_posts(
+pub async fn unaccept_answer(
     State(state): State<PostState>,
     auth_session: AuthSession,
-    Query(params): Query<ListPostsParams>,
-) -> ListPostsResult {
-    let user_id = &auth_session.user.as_ref().unwrap().id();
-    let tags = urlencoding::decode(&params.tags).map_err(|_| InvalidParameter("传入的tag无效"))?;
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<UnacceptAnswerParams>,
+) {
+    let user = auth_session.user.as_ref().unwrap();
+    let is_staff = auth_session
+        .backend
+        .has_perm(user, Permission::TA)
+        .await
+        .map_err(|_| ProcessError::GeneralError("验证权限失败"))?;
 
-    if params.show_hidden
-        && !auth_session
-            .backend
-            .has_perm(auth_session.user.as_ref().unwrap(), Permission::TA)
-            .await
-            .map_err(|_| ProcessError::GeneralError("验证权限失败"))?
+    let user_id = &user.id();
+    if !state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
     {
-        return Err(AuthError::PermissionDenied("您无权查看隐藏帖子").into());
+        return Err(AuthError::PermissionDenied("您无权查看此帖子").into());
     }
+    state
+        .post_service
+        .unaccept_answer(user_id, &ip_addr, params.post_id, is_staff)
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosePostParams {
+    /// 主题帖Id
+    pub post_id: i32,
+
+    /// 关闭（true）或重新开放（false）
+    pub closed: bool,
+}
+
+/// 关闭或重新开放主题帖
+///
+/// 关闭后不能再回帖
+#[utoipa::path(put, path = "/post/close", tag = "Post", params(ClosePostParams))]
+#[forum_handler]
+pub async fn close_post(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<ClosePostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
 
     if state
         .post_service
-        .ensure_query_board_permission(&user_id, &params.board_id)
+        .ensure_query_post_permission(user_id, params.post_id)
         .await?
     {
-        Ok::<_, ApiError>(ListPostsResult {
-            total_count: state
-                .post_service
-                .get_posts_count(&params.board_id, &tags, params.show_hidden, false)
-                .await?,
-            posts: state
-                .post_service
-                .get_posts(
-                    &params.board_id,
-                    &tags,
-                    params.show_hidden,
-                    false,
-                    false,
-                    params.page_size,
-                    params.page_index,
-                )
-                .await?,
-        })
+        state
+            .post_service
+            .set_post_closed(user_id, &ip_addr, params.post_id, params.closed)
+            .await
     } else {
-        Err(AuthError::PermissionDenied("您无权查看本板块").into())
+        Err(AuthError::PermissionDenied("您无权关闭该帖子").into())
     }
 }
 
 #[derive(Debug, Clone, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
-pub struct GetPostsParams {
+pub struct PublishPostParams {
+    /// 主题帖Id
     pub post_id: i32,
-    pub show_hidden: bool,
+
+    /// 是否将提问者的帖子改为匿名（默认否）
+    #[serde(default)]
+    pub anonymize: bool,
 }
 
-/// 显示帖子（含回帖等）
+/// 公开私密提问
 ///
-/// 用于在帖子页中使用，包含回帖等内容
-#[utoipa::path(get, path = "/post", tag = "Post", params(GetPostsParams))]
+/// 将有参考价值的私密提问连同回帖公开给课程内所有用户
+#[utoipa::path(put, path = "/post/publish", tag = "Post", params(PublishPostParams))]
 #[forum_handler]
-pub async fn get_posts(
+pub async fn publish_post(
     State(state): State<PostState>,
     auth_session: AuthSession,
-    Query(params): Query<GetPostsParams>,
-) -> GetPostsResult {
-    if params.show_hidden
-        && !auth_session
-            .backend
-            .has_perm(auth_session.user.as_ref().unwrap(), Permission::TA)
-            .await
-            .map_err(|_| ProcessError::GeneralError("验证权限失败"))?
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<PublishPostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
     {
-        return Err(AuthError::PermissionDenied("您无权查看隐藏帖子").into());
+        state
+            .post_service
+            .publish_post(user_id, &ip_addr, params.post_id, params.anonymize)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权公开该帖子").into())
     }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAnnouncementReceiptParams {
+    /// 公告的主题帖Id
+    pub post_id: i32,
+}
 
+/// 查看课程公告的阅读回执
+///
+/// 列出选修该课程的学生中已读（按阅读时间排列）与未读（按学号排列）的学生
+#[utoipa::path(
+    get,
+    path = "/post/announcement/receipt",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(AnnouncementReceipt))
+    ),
+    params(GetAnnouncementReceiptParams)
+)]
+#[forum_handler]
+pub async fn get_announcement_receipt(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<GetAnnouncementReceiptParams>,
+) -> AnnouncementReceipt {
     let user_id = &auth_session.user.as_ref().unwrap().id();
+
     if state
         .post_service
         .ensure_query_post_permission(user_id, params.post_id)
@@ -374,7 +612,7 @@ pub async fn get_posts(
     {
         state
             .post_service
-            .get_post(params.post_id, params.show_hidden)
+            .get_announcement_receipt(params.post_id)
             .await
     } else {
         Err(AuthError::PermissionDenied("您无权查看此帖子").into())
@@ -383,18 +621,891 @@ pub async fn get_posts(
 
 #[derive(Debug, Clone, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
-pub struct GetPostParentParams {
+pub struct ListUnansweredParams {
+    /// 板块id（为空则列出所有有权查看的课程）
+    pub board_id: Option<String>,
+
+    /// 分页: 页面大小
+    pub page_size: u64,
+
+    /// 分页: 页面编号
+    pub page_index: u64,
+}
+
+/// 列出待回答的提问
+///
+/// 没有任何助教及以上用户回帖的提问，按发帖时间先后排列，并附带认领情况
+#[utoipa::path(
+    get,
+    path = "/post/unanswered",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(UnansweredQuestions))
+    ),
+    params(ListUnansweredParams)
+)]
+#[forum_handler]
+pub async fn list_unanswered_questions(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<ListUnansweredParams>,
+) -> UnansweredQuestions {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .post_service
+        .get_unanswered_questions(
+            user_id,
+            params.board_id.as_deref(),
+            params.page_size,
+            params.page_index,
+        )
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimPostParams {
+    /// 主题帖Id
     pub post_id: i32,
 }
 
-/// 查询帖子的父亲帖子
+/// 认领待回答的提问
 ///
-/// 用于跳转的时候准确跳转到父亲帖子
-#[utoipa::path(get, path = "/post/parent", tag = "Post", params(GetPostParentParams))]
+/// 同一帖子只能被一名助教认领
+#[utoipa::path(put, path = "/post/claim", tag = "Post", params(ClaimPostParams))]
 #[forum_handler]
-pub async fn get_post_parent(
+pub async fn claim_post(
     State(state): State<PostState>,
-    Query(params): Query<GetPostParentParams>,
-) -> Option<i32> {
-    state.post_service.get_parent_post(params.post_id).await
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<ClaimPostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
+    {
+        state
+            .post_service
+            .claim_post(user_id, &ip_addr, params.post_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看此帖子").into())
+    }
+}
+
+/// 取消认领
+#[utoipa::path(delete, path = "/post/claim", tag = "Post", params(ClaimPostParams))]
+#[forum_handler]
+pub async fn unclaim_post(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<ClaimPostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .post_service
+        .unclaim_post(user_id, &ip_addr, params.post_id)
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribePostParams {
+    /// 帖子Id（回帖则订阅其所在的主题帖）
+    pub post_id: i32,
+
+    /// 订阅方式
+    pub mode: SubscriptionMode,
+}
+
+/// 关注或屏蔽主题帖
+///
+/// 关注后主题帖中的任何回帖都会收到通知，屏蔽后不再收到该主题帖的任何通知。
+/// 发帖和回帖时会自动关注所在的主题帖
+#[utoipa::path(
+    put,
+    path = "/post/subscription",
+    tag = "Post",
+    params(SubscribePostParams)
+)]
+#[forum_handler]
+pub async fn subscribe_post(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<SubscribePostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
+    {
+        state
+            .post_service
+            .subscribe_post(user_id, params.post_id, params.mode)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看此帖子").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsubscribePostParams {
+    /// 帖子Id
+    pub post_id: i32,
+}
+
+/// 取消订阅主题帖
+///
+/// 取消后仅在自己的帖子被直接回复时收到通知
+#[utoipa::path(
+    delete,
+    path = "/post/subscription",
+    tag = "Post",
+    params(UnsubscribePostParams)
+)]
+#[forum_handler]
+pub async fn unsubscribe_post(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<UnsubscribePostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .post_service
+        .unsubscribe_post(user_id, params.post_id)
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSubscriptionsParams {
+    /// 分页: 页面大小
+    pub page_size: u64,
+
+    /// 分页: 页面编号
+    pub page_index: u64,
+}
+
+/// 列出我订阅的主题帖
+#[utoipa::path(
+    get,
+    path = "/post/subscription",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(SubscribedPosts))
+    ),
+    params(ListSubscriptionsParams)
+)]
+#[forum_handler]
+pub async fn list_subscriptions(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<ListSubscriptionsParams>,
+) -> SubscribedPosts {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .post_service
+        .get_user_subscriptions(user_id, params.page_size, params.page_index)
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardReadParams {
+    /// 板块id
+    pub board_id: String,
+}
+
+/// 将板块标记为已读
+///
+/// 板块内所有主题帖的现有回帖都视为已读
+#[utoipa::path(put, path = "/post/read", tag = "Post", params(BoardReadParams))]
+#[forum_handler]
+pub async fn mark_board_read(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<BoardReadParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    if state
+        .post_service
+        .ensure_query_board_permission(user_id, &params.board_id)
+        .await?
+    {
+        state
+            .post_service
+            .mark_board_read(user_id, &params.board_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看本板块").into())
+    }
+}
+
+/// 统计课程各板块的未读主题帖数
+///
+/// 传入课程中的任一板块id，返回该课程所有有未读帖子的板块，包括汇总板块
+#[utoipa::path(
+    get,
+    path = "/post/unread",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(Vec<BoardUnread>))
+    ),
+    params(BoardReadParams)
+)]
+#[forum_handler]
+pub async fn list_board_unread_counts(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<BoardReadParams>,
+) -> Vec<BoardUnread> {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    if state
+        .post_service
+        .ensure_query_board_permission(user_id, &params.board_id)
+        .await?
+    {
+        state
+            .post_service
+            .get_board_unread_counts(user_id, &params.board_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看本板块").into())
+    }
+}
+
+/// 彻底删除超过保留期限的已删除帖子
+///
+/// 返回删除的帖子数量
+#[utoipa::path(
+    delete,
+    path = "/post/purge",
+    tag = "Post",
+    responses(
+        (status = 200, body = u64)
+    )
+)]
+#[forum_handler]
+pub async fn purge_deleted_posts(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+) -> u64 {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .post_service
+        .purge_deleted_posts(user_id, &ip_addr)
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPostRevisionsParams {
+    /// 帖子Id
+    pub post_id: i32,
+}
+
+/// 列出帖子的修改历史
+///
+/// 每次编辑或回滚前的标题与内容都会保存为一个历史版本，列表中不含内容
+#[utoipa::path(
+    get,
+    path = "/post/revision",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(Vec<post_revision::Model>))
+    ),
+    params(ListPostRevisionsParams)
+)]
+#[forum_handler]
+pub async fn list_post_revisions(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<ListPostRevisionsParams>,
+) -> Vec<post_revision::Model> {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
+    {
+        state
+            .post_service
+            .list_post_revisions(user_id, params.post_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看此帖子").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffPostRevisionsParams {
+    /// 帖子Id
+    pub post_id: i32,
+
+    /// 较早的版本
+    pub from_rev_id: i32,
+
+    /// 较新的版本（为空则与当前版本比较）
+    pub to_rev_id: Option<i32>,
+}
+
+/// 比较帖子的两个版本
+///
+/// 正文转换为纯文本后比较
+#[utoipa::path(
+    get,
+    path = "/post/revision/diff",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(PostRevisionDiff))
+    ),
+    params(DiffPostRevisionsParams)
+)]
+#[forum_handler]
+pub async fn diff_post_revisions(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<DiffPostRevisionsParams>,
+) -> PostRevisionDiff {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
+    {
+        state
+            .post_service
+            .diff_post_revisions(params.post_id, params.from_rev_id, params.to_rev_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看此帖子").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackPostParams {
+    /// 帖子Id
+    pub post_id: i32,
+
+    /// 回滚到的历史版本
+    pub rev_id: i32,
+}
+
+/// 将帖子回滚到某个历史版本
+///
+/// 回滚前的内容同样会保存为历史版本
+#[utoipa::path(
+    put,
+    path = "/post/revision/rollback",
+    tag = "Post",
+    params(RollbackPostParams)
+)]
+#[forum_handler]
+pub async fn rollback_post(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    SecureClientIp(ip_addr): SecureClientIp,
+    Query(params): Query<RollbackPostParams>,
+) {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+
+    if state
+        .post_service
+        .ensure_edit_posts_permission(user_id, &vec![params.post_id])
+        .await?
+    {
+        state
+            .post_service
+            .rollback_post(user_id, &ip_addr, params.post_id, params.rev_id)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权回滚该帖子").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPostsParams {
+    /// 板块id
+    pub board_id: String,
+
+    /// 标签序号
+    pub tags: String,
+
+    /// 是否显示隐藏帖子
+    pub show_hidden: bool,
+
+    /// 主题帖状态（为空则不限）
+    pub status: Option<PostStatus>,
+
+    /// 排序方式
+    #[serde(default)]
+    pub sort: PostSort,
+
+    /// 分页: 上一页返回的游标（为空则按页面编号分页）
+    pub cursor: Option<String>,
+
+    /// 分页: 页面大小
+    pub page_size: u64,
+
+    /// 分页: 页面编号（使用游标时忽略，为空则为第一页）
+    pub page_index: Option<u64>,
+
+    /// 是否返回帖子总数（默认返回，数量会缓存一段时间）
+    pub with_count: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, ToResponse, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPostsResult {
+    /// 帖子总数，未要求时为空
+    pub total_count: Option<u64>,
+    pub posts: Vec<post::Model>,
+
+    /// 各主题帖的回帖概况与未读情况，与帖子一一对应
+    pub summaries: Vec<ThreadSummary>,
+
+    /// 下一页的游标，没有更多帖子时为空
+    pub next_cursor: Option<String>,
+}
+
+/// 列出帖子
+#[utoipa::path(
+    get,
+    path = "/post/list",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(ListPostsResult))
+    ),
+    params(ListPostsParams)
+)]
+#[forum_handler]
+pub async fn list_posts(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<ListPostsParams>,
+) -> ListPostsResult {
+    if params.page_index == Some(0) || params.page_size < 1 {
+        return Err(InvalidParameter("分页参数无效").into());
+    }
+
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    let tags = urlencoding::decode(&params.tags).map_err(|_| InvalidParameter("传入的tag无效"))?;
+
+    if params.show_hidden
+        && !auth_session
+            .backend
+            .has_perm(auth_session.user.as_ref().unwrap(), Permission::TA)
+            .await
+            .map_err(|_| ProcessError::GeneralError("验证权限失败"))?
+    {
+        return Err(AuthError::PermissionDenied("您无权查看隐藏帖子").into());
+    }
+
+    if state
+        .post_service
+        .ensure_query_board_permission(&user_id, &params.board_id)
+        .await?
+    {
+        let total_count = match params.with_count.unwrap_or(true) {
+            true => Some(
+                state
+                    .post_service
+                    .get_posts_count(
+                        user_id,
+                        &params.board_id,
+                        &tags,
+                        params.show_hidden,
+                        params.status,
+                        false,
+                    )
+                    .await?,
+            ),
+            false => None,
+        };
+        let page = state
+            .post_service
+            .get_posts(
+                user_id,
+                &params.board_id,
+                &tags,
+                params.show_hidden,
+                params.status,
+                false,
+                false,
+                params.sort,
+                params.cursor.as_deref(),
+                params.page_size,
+                params.page_index.unwrap_or(1),
+            )
+            .await?;
+        let summaries = state
+            .post_service
+            .get_thread_summaries(user_id, &page.posts)
+            .await?;
+
+        Ok::<_, ApiError>(ListPostsResult {
+            total_count,
+            posts: page.posts,
+            summaries,
+            next_cursor: page.next_cursor,
+        })
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看本板块").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPostsParams {
+    pub post_id: i32,
+    pub show_hidden: bool,
+}
+
+/// 显示帖子（含回帖等）
+///
+/// 用于在帖子页中使用，包含回帖等内容
+#[utoipa::path(get, path = "/post", tag = "Post", params(GetPostsParams))]
+#[forum_handler]
+pub async fn get_posts(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<GetPostsParams>,
+) -> GetPostsResult {
+    if params.show_hidden
+        && !auth_session
+            .backend
+            .has_perm(auth_session.user.as_ref().unwrap(), Permission::TA)
+            .await
+            .map_err(|_| ProcessError::GeneralError("验证权限失败"))?
+    {
+        return Err(AuthError::PermissionDenied("您无权查看隐藏帖子").into());
+    }
+
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
+    {
+        state
+            .post_service
+            .get_post(user_id, params.post_id, params.show_hidden)
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看此帖子").into())
+    }
+}
+
+/// 每层最多返回的子回帖数
+const MAX_TREE_PAGE_SIZE: u64 = 100;
+
+/// 帖子树最多展开的层数
+const MAX_TREE_DEPTH: u32 = 10;
+
+fn default_tree_page_size() -> u64 {
+    20
+}
+
+fn default_tree_depth() -> u32 {
+    3
+}
+
+fn default_context_siblings() -> u64 {
+    3
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPostTreeParams {
+    pub post_id: i32,
+
+    #[serde(default)]
+    pub show_hidden: bool,
+
+    /// 每个帖子最多返回的子回帖数（默认20，最大100）
+    #[serde(default = "default_tree_page_size")]
+    pub page_size: u64,
+
+    /// 上一页最后一个子回帖的id，用于继续获取本帖的子回帖
+    pub cursor: Option<i32>,
+
+    /// 展开的层数（默认3，最大10），超过的回帖只返回数量
+    #[serde(default = "default_tree_depth")]
+    pub max_depth: u32,
+}
+
+/// 以树形结构显示帖子
+///
+/// 每个帖子的子回帖分页返回，可以用帖子id与游标继续获取某一分支的回帖
+#[utoipa::path(
+    get,
+    path = "/post/tree",
+    tag = "Post",
+    responses(
+        (status = 200, body = PostTreeNode)
+    ),
+    params(GetPostTreeParams)
+)]
+#[forum_handler]
+pub async fn get_post_tree(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<GetPostTreeParams>,
+) -> PostTreeNode {
+    if !(1..=MAX_TREE_PAGE_SIZE).contains(&params.page_size)
+        || !(1..=MAX_TREE_DEPTH).contains(&params.max_depth)
+    {
+        return Err(InvalidParameter("分页参数无效").into());
+    }
+
+    if params.show_hidden
+        && !auth_session
+            .backend
+            .has_perm(auth_session.user.as_ref().unwrap(), Permission::TA)
+            .await
+            .map_err(|_| ProcessError::GeneralError("验证权限失败"))?
+    {
+        return Err(AuthError::PermissionDenied("您无权查看隐藏帖子").into());
+    }
+
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
+    {
+        state
+            .post_service
+            .get_post_tree(
+                user_id,
+                params.post_id,
+                params.show_hidden,
+                params.page_size,
+                params.cursor,
+                params.max_depth,
+            )
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看此帖子").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPostContextParams {
+    /// 定位的回帖id
+    pub post_id: i32,
+
+    #[serde(default)]
+    pub show_hidden: bool,
+
+    /// 定位回帖前后各返回的相邻回帖数（默认3）
+    #[serde(default = "default_context_siblings")]
+    pub siblings: u64,
+
+    /// 定位回帖最多返回的子回帖数（默认20，最大100）
+    #[serde(default = "default_tree_page_size")]
+    pub page_size: u64,
+}
+
+/// 定位到某个回帖
+///
+/// 用于从通知等处跳转到深层回帖，返回从主题帖到该回帖的路径
+#[utoipa::path(
+    get,
+    path = "/post/tree/context",
+    tag = "Post",
+    responses(
+        (status = 200, body = PostTreeContext)
+    ),
+    params(GetPostContextParams)
+)]
+#[forum_handler]
+pub async fn get_post_context(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<GetPostContextParams>,
+) -> PostTreeContext {
+    if !(1..=MAX_TREE_PAGE_SIZE).contains(&params.page_size) || params.siblings > MAX_TREE_PAGE_SIZE
+    {
+        return Err(InvalidParameter("分页参数无效").into());
+    }
+
+    if params.show_hidden
+        && !auth_session
+            .backend
+            .has_perm(auth_session.user.as_ref().unwrap(), Permission::TA)
+            .await
+            .map_err(|_| ProcessError::GeneralError("验证权限失败"))?
+    {
+        return Err(AuthError::PermissionDenied("您无权查看隐藏帖子").into());
+    }
+
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    if state
+        .post_service
+        .ensure_query_post_permission(user_id, params.post_id)
+        .await?
+    {
+        state
+            .post_service
+            .get_post_context(
+                user_id,
+                params.post_id,
+                params.show_hidden,
+                params.siblings,
+                params.page_size,
+            )
+            .await
+    } else {
+        Err(AuthError::PermissionDenied("您无权查看此帖子").into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPostParentParams {
+    pub post_id: i32,
+}
+
+/// 查询帖子的父亲帖子
+///
+/// 用于跳转的时候准确跳转到父亲帖子
+#[utoipa::path(get, path = "/post/parent", tag = "Post", params(GetPostParentParams))]
+#[forum_handler]
+pub async fn get_post_parent(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<GetPostParentParams>,
+) -> Option<i32> {
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .post_service
+        .get_parent_post(user_id, params.post_id)
+        .await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPostsParams {
+    /// 搜索关键词
+    pub query: String,
+
+    /// 板块id（为空则搜索所有有权查看的课程）
+    pub board_id: Option<String>,
+
+    /// 是否显示隐藏帖子
+    #[serde(default)]
+    pub show_hidden: bool,
+
+    /// 标签序号（同时带有这些标签）
+    pub tags: Option<String>,
+
+    /// 布置周
+    pub week: Option<i8>,
+
+    /// 作业序号
+    pub hw_id: Option<i16>,
+
+    /// 发帖人学号
+    pub author: Option<String>,
+
+    /// 帖子类型
+    pub post_type: Option<String>,
+
+    /// 主题帖状态
+    pub status: Option<PostStatus>,
+
+    /// 发帖时间不早于
+    pub date_from: Option<NaiveDateTime>,
+
+    /// 发帖时间不晚于
+    pub date_to: Option<NaiveDateTime>,
+
+    /// 排序方式
+    #[serde(default)]
+    pub sort: PostSearchSort,
+
+    /// 分页: 页面大小
+    pub page_size: u64,
+
+    /// 分页: 页面编号
+    pub page_index: u64,
+}
+
+/// 搜索帖子
+///
+/// 仅返回用户有权查看的课程中的帖子，包含高亮的标题和正文片段，
+/// 以及周次、作业、发帖人、类型和标签的分面统计
+#[utoipa::path(
+    get,
+    path = "/post/search",
+    tag = "Post",
+    responses(
+        (status = 200, body = inline(PostSearchResult))
+    ),
+    params(SearchPostsParams)
+)]
+#[forum_handler]
+pub async fn search_posts(
+    State(state): State<PostState>,
+    auth_session: AuthSession,
+    Query(params): Query<SearchPostsParams>,
+) -> PostSearchResult {
+    if params.page_index < 1 || params.page_size < 1 || params.page_size > 50 {
+        return Err(InvalidParameter("分页参数无效").into());
+    }
+
+    if params.show_hidden
+        && !auth_session
+            .backend
+            .has_perm(auth_session.user.as_ref().unwrap(), Permission::TA)
+            .await
+            .map_err(|_| ProcessError::GeneralError("验证权限失败"))?
+    {
+        return Err(AuthError::PermissionDenied("您无权查看隐藏帖子").into());
+    }
+
+    let tags = params
+        .tags
+        .as_deref()
+        .map(urlencoding::decode)
+        .transpose()
+        .map_err(|_| InvalidParameter("传入的tag无效"))?;
+    let filters = PostSearchFilters {
+        tags: tags.map(Into::into),
+        week: params.week,
+        hw_id: params.hw_id,
+        author: params.author,
+        post_type: params.post_type,
+        status: params.status,
+        date_from: params.date_from,
+        date_to: params.date_to,
+    };
+
+    let user_id = &auth_session.user.as_ref().unwrap().id();
+    state
+        .post_service
+        .search_posts(
+            user_id,
+            &params.query,
+            params.board_id.as_deref(),
+            params.show_hidden,
+            filters,
+            params.sort,
+            params.page_size,
+            params.page_index,
+        )
+        .await
 }
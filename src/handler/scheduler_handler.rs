@@ -0,0 +1,49 @@
+use axum::extract::{Query, State};
+use forum_macros::forum_handler;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    dto::scheduled_job::ScheduledJobInfo, service::scheduler_service::SchedulerServiceTrait,
+    state::scheduler_state::SchedulerState,
+};
+
+/// 查看所有定时任务
+///
+/// 返回配置中的任务及其上次运行、下次运行的时间与结果
+#[utoipa::path(
+    get,
+    path = "/scheduler/job",
+    tag = "Scheduler",
+    responses(
+        (status = 200, body = inline(Vec<ScheduledJobInfo>))
+    ),
+)]
+#[forum_handler]
+pub async fn list_jobs(State(state): State<SchedulerState>) -> Vec<ScheduledJobInfo> {
+    state.scheduler_service.list_jobs().await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerJobParams {
+    /// 任务名称
+    pub name: String,
+}
+
+/// 立即运行定时任务
+///
+/// 任务在后台运行，不影响原有的运行计划，可通过任务列表查询结果
+#[utoipa::path(
+    post,
+    path = "/scheduler/job/run",
+    tag = "Scheduler",
+    params(TriggerJobParams)
+)]
+#[forum_handler]
+pub async fn trigger_job(
+    State(state): State<SchedulerState>,
+    Query(params): Query<TriggerJobParams>,
+) {
+    state.scheduler_service.trigger_job(&params.name)
+}
@@ -0,0 +1,65 @@
+use axum::extract::{Query, State};
+use forum_macros::forum_handler;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    service::search_engine_service::{
+        IndexJob, IndexJobProgress, OutboxStatus, SearchEngineServiceTrait,
+    },
+    state::search_state::SearchState,
+};
+
+/// 查看搜索引擎同步队列
+///
+/// 返回队列深度以及同步失败的条目
+#[utoipa::path(
+    get,
+    path = "/search/outbox",
+    tag = "Search",
+    responses(
+        (status = 200, body = inline(OutboxStatus))
+    ),
+)]
+#[forum_handler]
+pub async fn get_outbox_status(State(state): State<SearchState>) -> OutboxStatus {
+    state.search_engine_service.get_outbox_status().await
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct StartIndexJobParams {
+    /// 任务类型（REINDEX：全量重建 RECONCILE：修复差异）
+    pub job: IndexJob,
+}
+
+/// 启动索引维护任务
+///
+/// 任务在后台运行，可通过进度接口查询
+#[utoipa::path(
+    post,
+    path = "/search/index-job",
+    tag = "Search",
+    params(StartIndexJobParams)
+)]
+#[forum_handler]
+pub async fn start_index_job(
+    State(state): State<SearchState>,
+    Query(params): Query<StartIndexJobParams>,
+) {
+    state.search_engine_service.spawn_index_job(params.job)
+}
+
+/// 查看索引维护任务的进度
+#[utoipa::path(
+    get,
+    path = "/search/index-job",
+    tag = "Search",
+    responses(
+        (status = 200, body = inline(IndexJobProgress))
+    ),
+)]
+#[forum_handler]
+pub async fn get_index_job_progress(State(state): State<SearchState>) -> IndexJobProgress {
+    Ok::<_, ApiError>(state.search_engine_service.get_index_job_progress())
+}
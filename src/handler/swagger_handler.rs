@@ -9,12 +9,17 @@ use utoipa::OpenApi;
         super::course_handler::get_my_courses,
         super::course_handler::get_my_courses_detail,
         super::course_handler::get_my_course_codes,
+        super::event_handler::stream_events,
         super::homework_handler::get::homework,
         super::homework_handler::get::homework_uploaded,
         super::homework_handler::post::homework_uploaded,
         super::metadata_handler::get::tags,
         super::notification_handler::get_my_notifications,
+        super::notification_handler::get_my_unread_count,
+        super::notification_handler::get_my_preferences,
+        super::notification_handler::set_my_preference,
         super::notification_handler::read_my_notifications,
+        super::notification_handler::read_target_notifications,
         super::notification_handler::read_all_my_notifications,
         super::notification_handler::delete_all_my_notifications,
         super::post_handler::set_post_tags,
@@ -23,9 +28,36 @@ use utoipa::OpenApi;
         super::post_handler::add_reply,
         super::post_handler::edit_post,
         super::post_handler::delete_posts,
+        super::post_handler::restore_post,
+        super::post_handler::move_post,
+        super::post_handler::accept_answer,
+        super::post_handler::unaccept_answer,
+        super::post_handler::close_post,
+        super::post_handler::publish_post,
+        super::post_handler::get_announcement_receipt,
+        super::post_handler::list_unanswered_questions,
+        super::post_handler::claim_post,
+        super::post_handler::unclaim_post,
+        super::post_handler::subscribe_post,
+        super::post_handler::unsubscribe_post,
+        super::post_handler::list_subscriptions,
+        super::post_handler::mark_board_read,
+        super::post_handler::list_board_unread_counts,
+        super::post_handler::purge_deleted_posts,
+        super::post_handler::list_post_revisions,
+        super::post_handler::diff_post_revisions,
+        super::post_handler::rollback_post,
         super::post_handler::list_posts,
         super::post_handler::get_posts,
+        super::post_handler::get_post_tree,
+        super::post_handler::get_post_context,
         super::post_handler::get_post_parent,
+        super::post_handler::search_posts,
+        super::scheduler_handler::list_jobs,
+        super::scheduler_handler::trigger_job,
+        super::search_handler::get_outbox_status,
+        super::search_handler::start_index_job,
+        super::search_handler::get_index_job_progress,
         super::upload_handler::add_image,
         super::user_handler::get_me,
         super::user_handler::get_my_info,
@@ -43,18 +75,50 @@ use utoipa::OpenApi;
             crate::entity::homework::Model,
             crate::entity::homework_uploaded::Model,
             crate::service::auth_service::Credentials,
+            crate::config::scheduler::JobKind,
             crate::dto::board::Board,
             crate::dto::course_tree::CourseTree,
+            crate::dto::forum_event::ForumEvent,
+            crate::dto::notification_page::NotificationPage,
+            crate::dto::notification_preference::NotificationMode,
+            crate::dto::notification_preference::NotificationPreference,
+            crate::dto::post_claim::UnansweredQuestion,
+            crate::dto::post_format::ContentFormat,
+            crate::dto::post_visibility::PostVisibility,
+            crate::dto::scheduled_job::JobStatus,
+            crate::dto::scheduled_job::ScheduledJobInfo,
+            crate::dto::post_list::PostSort,
+            crate::dto::post_list::ThreadSummary,
+            crate::dto::post_read::BoardUnread,
+            crate::dto::post_tree::PostTreeNode,
+            crate::dto::post_tree::PostTreeContext,
+            crate::dto::announcement::AnnouncementReader,
+            crate::dto::announcement::AnnouncementReceipt,
+            crate::dto::post_revision::DiffKind,
+            crate::dto::post_revision::DiffSegment,
+            crate::dto::post_search::PostSearchHit,
+            crate::dto::post_search::PostSearchSort,
+            crate::dto::post_status::PostStatus,
+            crate::dto::post_subscription::SubscribedPost,
+            crate::dto::post_subscription::SubscriptionMode,
+            crate::entity::post_claim::Model,
+            crate::entity::post_revision::Model,
+            crate::entity::post_subscription::Model,
+            crate::entity::search_outbox::Model,
+            crate::service::search_engine_service::IndexJob,
         )
     ),
     tags(
         (name = "Auth", description = "登录、验证相关API"),
         (name = "Board", description = "板块相关API"),
         (name = "Course", description = "课程相关API"),
+        (name = "Event", description = "实时事件相关API"),
         (name = "Homework", description = "作业相关API"),
         (name = "Metadata", description = "元数据相关API"),
         (name = "Notification", description = "通知相关API"),
         (name = "Post", description = "帖子相关API"),
+        (name = "Scheduler", description = "定时任务相关API"),
+        (name = "Search", description = "搜索引擎相关API"),
         (name = "Upload", description = "上传相关API"),
         (name = "User", description = "用户相关API"),
     )
@@ -7,10 +7,14 @@ use log::{error, info};
 use time::Duration;
 use tower_sessions::{Expiry, SessionManagerLayer};
 
-use crate::config::database::DatabaseTrait;
+use crate::config::database::{DatabaseTrait, Db};
 use crate::config::redis::RedisTrait;
-use crate::config::{database, meili, redis, s3, session};
+use crate::config::{database, migration, redis, s3, session};
+use crate::search::SearchBackend;
 use crate::service::auth_service::AuthBackend;
+use crate::service::search_engine_service::{
+    IndexJob, SearchEngineService, SearchEngineServiceTrait,
+};
 
 pub mod config;
 mod dto;
@@ -21,6 +25,7 @@ pub mod middleware;
 mod repository;
 mod response;
 mod routes;
+mod search;
 mod service;
 mod state;
 pub mod utils;
@@ -29,6 +34,28 @@ fn panic() -> ! {
     exit(1);
 }
 
+/// 命令行维护任务：`reindex`全量重建搜索索引，`reconcile`修复索引与数据库的差异
+async fn run_command(command: &str, db_conn: &Arc<Db>, search_backend: &Arc<dyn SearchBackend>) {
+    let job = match command {
+        "reindex" => IndexJob::Reindex,
+        "reconcile" => IndexJob::Reconcile,
+        _ => {
+            error!("未知的命令：{}，可用的命令有reindex、reconcile", command);
+            panic()
+        }
+    };
+
+    // 同步队列由正在运行的服务端处理，命令行进程不启动同步任务
+    let search_engine_service = SearchEngineService::without_worker(search_backend, db_conn);
+    match search_engine_service.run_index_job(job).await {
+        Ok(progress) => info!("索引维护任务完成：{:?}", progress),
+        Err(e) => {
+            error!("\n[Index Job Failed]\n索引维护任务失败\n\n{}", e);
+            panic()
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
@@ -42,6 +69,18 @@ async fn main() {
         });
     let db_conn = Arc::new(db_conn);
 
+    match migration::run(&db_conn).await {
+        Ok(executed) if !executed.is_empty() => info!("已执行数据库迁移：{:?}", executed),
+        Ok(_) => {}
+        Err(e) => {
+            error!(
+                "\n[Database Migration Failed]\n数据库迁移失败，请检查数据库权限与表结构\n\n{}",
+                e
+            );
+            panic()
+        }
+    }
+
     let redis_conn = Arc::new(redis::Redis::init().await.unwrap_or_else(|e| {
         error!("\n[Redis Connection Failed]\nRedis连接失败，请检查配置是否正确、网络连接情况和服务端配置\n\n{}",e);
         panic()
@@ -57,7 +96,18 @@ async fn main() {
         panic()
     }));
 
-    let meili_client = Arc::new(meili::Meili::init());
+    let search_backend = search::init_backend().unwrap_or_else(|e| {
+        error!(
+            "\n[Search Backend Init Failed]\n搜索后端初始化失败，请检查search配置是否正确\n\n{}",
+            e
+        );
+        panic()
+    });
+
+    if let Some(command) = std::env::args().nth(1) {
+        run_command(&command, &db_conn, &search_backend).await;
+        return;
+    }
 
     // Session层
     let session_store = session::RedisSession::new(&redis_conn);
@@ -92,7 +142,7 @@ async fn main() {
             Arc::clone(&db_conn),
             Arc::clone(&redis_conn),
             Arc::clone(&s3_client),
-            Arc::clone(&meili_client),
+            Arc::clone(&search_backend),
             auth_layer,
         ),
     )
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Local;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::announcement_read::{self, Column as Col, Entity, Model as AnnouncementRead};
+
+#[derive(Debug, Clone)]
+pub struct AnnouncementReadRepository {
+    db_conn: Arc<Db>,
+}
+
+impl AnnouncementReadRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// 删除公告的阅读回执，可在事务中调用
+    pub async fn delete_by_posts<C: ConnectionTrait>(
+        conn: &C,
+        post_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        Entity::delete_many()
+            .filter(Col::ArPostId.is_in(post_ids.iter().copied()))
+            .exec(conn)
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+pub trait AnnouncementReadRepositoryTrait {
+    type Error;
+
+    /// 记录用户打开了公告，已有记录时保留第一次打开的时间
+    async fn mark_read(&self, post_id: i32, user_id: &str) -> Result<(), Self::Error>;
+
+    /// 获取公告的所有阅读回执
+    async fn get_by_post(&self, post_id: i32) -> Result<Vec<AnnouncementRead>, Self::Error>;
+}
+
+#[async_trait]
+impl AnnouncementReadRepositoryTrait for AnnouncementReadRepository {
+    type Error = DbErr;
+
+    async fn mark_read(&self, post_id: i32, user_id: &str) -> Result<(), Self::Error> {
+        let record = announcement_read::ActiveModel {
+            ar_post_id: Set(post_id),
+            ar_sno: Set(user_id.to_string()),
+            ar_date: Set(Local::now().naive_local()),
+        };
+        Entity::insert(record)
+            .on_conflict(
+                OnConflict::columns([Col::ArPostId, Col::ArSno])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+
+    async fn get_by_post(&self, post_id: i32) -> Result<Vec<AnnouncementRead>, Self::Error> {
+        Entity::find()
+            .filter(Col::ArPostId.eq(post_id))
+            .all(self.db_conn.get_db())
+            .await
+    }
+}
@@ -1,7 +1,8 @@
 use crate::config::database::{DatabaseTrait, Db};
 use crate::entity::{log_login, log_post};
 use async_trait::async_trait;
-use sea_orm::{ActiveModelTrait, DbErr};
+use chrono::NaiveDateTime;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -24,6 +25,9 @@ pub trait LogRepositoryTrait {
     async fn add_login(&self, log: log_login::Model) -> Result<(), Self::Error>;
 
     async fn add_post(&self, log: log_post::Model) -> Result<(), Self::Error>;
+
+    /// 删除该时间之前的登录与发帖日志，返回删除的条数
+    async fn delete_all_before(&self, before: NaiveDateTime) -> Result<u64, Self::Error>;
 }
 
 #[async_trait]
@@ -49,4 +53,17 @@ impl LogRepositoryTrait for LogRepository {
         .await
         .map(|_| ())
     }
+
+    async fn delete_all_before(&self, before: NaiveDateTime) -> Result<u64, Self::Error> {
+        let db = self.db_conn.get_db();
+        let logins = log_login::Entity::delete_many()
+            .filter(log_login::Column::LogLoginDate.lt(before))
+            .exec(db)
+            .await?;
+        let posts = log_post::Entity::delete_many()
+            .filter(log_post::Column::LogPostDate.lt(before))
+            .exec(db)
+            .await?;
+        Ok(logins.rows_affected + posts.rows_affected)
+    }
 }
@@ -1,7 +1,17 @@
+pub mod announcement_read_repo;
 pub mod course_repo;
 pub mod homework_repo;
 pub mod log_repo;
+pub mod notification_pending_repo;
+pub mod notification_preference_repo;
 pub mod notification_repo;
+pub mod post_claim_repo;
+pub mod post_read_repo;
 pub mod post_repo;
+pub mod post_revision_repo;
+pub mod post_subscription_repo;
+pub mod scheduled_job_repo;
+pub mod search_outbox_repo;
+pub mod search_rebuild_repo;
 pub mod student_info_repo;
 pub mod user_repo;
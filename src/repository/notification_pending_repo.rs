@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel,
+    NotSet, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::notification_pending::{
+    self, Column as Col, Entity, Model as PendingNotification,
+};
+
+#[derive(Debug, Clone)]
+pub struct NotificationPendingRepository {
+    db_conn: Arc<Db>,
+}
+
+impl NotificationPendingRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// 删除已发送的通知，返回删除的数量，可在事务中调用
+    pub async fn delete_by_ids<C: ConnectionTrait>(conn: &C, ids: &[u64]) -> Result<u64, DbErr> {
+        Entity::delete_many()
+            .filter(Col::PendId.is_in(ids.iter().copied()))
+            .exec(conn)
+            .await
+            .map(|res| res.rows_affected)
+    }
+
+    /// 删除指向这些帖子的待合并通知，可在事务中调用
+    pub async fn delete_by_posts<C: ConnectionTrait>(
+        conn: &C,
+        post_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        Entity::delete_many()
+            .filter(
+                Condition::any()
+                    .add(Col::PendPostId.is_in(post_ids.iter().copied()))
+                    .add(Col::PendRootId.is_in(post_ids.iter().copied())),
+            )
+            .exec(conn)
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+pub trait NotificationPendingRepositoryTrait {
+    type Error;
+
+    /// 保存待合并的通知
+    async fn insert(&self, pending: PendingNotification) -> Result<(), Self::Error>;
+
+    /// 一次保存多条待合并的通知
+    async fn insert_many(&self, pending: Vec<PendingNotification>) -> Result<(), Self::Error>;
+
+    /// 获取有通知到期的接收者
+    async fn get_due_receivers(
+        &self,
+        now: NaiveDateTime,
+        limit: u64,
+    ) -> Result<Vec<String>, Self::Error>;
+
+    /// 获取这些接收者所有到期的通知，按产生顺序排列
+    async fn get_due_by_receivers(
+        &self,
+        receivers: &[String],
+        now: NaiveDateTime,
+    ) -> Result<Vec<PendingNotification>, Self::Error>;
+}
+
+#[async_trait]
+impl NotificationPendingRepositoryTrait for NotificationPendingRepository {
+    type Error = DbErr;
+
+    async fn insert(&self, pending: PendingNotification) -> Result<(), Self::Error> {
+        notification_pending::ActiveModel {
+            pend_id: NotSet,
+            ..pending.into_active_model()
+        }
+        .insert(self.db_conn.get_db())
+        .await
+        .map(|_| ())
+    }
+
+    async fn insert_many(&self, pending: Vec<PendingNotification>) -> Result<(), Self::Error> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let models = pending
+            .into_iter()
+            .map(|p| notification_pending::ActiveModel {
+                pend_id: NotSet,
+                ..p.into_active_model()
+            });
+        Entity::insert_many(models)
+            .exec_without_returning(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+
+    async fn get_due_receivers(
+        &self,
+        now: NaiveDateTime,
+        limit: u64,
+    ) -> Result<Vec<String>, Self::Error> {
+        Entity::find()
+            .select_only()
+            .column(Col::PendReceiver)
+            .distinct()
+            .filter(Col::PendDue.lte(now))
+            .limit(limit)
+            .into_tuple()
+            .all(self.db_conn.get_db())
+            .await
+    }
+
+    async fn get_due_by_receivers(
+        &self,
+        receivers: &[String],
+        now: NaiveDateTime,
+    ) -> Result<Vec<PendingNotification>, Self::Error> {
+        Entity::find()
+            .filter(Col::PendReceiver.is_in(receivers.iter().cloned()))
+            .filter(Col::PendDue.lte(now))
+            .order_by_asc(Col::PendId)
+            .all(self.db_conn.get_db())
+            .await
+    }
+}
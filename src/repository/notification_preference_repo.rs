@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Local;
+use sea_orm::{sea_query::OnConflict, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::notification_preference::{
+    self, Column as Col, Entity, Model as NotificationPreference,
+};
+
+#[derive(Debug, Clone)]
+pub struct NotificationPreferenceRepository {
+    db_conn: Arc<Db>,
+}
+
+impl NotificationPreferenceRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+}
+
+#[async_trait]
+pub trait NotificationPreferenceRepositoryTrait {
+    type Error;
+
+    /// 设置用户对一类通知的接收方式
+    async fn set(&self, user_id: &str, ntf_type: &str, mode: &str) -> Result<(), Self::Error>;
+
+    /// 获取用户对一类通知的设置，未设置时为空
+    async fn get(
+        &self,
+        user_id: &str,
+        ntf_type: &str,
+    ) -> Result<Option<NotificationPreference>, Self::Error>;
+
+    /// 获取用户的所有设置
+    async fn get_by_user(&self, user_id: &str) -> Result<Vec<NotificationPreference>, Self::Error>;
+
+    /// 获取这些用户对一类通知的设置，未设置的用户不返回
+    async fn get_by_users(
+        &self,
+        user_ids: &[String],
+        ntf_type: &str,
+    ) -> Result<Vec<NotificationPreference>, Self::Error>;
+}
+
+#[async_trait]
+impl NotificationPreferenceRepositoryTrait for NotificationPreferenceRepository {
+    type Error = DbErr;
+
+    async fn set(&self, user_id: &str, ntf_type: &str, mode: &str) -> Result<(), Self::Error> {
+        let preference = notification_preference::ActiveModel {
+            pref_sno: Set(user_id.to_string()),
+            pref_type: Set(ntf_type.to_string()),
+            pref_mode: Set(mode.to_string()),
+            pref_date: Set(Local::now().naive_local()),
+        };
+        Entity::insert(preference)
+            .on_conflict(
+                OnConflict::columns([Col::PrefSno, Col::PrefType])
+                    .update_columns([Col::PrefMode, Col::PrefDate])
+                    .to_owned(),
+            )
+            .exec_without_returning(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+
+    async fn get(
+        &self,
+        user_id: &str,
+        ntf_type: &str,
+    ) -> Result<Option<NotificationPreference>, Self::Error> {
+        Entity::find_by_id((user_id.to_string(), ntf_type.to_string()))
+            .one(self.db_conn.get_db())
+            .await
+    }
+
+    async fn get_by_user(&self, user_id: &str) -> Result<Vec<NotificationPreference>, Self::Error> {
+        Entity::find()
+            .filter(Col::PrefSno.eq(user_id))
+            .all(self.db_conn.get_db())
+            .await
+    }
+
+    async fn get_by_users(
+        &self,
+        user_ids: &[String],
+        ntf_type: &str,
+    ) -> Result<Vec<NotificationPreference>, Self::Error> {
+        Entity::find()
+            .filter(Col::PrefSno.is_in(user_ids.iter().cloned()))
+            .filter(Col::PrefType.eq(ntf_type))
+            .all(self.db_conn.get_db())
+            .await
+    }
+}
@@ -1,8 +1,12 @@
 use crate::config::database::{DatabaseTrait, Db};
-use crate::entity::notification::{Column as Cols, Entity, Model as Notification};
+use crate::entity::notification::{self, Column as Cols, Entity, Model as Notification};
 use crate::error::api_error::ApiError;
 use chrono::NaiveDateTime;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, NotSet,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -17,18 +21,117 @@ impl NotificationRepository {
         }
     }
 
+    /// 删除指向这些帖子的通知，可在事务中调用
+    pub async fn delete_by_posts<C: ConnectionTrait>(
+        conn: &C,
+        post_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        Entity::delete_many()
+            .filter(
+                Condition::any()
+                    .add(Cols::NtfPostId.is_in(post_ids.iter().copied()))
+                    .add(Cols::NtfRootId.is_in(post_ids.iter().copied())),
+            )
+            .exec(conn)
+            .await
+            .map(|_| ())
+    }
+
+    /// 分页获取用户的通知，`before`为上一页最后一条通知的id
+    ///
+    /// 通知id随发送时间递增，按id倒序即按时间从新到旧
     pub async fn find_all_by_receiver_order_by_date_time_desc(
         &self,
         receiver: &str,
+        unread_only: bool,
+        before: Option<u64>,
+        limit: u64,
+    ) -> Result<Vec<Notification>, ApiError> {
+        let mut query = Entity::find().filter(Cols::NtfReceiver.eq(receiver));
+        if unread_only {
+            query = query.filter(Cols::NtfRead.eq(false));
+        }
+        if let Some(before) = before {
+            query = query.filter(Cols::NtfId.lt(before));
+        }
+        query
+            .order_by_desc(Cols::NtfId)
+            .limit(limit)
+            .all(self.db_conn.get_db())
+            .await
+            .map_err(Into::into)
+    }
+
+    /// 一次写入多条通知
+    pub async fn insert_many(&self, notifications: Vec<Notification>) -> Result<(), ApiError> {
+        if notifications.is_empty() {
+            return Ok(());
+        }
+        let models = notifications
+            .into_iter()
+            .map(|n| notification::ActiveModel {
+                ntf_id: NotSet,
+                ..n.into_active_model()
+            });
+        Entity::insert_many(models)
+            .exec_without_returning(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// 查询这些用户收到的指向该帖子的某类通知
+    pub async fn find_all_by_receivers_and_post(
+        &self,
+        receivers: &[String],
+        ntf_type: &str,
+        post_id: i32,
     ) -> Result<Vec<Notification>, ApiError> {
         Entity::find()
-            .filter(Cols::NtfReceiver.eq(receiver))
-            .order_by_desc(Cols::NtfDatetime)
+            .filter(Cols::NtfReceiver.is_in(receivers.iter().cloned()))
+            .filter(Cols::NtfType.eq(ntf_type))
+            .filter(Cols::NtfPostId.eq(post_id))
             .all(self.db_conn.get_db())
             .await
             .map_err(Into::into)
     }
 
+    pub async fn count_unread_by_receiver(&self, receiver: &str) -> Result<u64, ApiError> {
+        Entity::find()
+            .filter(Cols::NtfReceiver.eq(receiver))
+            .filter(Cols::NtfRead.eq(false))
+            .count(self.db_conn.get_db())
+            .await
+            .map_err(Into::into)
+    }
+
+    /// 将用户指向某个帖子或主题帖的未读通知标为已读，两者都为空时不做修改
+    pub async fn read_all_by_receiver_and_target(
+        &self,
+        receiver: &str,
+        post_id: Option<i32>,
+        root_id: Option<i32>,
+    ) -> Result<u64, ApiError> {
+        if post_id.is_none() && root_id.is_none() {
+            return Ok(0);
+        }
+        let mut query = Entity::update_many()
+            .col_expr(Cols::NtfRead, Expr::value(true))
+            .filter(Cols::NtfReceiver.eq(receiver))
+            .filter(Cols::NtfRead.eq(false));
+        if let Some(post_id) = post_id {
+            query = query.filter(Cols::NtfPostId.eq(post_id));
+        }
+        if let Some(root_id) = root_id {
+            query = query.filter(Cols::NtfRootId.eq(root_id));
+        }
+        query
+            .exec(self.db_conn.get_db())
+            .await
+            .map(|result| result.rows_affected)
+            .map_err(Into::into)
+    }
+
     pub async fn delete_all_by_date_time_before(
         &self,
         before: NaiveDateTime,
@@ -47,7 +150,7 @@ impl NotificationRepository {
     ) -> Result<(), ApiError> {
         Entity::delete_many()
             .filter(Cols::NtfDatetime.lt(before))
-            .filter(Cols::NtfReceiver.eq(1))
+            .filter(Cols::NtfRead.eq(true))
             .exec(self.db_conn.get_db())
             .await
             .map(|_| ())
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Local;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::post_claim::{self, Column as Col, Entity, Model as Claim};
+
+#[derive(Debug, Clone)]
+pub struct PostClaimRepository {
+    db_conn: Arc<Db>,
+}
+
+impl PostClaimRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// 删除帖子的认领记录，可在事务中调用
+    pub async fn delete_by_posts<C: ConnectionTrait>(
+        conn: &C,
+        post_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        Entity::delete_many()
+            .filter(Col::ClaimPostId.is_in(post_ids.iter().copied()))
+            .exec(conn)
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+pub trait PostClaimRepositoryTrait {
+    type Error;
+
+    /// 认领帖子，帖子已被认领时返回`false`
+    async fn claim(&self, post_id: i32, user_id: &str) -> Result<bool, Self::Error>;
+
+    /// 取消用户对帖子的认领，返回是否存在该认领
+    async fn unclaim(&self, post_id: i32, user_id: &str) -> Result<bool, Self::Error>;
+
+    /// 获取帖子的认领记录
+    async fn get(&self, post_id: i32) -> Result<Option<Claim>, Self::Error>;
+
+    /// 获取多个帖子的认领记录
+    async fn get_many(&self, post_ids: &[i32]) -> Result<Vec<Claim>, Self::Error>;
+}
+
+#[async_trait]
+impl PostClaimRepositoryTrait for PostClaimRepository {
+    type Error = DbErr;
+
+    async fn claim(&self, post_id: i32, user_id: &str) -> Result<bool, Self::Error> {
+        let claim = post_claim::ActiveModel {
+            claim_post_id: Set(post_id),
+            claim_sno: Set(user_id.to_string()),
+            claim_date: Set(Local::now().naive_local()),
+        };
+        let rows = Entity::insert(claim)
+            .on_conflict(OnConflict::column(Col::ClaimPostId).do_nothing().to_owned())
+            .exec_without_returning(self.db_conn.get_db())
+            .await?;
+        Ok(rows > 0)
+    }
+
+    async fn unclaim(&self, post_id: i32, user_id: &str) -> Result<bool, Self::Error> {
+        let res = Entity::delete_many()
+            .filter(Col::ClaimPostId.eq(post_id))
+            .filter(Col::ClaimSno.eq(user_id))
+            .exec(self.db_conn.get_db())
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    async fn get(&self, post_id: i32) -> Result<Option<Claim>, Self::Error> {
+        Entity::find_by_id(post_id).one(self.db_conn.get_db()).await
+    }
+
+    async fn get_many(&self, post_ids: &[i32]) -> Result<Vec<Claim>, Self::Error> {
+        if post_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        Entity::find()
+            .filter(Col::ClaimPostId.is_in(post_ids.iter().copied()))
+            .all(self.db_conn.get_db())
+            .await
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::post_read::{Column as Col, Entity, Model as PostRead};
+
+#[derive(Debug, Clone)]
+pub struct PostReadRepository {
+    db_conn: Arc<Db>,
+}
+
+impl PostReadRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// 删除主题帖的阅读记录，返回被删除的记录，可在事务中调用
+    pub async fn delete_by_posts<C: ConnectionTrait>(
+        conn: &C,
+        post_ids: &[i32],
+    ) -> Result<Vec<PostRead>, DbErr> {
+        let records = Entity::find()
+            .filter(Col::ReadPostId.is_in(post_ids.iter().copied()))
+            .all(conn)
+            .await?;
+        Entity::delete_many()
+            .filter(Col::ReadPostId.is_in(post_ids.iter().copied()))
+            .exec(conn)
+            .await?;
+        Ok(records)
+    }
+}
+
+#[async_trait]
+pub trait PostReadRepositoryTrait {
+    type Error;
+
+    /// 获取用户在这些主题帖中的阅读记录
+    async fn get_many(&self, user_id: &str, post_ids: &[i32])
+        -> Result<Vec<PostRead>, Self::Error>;
+
+    /// 批量写入阅读记录，已有记录只会向更新的帖子推进
+    async fn upsert_many(&self, records: Vec<PostRead>) -> Result<(), Self::Error>;
+}
+
+#[async_trait]
+impl PostReadRepositoryTrait for PostReadRepository {
+    type Error = DbErr;
+
+    async fn get_many(
+        &self,
+        user_id: &str,
+        post_ids: &[i32],
+    ) -> Result<Vec<PostRead>, Self::Error> {
+        if post_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        Entity::find()
+            .filter(Col::ReadSno.eq(user_id))
+            .filter(Col::ReadPostId.is_in(post_ids.iter().copied()))
+            .all(self.db_conn.get_db())
+            .await
+    }
+
+    async fn upsert_many(&self, records: Vec<PostRead>) -> Result<(), Self::Error> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        Entity::insert_many(records.into_iter().map(IntoActiveModel::into_active_model))
+            .on_conflict(
+                OnConflict::columns([Col::ReadSno, Col::ReadPostId])
+                    .value(
+                        Col::ReadLastId,
+                        Expr::cust("greatest(read_last_id, values(read_last_id))"),
+                    )
+                    .update_column(Col::ReadDate)
+                    .to_owned(),
+            )
+            .exec_without_returning(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+}
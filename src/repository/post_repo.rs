@@ -1,13 +1,22 @@
 use std::{sync::Arc, vec};
 
 use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
 use sea_orm::{
-    ColumnTrait, Condition, DbBackend, EntityTrait, FromQueryResult, JsonValue, Order,
-    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, Statement,
+    ColumnTrait, Condition, ConnectionTrait, DbBackend, DbErr, EntityTrait, FromQueryResult,
+    IntoSimpleExpr, JsonValue, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select,
+    Statement,
 };
 
 use crate::{
     config::database::{DatabaseTrait, Db},
+    dto::{
+        post_anonymity::ALIAS_PREFIX,
+        post_list::{PostCursor, PostSort, ThreadSummary},
+        post_visibility::PostVisibility,
+        student_short_info::StudentShortInfo,
+    },
     entity::post::{Column as Col, Entity, Model as Post},
 };
 
@@ -22,8 +31,12 @@ pub trait PostRepositoryTrait {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error>;
@@ -36,8 +49,12 @@ pub trait PostRepositoryTrait {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error>;
@@ -50,8 +67,12 @@ pub trait PostRepositoryTrait {
         homework_id: i16,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error>;
@@ -63,8 +84,12 @@ pub trait PostRepositoryTrait {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error>;
@@ -77,8 +102,12 @@ pub trait PostRepositoryTrait {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error>;
@@ -90,6 +119,8 @@ pub trait PostRepositoryTrait {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error>;
 
@@ -101,6 +132,8 @@ pub trait PostRepositoryTrait {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error>;
 
@@ -112,6 +145,8 @@ pub trait PostRepositoryTrait {
         homework_id: i16,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error>;
 
@@ -122,6 +157,8 @@ pub trait PostRepositoryTrait {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error>;
 
@@ -133,18 +170,65 @@ pub trait PostRepositoryTrait {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error>;
 
     /// 获取帖子但不包含内容
     async fn get_post_without_content(&self, post_id: i32) -> Result<Option<Post>, Self::Error>;
 
+    /// 获取多个帖子但不包含内容
+    async fn get_posts_without_content(&self, post_ids: &[i32]) -> Result<Vec<Post>, Self::Error>;
+
     /// 递归查询某个帖子旗下的子帖子
     async fn get_posts_recursively(&self, post_id: i32) -> Result<Vec<Post>, Self::Error>;
 
     /// 递归查询某个帖子的父帖子
     async fn get_parent_post_recursively(&self, post_id: i32) -> Result<Option<Post>, Self::Error>;
 
+    /// 查询删除时间早于指定时间的帖子id
+    async fn get_deleted_post_ids_before(
+        &self,
+        before: NaiveDateTime,
+    ) -> Result<Vec<i32>, Self::Error>;
+
+    /// 查询这些课程中没有助教及以上用户回帖的提问，按发帖时间先后排列
+    async fn get_unanswered_questions(
+        &self,
+        courses: &[(String, String)],
+        staff_level: i32,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Post>, Self::Error>;
+
+    /// 查询这些课程中没有助教及以上用户回帖的提问数量
+    async fn get_unanswered_questions_count(
+        &self,
+        courses: &[(String, String)],
+        staff_level: i32,
+    ) -> Result<u64, Self::Error>;
+
+    /// 统计这些主题帖的回帖概况，没有回帖的主题帖不返回
+    async fn get_thread_summaries(
+        &self,
+        root_ids: &[i32],
+        staff_level: i32,
+    ) -> Result<Vec<ThreadSummary>, Self::Error>;
+
+    /// 统计这些主题帖中id大于已读位置的未删除回帖数，参数为主题帖id与已读到的帖子id
+    async fn count_replies_after(
+        &self,
+        read_positions: &[(i32, i32)],
+    ) -> Result<Vec<(i32, i64)>, Self::Error>;
+
+    /// 获取课程中所有未删除的主题帖（不含内容）
+    async fn get_course_threads(
+        &self,
+        term: &str,
+        course_code: &str,
+    ) -> Result<Vec<Post>, Self::Error>;
+
     /// 查询指定帖子的发帖用户等级
     async fn get_post_sender_user_level(&self, post_id: i32) -> Result<Option<i64>, Self::Error>;
 
@@ -155,6 +239,44 @@ pub trait PostRepositoryTrait {
     ) -> Result<Option<i64>, Self::Error>;
 }
 
+/// 主题帖回帖概况的查询结果
+#[derive(Debug, FromQueryResult)]
+struct ThreadSummaryRow {
+    root_id: i32,
+    reply_count: i64,
+    last_reply_date: Option<NaiveDateTime>,
+    staff_replied: Option<i64>,
+    last_replier_alias: Option<String>,
+    nick_name: Option<String>,
+    real_name: Option<String>,
+    description: Option<String>,
+    stu_no: Option<String>,
+    major: Option<String>,
+    role: Option<String>,
+}
+
+impl From<ThreadSummaryRow> for ThreadSummary {
+    fn from(row: ThreadSummaryRow) -> Self {
+        let last_replier = row.stu_no.map(|stu_no| StudentShortInfo {
+            nick_name: row.nick_name.unwrap_or_default(),
+            real_name: row.real_name.unwrap_or_default(),
+            description: row.description.unwrap_or_default(),
+            stu_no,
+            major: row.major.unwrap_or_default(),
+            role: row.role.unwrap_or_default(),
+        });
+        Self {
+            post_id: row.root_id,
+            reply_count: row.reply_count,
+            last_reply_date: row.last_reply_date,
+            last_replier,
+            last_replier_alias: row.last_replier_alias,
+            staff_replied: row.staff_replied.unwrap_or_default() > 0,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct PostRepository {
     db: Arc<Db>,
@@ -165,6 +287,48 @@ impl PostRepository {
         Self { db: Arc::clone(db) }
     }
 
+    /// 重新统计这些主题帖的回帖数、最后活跃时间与最新帖子id，在发帖、回帖、删除或恢复帖子的事务中调用
+    pub async fn refresh_thread_stats<C: ConnectionTrait>(
+        conn: &C,
+        root_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        if root_ids.is_empty() {
+            return Ok(());
+        }
+
+        let sql = format!(
+            r#"
+        update post p
+            join (with recursive thread as (select post_id as root_id, post_id
+                                            from post
+                                            where post_id in ({})
+                                            union all
+                                            select thread.root_id, post.post_id
+                                            from post
+                                                    join thread on post.post_answer_id = thread.post_id)
+                  select thread.root_id,
+                         sum(reply.post_id <> thread.root_id and reply.post_is_del = '0') as reply_count,
+                         max(if(reply.post_is_del = '0', reply.post_date, null)) as active_date,
+                         max(if(reply.post_is_del = '0', reply.post_id, null)) as last_id
+                  from thread
+                          join post reply on reply.post_id = thread.post_id
+                  group by thread.root_id) stats on stats.root_id = p.post_id
+        set p.post_reply_count = stats.reply_count,
+            p.post_active_date = coalesce(stats.active_date, p.post_date),
+            p.post_last_id = coalesce(stats.last_id, p.post_id)
+        "#,
+            vec!["?"; root_ids.len()].join(", ")
+        );
+
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            sql,
+            root_ids.iter().map(|&id| id.into()).collect::<Vec<_>>(),
+        ))
+        .await?;
+        Ok(())
+    }
+
     fn select_head(with_content: bool) -> Select<Entity> {
         Entity::find().select_only().columns({
             let mut cols = vec![
@@ -177,6 +341,8 @@ impl PostRepository {
                 Col::PostAnswerId,
                 Col::PostType,
                 Col::PostSenderNo,
+                Col::PostAnonymous,
+                Col::PostAlias,
                 Col::PostPriority,
                 Col::PostTag01,
                 Col::PostTag02,
@@ -189,18 +355,129 @@ impl PostRepository {
                 Col::PostTag09,
                 Col::PostTag10,
                 Col::PostTitle,
+                Col::PostFormat,
                 Col::PostDate,
                 Col::PostIsDel,
+                Col::PostDelDate,
+                Col::PostDelBatch,
+                Col::PostStatus,
+                Col::PostVisibility,
+                Col::PostPrivateSno,
+                Col::PostAcceptedId,
+                Col::PostReplyCount,
+                Col::PostActiveDate,
+                Col::PostLastId,
                 Col::PostComment,
             ];
             if with_content {
-                cols.push(Col::PostContent)
+                cols.push(Col::PostContent);
+                cols.push(Col::PostSource);
             }
             cols
         })
     }
 
-    fn select_filter(tag_names: Vec<&str>, show_hidden: bool, with_repies: bool) -> Condition {
+    /// 发帖人在主题帖中的匿名称呼，已匿名发过帖则沿用，否则按匿名发帖人的先后顺序编号
+    ///
+    /// 在发帖的事务中调用，锁定主题帖以免并发时编号重复
+    pub async fn thread_alias<C: ConnectionTrait>(
+        conn: &C,
+        root_id: i32,
+        sender_no: &str,
+    ) -> Result<String, DbErr> {
+        Entity::find_by_id(root_id)
+            .select_only()
+            .column(Col::PostId)
+            .lock_exclusive()
+            .into_tuple::<i32>()
+            .one(conn)
+            .await?;
+
+        let sql = r#"
+        with recursive thread as (select post_id, post_sno, post_alias
+                    from post
+                    where post_id = ?
+                    union all
+                    select post.post_id, post.post_sno, post.post_alias
+                    from post
+                            join thread on post.post_answer_id = thread.post_id)
+        select distinct post_sno, post_alias
+        from thread
+        where post_alias is not null
+        "#;
+        let aliases = JsonValue::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            sql,
+            [root_id.into()],
+        ))
+        .all(conn)
+        .await?;
+
+        let existing = aliases
+            .iter()
+            .find(|row| row["post_sno"].as_str() == Some(sender_no))
+            .and_then(|row| row["post_alias"].as_str());
+        Ok(match existing {
+            Some(alias) => alias.to_string(),
+            None => format!("{}{}", ALIAS_PREFIX, aliases.len() + 1),
+        })
+    }
+
+    /// 未被助教回答的提问的查询语句，`columns`为查询的列
+    ///
+    /// 参数依次为各课程的学期与课程代码、助教的用户等级
+    fn unanswered_questions_sql(courses_len: usize, columns: &str) -> String {
+        format!(
+            r#"
+        with recursive thread as (select post_id as root_id, post_id
+                    from post
+                    where post_answer_id is null
+                      and post_type = 'Question'
+                      and post_is_del = '0'
+                      and (post_status is null or post_status <> 'CLOSED')
+                      and (post_term, post_ccode) in ({})
+                    union all
+                    select thread.root_id, post.post_id
+                    from post
+                            join thread on post.post_answer_id = thread.post_id)
+        select {}
+        from post
+        where post_id in (select root_id from thread)
+          and post_id not in (select thread.root_id
+                    from thread
+                            join post reply on reply.post_id = thread.post_id
+                    where thread.post_id <> thread.root_id
+                      and reply.post_is_del = '0'
+                      and exists(select 1
+                                from student s
+                                where s.stu_no = reply.post_sno
+                                  and cast(s.stu_userlevel as signed) >= ?))
+        "#,
+            vec!["(?, ?)"; courses_len].join(", "),
+            columns
+        )
+    }
+
+    fn unanswered_questions_values(
+        courses: &[(String, String)],
+        staff_level: i32,
+    ) -> Vec<sea_orm::Value> {
+        let mut values: Vec<sea_orm::Value> = courses
+            .iter()
+            .flat_map(|(term, code)| [term.as_str().into(), code.as_str().into()])
+            .collect();
+        values.push(staff_level.into());
+        values
+    }
+
+    /// 列出帖子的过滤条件，`visible_to`不为空时只包含公开帖子与该用户的私密帖子
+    fn select_filter(
+        tag_names: Vec<&str>,
+        show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
+        with_repies: bool,
+    ) -> Condition {
         let mut condition = Condition::all();
         if !tag_names.is_empty() {
             for tag_name in tag_names {
@@ -212,6 +489,16 @@ impl PostRepository {
         if !show_hidden {
             condition = condition.add(Col::PostIsDel.eq("0"))
         }
+        if let Some(user_id) = visible_to {
+            condition = condition.add(
+                Condition::any()
+                    .add(Col::PostVisibility.eq(PostVisibility::Public.as_str()))
+                    .add(Col::PostPrivateSno.eq(user_id)),
+            )
+        }
+        if let Some(status) = status {
+            condition = condition.add(Col::PostStatus.eq(status))
+        }
         if !with_repies {
             condition = condition.add(Col::PostAnswerId.is_null())
         }
@@ -226,6 +513,108 @@ trait SelectConsumer {
     async fn select_consumer_many(self, db_conn: &Arc<Db>) -> Result<Vec<Post>, Self::Error>;
 }
 
+trait SelectPaginate {
+    /// 按排序方式分页，有游标时从游标之后开始，否则按偏移量
+    fn paginate_by(
+        self,
+        sort: PostSort,
+        after: Option<&PostCursor>,
+        limit: u64,
+        offset: u64,
+    ) -> Self;
+}
+
+/// 排序列为空时按以下值参与排序与游标比较，避免与NULL比较使翻页中断
+const NULL_PRIORITY: &str = "";
+const NULL_REPLY_COUNT: i32 = 0;
+
+/// 发帖时间等为空时按MySQL的最小时间排序
+fn null_date() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(1000, 1, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .unwrap()
+}
+
+/// 排序键，可为空的列替换为`coalesce(列, 默认值)`
+fn sort_key(col: Col) -> SimpleExpr {
+    let fallback: sea_orm::Value = match col {
+        Col::PostPriority => NULL_PRIORITY.into(),
+        Col::PostDate | Col::PostActiveDate => null_date().into(),
+        Col::PostReplyCount => NULL_REPLY_COUNT.into(),
+        _ => return col.into_simple_expr(),
+    };
+    Func::coalesce([col.into_simple_expr(), Expr::val(fallback).into()]).into()
+}
+
+impl SelectPaginate for Select<Entity> {
+    fn paginate_by(
+        self,
+        sort: PostSort,
+        after: Option<&PostCursor>,
+        limit: u64,
+        offset: u64,
+    ) -> Self {
+        // 最后一列为帖子id，保证顺序唯一
+        let (keys, order) = match sort {
+            PostSort::PriorityFirst => (
+                vec![Col::PostPriority, Col::PostDate, Col::PostId],
+                Order::Desc,
+            ),
+            PostSort::Newest => (vec![Col::PostDate, Col::PostId], Order::Desc),
+            PostSort::Oldest => (vec![Col::PostDate, Col::PostId], Order::Asc),
+            PostSort::LastActivity => (vec![Col::PostActiveDate, Col::PostId], Order::Desc),
+            PostSort::MostReplies => (vec![Col::PostReplyCount, Col::PostId], Order::Desc),
+        };
+
+        let mut select = match after {
+            Some(cursor) => {
+                let date = cursor.date.unwrap_or_else(null_date);
+                let values: Vec<sea_orm::Value> = match sort {
+                    PostSort::PriorityFirst => vec![
+                        cursor
+                            .priority
+                            .clone()
+                            .unwrap_or_else(|| NULL_PRIORITY.to_string())
+                            .into(),
+                        date.into(),
+                        cursor.post_id.into(),
+                    ],
+                    PostSort::Newest | PostSort::Oldest | PostSort::LastActivity => {
+                        vec![date.into(), cursor.post_id.into()]
+                    }
+                    PostSort::MostReplies => vec![
+                        cursor.reply_count.unwrap_or(NULL_REPLY_COUNT).into(),
+                        cursor.post_id.into(),
+                    ],
+                };
+
+                // (a, b) < (x, y) 展开为 a < x or (a = x and b < y)
+                let mut condition: Option<Condition> = None;
+                for (&key, value) in keys.iter().zip(values).rev() {
+                    let beyond = match order {
+                        Order::Asc => Expr::expr(sort_key(key)).gt(value.clone()),
+                        _ => Expr::expr(sort_key(key)).lt(value.clone()),
+                    };
+                    condition = Some(match condition {
+                        None => Condition::all().add(beyond),
+                        Some(rest) => Condition::any().add(beyond).add(
+                            Condition::all()
+                                .add(Expr::expr(sort_key(key)).eq(value))
+                                .add(rest),
+                        ),
+                    });
+                }
+                self.filter(condition.unwrap())
+            }
+            None => self.offset(offset),
+        };
+        for key in keys {
+            select = select.order_by(sort_key(key), order.clone());
+        }
+        select.limit(limit)
+    }
+}
+
 impl SelectConsumer for Select<Entity> {
     type Error = sea_orm::DbErr;
 
@@ -249,8 +638,12 @@ impl PostRepositoryTrait for PostRepository {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error> {
@@ -260,11 +653,14 @@ impl PostRepositoryTrait for PostRepository {
             .filter(Col::PostHwId.eq(-1))
             .filter(Col::PostChapter.eq(-1))
             .filter(Col::PostWeek.eq(-1))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
-            .order_by(Col::PostPriority, Order::Desc)
-            .order_by(Col::PostId, Order::Desc)
-            .offset(offset)
-            .limit(limit)
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
+            .paginate_by(sort, after, limit, offset)
             .select_consumer_many(&self.db)
             .await
     }
@@ -277,8 +673,12 @@ impl PostRepositoryTrait for PostRepository {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error> {
@@ -287,11 +687,14 @@ impl PostRepositoryTrait for PostRepository {
             .filter(Col::PostCourseCode.eq(course_code))
             .filter(Col::PostHwId.eq(-1))
             .filter(Col::PostWeek.eq(week))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
-            .order_by(Col::PostPriority, Order::Desc)
-            .order_by(Col::PostId, Order::Desc)
-            .offset(offset)
-            .limit(limit)
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
+            .paginate_by(sort, after, limit, offset)
             .select_consumer_many(&self.db)
             .await
     }
@@ -304,8 +707,12 @@ impl PostRepositoryTrait for PostRepository {
         homework_id: i16,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error> {
@@ -313,11 +720,14 @@ impl PostRepositoryTrait for PostRepository {
             .filter(Col::PostTerm.eq(term))
             .filter(Col::PostCourseCode.eq(course_code))
             .filter(Col::PostHwId.eq(homework_id))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
-            .order_by(Col::PostPriority, Order::Desc)
-            .order_by(Col::PostId, Order::Desc)
-            .offset(offset)
-            .limit(limit)
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
+            .paginate_by(sort, after, limit, offset)
             .select_consumer_many(&self.db)
             .await
     }
@@ -329,19 +739,26 @@ impl PostRepositoryTrait for PostRepository {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error> {
         Self::select_head(with_content)
             .filter(Col::PostTerm.eq(term))
             .filter(Col::PostCourseCode.eq(course_code))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
-            .order_by(Col::PostPriority, Order::Desc)
-            .order_by(Col::PostId, Order::Desc)
-            .offset(offset)
-            .limit(limit)
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
+            .paginate_by(sort, after, limit, offset)
             .select_consumer_many(&self.db)
             .await
     }
@@ -354,8 +771,12 @@ impl PostRepositoryTrait for PostRepository {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        after: Option<&PostCursor>,
         limit: u64,
         offset: u64,
     ) -> Result<Vec<Post>, Self::Error> {
@@ -363,11 +784,14 @@ impl PostRepositoryTrait for PostRepository {
             .filter(Col::PostTerm.eq(term))
             .filter(Col::PostCourseCode.eq(course_code))
             .filter(Col::PostWeek.eq(week))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
-            .order_by(Col::PostPriority, Order::Desc)
-            .order_by(Col::PostId, Order::Desc)
-            .offset(offset)
-            .limit(limit)
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
+            .paginate_by(sort, after, limit, offset)
             .select_consumer_many(&self.db)
             .await
     }
@@ -379,6 +803,8 @@ impl PostRepositoryTrait for PostRepository {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error> {
         Entity::find()
@@ -387,7 +813,13 @@ impl PostRepositoryTrait for PostRepository {
             .filter(Col::PostHwId.eq(-1))
             .filter(Col::PostChapter.eq(-1))
             .filter(Col::PostWeek.eq(-1))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
             .count(self.db.get_db())
             .await
     }
@@ -400,6 +832,8 @@ impl PostRepositoryTrait for PostRepository {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error> {
         Entity::find()
@@ -407,7 +841,13 @@ impl PostRepositoryTrait for PostRepository {
             .filter(Col::PostCourseCode.eq(course_code))
             .filter(Col::PostHwId.eq(-1))
             .filter(Col::PostWeek.eq(week))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
             .count(self.db.get_db())
             .await
     }
@@ -420,13 +860,21 @@ impl PostRepositoryTrait for PostRepository {
         homework_id: i16,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error> {
         Entity::find()
             .filter(Col::PostTerm.eq(term))
             .filter(Col::PostCourseCode.eq(course_code))
             .filter(Col::PostHwId.eq(homework_id))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
             .count(self.db.get_db())
             .await
     }
@@ -438,12 +886,20 @@ impl PostRepositoryTrait for PostRepository {
         course_code: &str,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error> {
         Entity::find()
             .filter(Col::PostTerm.eq(term))
             .filter(Col::PostCourseCode.eq(course_code))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
             .count(self.db.get_db())
             .await
     }
@@ -456,13 +912,21 @@ impl PostRepositoryTrait for PostRepository {
         week: i8,
         tag_names: Vec<&str>,
         show_hidden: bool,
+        visible_to: Option<&str>,
+        status: Option<&str>,
         with_replies: bool,
     ) -> Result<u64, Self::Error> {
         Entity::find()
             .filter(Col::PostTerm.eq(term))
             .filter(Col::PostCourseCode.eq(course_code))
             .filter(Col::PostWeek.eq(week))
-            .filter(Self::select_filter(tag_names, show_hidden, with_replies))
+            .filter(Self::select_filter(
+                tag_names,
+                show_hidden,
+                visible_to,
+                status,
+                with_replies,
+            ))
             .count(self.db.get_db())
             .await
     }
@@ -475,6 +939,17 @@ impl PostRepositoryTrait for PostRepository {
             .await
     }
 
+    /// 获取多个帖子但不包含内容
+    async fn get_posts_without_content(&self, post_ids: &[i32]) -> Result<Vec<Post>, Self::Error> {
+        if post_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        Self::select_head(false)
+            .filter(Col::PostId.is_in(post_ids.iter().copied()))
+            .select_consumer_many(&self.db)
+            .await
+    }
+
     /// 递归查询某个帖子旗下的子帖子
     async fn get_posts_recursively(&self, post_id: i32) -> Result<Vec<Post>, Self::Error> {
         let sql = r#"
@@ -528,6 +1003,211 @@ impl PostRepositoryTrait for PostRepository {
             .await
     }
 
+    /// 查询删除时间早于指定时间的帖子id
+    async fn get_deleted_post_ids_before(
+        &self,
+        before: NaiveDateTime,
+    ) -> Result<Vec<i32>, Self::Error> {
+        Entity::find()
+            .select_only()
+            .column(Col::PostId)
+            .filter(Col::PostIsDel.eq("1"))
+            .filter(Col::PostDelDate.lt(before))
+            .into_tuple()
+            .all(self.db.get_db())
+            .await
+    }
+
+    /// 查询这些课程中没有助教及以上用户回帖的提问，按发帖时间先后排列
+    async fn get_unanswered_questions(
+        &self,
+        courses: &[(String, String)],
+        staff_level: i32,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Post>, Self::Error> {
+        if courses.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = Self::unanswered_questions_sql(courses.len(), "*")
+            + "order by post_date, post_id limit ? offset ?";
+        let mut values = Self::unanswered_questions_values(courses, staff_level);
+        values.extend([limit.into(), offset.into()]);
+
+        Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::MySql,
+                sql,
+                values,
+            ))
+            .all(self.db.get_db())
+            .await
+    }
+
+    /// 查询这些课程中没有助教及以上用户回帖的提问数量
+    async fn get_unanswered_questions_count(
+        &self,
+        courses: &[(String, String)],
+        staff_level: i32,
+    ) -> Result<u64, Self::Error> {
+        if courses.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = Self::unanswered_questions_sql(courses.len(), "count(*) as count");
+        let result = JsonValue::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            sql,
+            Self::unanswered_questions_values(courses, staff_level),
+        ))
+        .one(self.db.get_db())
+        .await?
+        .ok_or(Self::Error::Custom("查询异常".into()))?;
+
+        Ok(result
+            .get("count")
+            .and_then(JsonValue::as_u64)
+            .unwrap_or_default())
+    }
+
+    /// 统计这些主题帖的回帖概况，没有回帖的主题帖不返回
+    async fn get_thread_summaries(
+        &self,
+        root_ids: &[i32],
+        staff_level: i32,
+    ) -> Result<Vec<ThreadSummary>, Self::Error> {
+        if root_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = format!(
+            r#"
+        with recursive thread as (select post_id as root_id, post_id
+                    from post
+                    where post_id in ({})
+                    union all
+                    select thread.root_id, post.post_id
+                    from post
+                            join thread on post.post_answer_id = thread.post_id),
+             reply as (select thread.root_id,
+                              post.post_sno,
+                              post.post_alias,
+                              post.post_date,
+                              row_number() over (partition by thread.root_id
+                                  order by post.post_date desc, post.post_id desc) as seq
+                    from thread
+                            join post on post.post_id = thread.post_id
+                    where thread.post_id <> thread.root_id
+                      and post.post_is_del = '0'),
+             stats as (select reply.root_id,
+                              count(*) as reply_count,
+                              max(reply.post_date) as last_reply_date,
+                              max(cast(s.stu_userlevel as signed) >= ?) as staff_replied,
+                              max(if(reply.seq = 1, reply.post_sno, null)) as last_replier_no,
+                              max(if(reply.seq = 1, reply.post_alias, null)) as last_replier_alias
+                    from reply
+                            left join student s on s.stu_no = reply.post_sno
+                    group by reply.root_id)
+        select stats.root_id,
+               stats.reply_count,
+               stats.last_reply_date,
+               stats.staff_replied,
+               stats.last_replier_alias,
+               si.nickname       as nick_name,
+               si.description    as description,
+               s.stu_name        as real_name,
+               s.stu_no          as stu_no,
+               s.stu_class_sname as major,
+               s.stu_userlevel   as role
+        from stats
+                 left join student s on s.stu_no = stats.last_replier_no
+                 left join student_info si on si.stu_no = s.stu_no
+        "#,
+            vec!["?"; root_ids.len()].join(", ")
+        );
+
+        let mut values: Vec<sea_orm::Value> = root_ids.iter().map(|&id| id.into()).collect();
+        values.push(staff_level.into());
+
+        let rows = ThreadSummaryRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            sql,
+            values,
+        ))
+        .all(self.db.get_db())
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    /// 统计这些主题帖中id大于已读位置的未删除回帖数，参数为主题帖id与已读到的帖子id
+    async fn count_replies_after(
+        &self,
+        read_positions: &[(i32, i32)],
+    ) -> Result<Vec<(i32, i64)>, Self::Error> {
+        if read_positions.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = format!(
+            r#"
+        with recursive position (root_id, last_id) as (select * from (values {}) as v),
+             thread as (select post_id as root_id, post_id
+                    from post
+                    where post_id in (select root_id from position)
+                    union all
+                    select thread.root_id, post.post_id
+                    from post
+                            join thread on post.post_answer_id = thread.post_id)
+        select thread.root_id as root_id, count(*) as unread_count
+        from thread
+                 join position on position.root_id = thread.root_id
+                 join post reply on reply.post_id = thread.post_id
+        where thread.post_id <> thread.root_id
+          and reply.post_is_del = '0'
+          and reply.post_id > position.last_id
+        group by thread.root_id
+        "#,
+            vec!["row(?, ?)"; read_positions.len()].join(", ")
+        );
+
+        let values: Vec<sea_orm::Value> = read_positions
+            .iter()
+            .flat_map(|&(root_id, last_id)| [root_id.into(), last_id.into()])
+            .collect();
+        let rows = JsonValue::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            sql,
+            values,
+        ))
+        .all(self.db.get_db())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let root_id = row.get("root_id")?.as_i64()?;
+                let unread_count = row.get("unread_count")?.as_i64()?;
+                Some((root_id as i32, unread_count))
+            })
+            .collect())
+    }
+
+    /// 获取课程中所有未删除的主题帖（不含内容）
+    async fn get_course_threads(
+        &self,
+        term: &str,
+        course_code: &str,
+    ) -> Result<Vec<Post>, Self::Error> {
+        Self::select_head(false)
+            .filter(Col::PostTerm.eq(term))
+            .filter(Col::PostCourseCode.eq(course_code))
+            .filter(Col::PostAnswerId.is_null())
+            .filter(Col::PostIsDel.eq("0"))
+            .select_consumer_many(&self.db)
+            .await
+    }
+
     async fn get_post_sender_user_level(&self, post_id: i32) -> Result<Option<i64>, Self::Error> {
         let sql = r"select s.stu_userlevel from post p left join student s on s.stu_no = p.post_sno where p.post_id = ?;";
 
@@ -603,3 +1283,106 @@ impl PostRepositoryTrait for PostRepository {
 //         )
 //     }
 // }
+
+#[cfg(test)]
+mod visibility_test {
+    use sea_orm::QueryTrait;
+
+    use super::*;
+
+    /// 列出帖子时的过滤条件部分
+    fn list_sql(visible_to: Option<&str>) -> String {
+        let sql = Entity::find()
+            .filter(PostRepository::select_filter(
+                vec![],
+                false,
+                visible_to,
+                None,
+                false,
+            ))
+            .build(DbBackend::MySql)
+            .to_string();
+        sql.split_once(" WHERE ").unwrap().1.to_string()
+    }
+
+    #[test]
+    fn test_student_list_excludes_others_private_posts() {
+        assert!(list_sql(Some("2150001")).contains(
+            "(`post`.`post_visibility` = 'PUBLIC' OR `post`.`post_private_sno` = '2150001')"
+        ));
+    }
+
+    #[test]
+    fn test_staff_list_includes_private_posts() {
+        let sql = list_sql(None);
+        assert!(!sql.contains("post_visibility"));
+        assert!(!sql.contains("post_private_sno"));
+    }
+}
+
+#[cfg(test)]
+mod paginate_test {
+    use sea_orm::QueryTrait;
+
+    use super::*;
+
+    fn cursor(sort: PostSort) -> PostCursor {
+        PostCursor {
+            sort,
+            priority: None,
+            date: None,
+            reply_count: None,
+            post_id: 7,
+        }
+    }
+
+    fn page_sql(sort: PostSort, after: Option<&PostCursor>) -> String {
+        Entity::find()
+            .select_only()
+            .column(Col::PostId)
+            .paginate_by(sort, after, 20, 0)
+            .build(DbBackend::MySql)
+            .to_string()
+    }
+
+    #[test]
+    fn test_null_cursor_keys_are_not_compared_with_null() {
+        for sort in [
+            PostSort::PriorityFirst,
+            PostSort::Newest,
+            PostSort::Oldest,
+            PostSort::LastActivity,
+            PostSort::MostReplies,
+        ] {
+            let sql = page_sql(sort, Some(&cursor(sort)));
+            assert!(!sql.contains("NULL"), "{:?}: {}", sort, sql);
+        }
+    }
+
+    #[test]
+    fn test_nullable_keys_are_coalesced() {
+        let sql = page_sql(PostSort::Newest, Some(&cursor(PostSort::Newest)));
+        assert!(sql.contains(
+            "WHERE COALESCE(`post`.`post_date`, '1000-01-01 00:00:00') < '1000-01-01 00:00:00' \
+             OR (COALESCE(`post`.`post_date`, '1000-01-01 00:00:00') = '1000-01-01 00:00:00' \
+             AND `post`.`post_id` < 7)"
+        ));
+        assert!(sql.contains(
+            "ORDER BY COALESCE(`post`.`post_date`, '1000-01-01 00:00:00') DESC, \
+             `post`.`post_id` DESC LIMIT 20"
+        ));
+
+        let sql = page_sql(PostSort::MostReplies, Some(&cursor(PostSort::MostReplies)));
+        assert!(sql.contains("COALESCE(`post`.`post_reply_count`, 0) < 0"));
+    }
+
+    #[test]
+    fn test_first_page_orders_like_cursor_pages() {
+        let sql = page_sql(PostSort::PriorityFirst, None);
+        assert!(sql.contains(
+            "ORDER BY COALESCE(`post`.`post_priority`, '') DESC, \
+             COALESCE(`post`.`post_date`, '1000-01-01 00:00:00') DESC, \
+             `post`.`post_id` DESC LIMIT 20"
+        ));
+    }
+}
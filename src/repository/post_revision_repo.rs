@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, NotSet,
+    QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::post_revision::{Column as Col, Entity, Model as Revision};
+
+#[derive(Debug, Clone)]
+pub struct PostRevisionRepository {
+    db_conn: Arc<Db>,
+}
+
+impl PostRevisionRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// 保存修改前的版本，可在事务中调用
+    pub async fn add<C: ConnectionTrait>(conn: &C, revision: Revision) -> Result<(), DbErr> {
+        let mut revision = revision.into_active_model();
+        revision.rev_id = NotSet;
+        revision.insert(conn).await.map(|_| ())
+    }
+
+    /// 删除帖子的全部修改历史，可在事务中调用
+    pub async fn delete_by_posts<C: ConnectionTrait>(
+        conn: &C,
+        post_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        Entity::delete_many()
+            .filter(Col::RevPostId.is_in(post_ids.iter().copied()))
+            .exec(conn)
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+pub trait PostRevisionRepositoryTrait {
+    type Error;
+
+    /// 获取帖子的修改历史（不含内容），按时间先后排列
+    async fn list_by_post(&self, post_id: i32) -> Result<Vec<Revision>, Self::Error>;
+
+    /// 获取帖子的某个历史版本
+    async fn get(&self, post_id: i32, rev_id: i32) -> Result<Option<Revision>, Self::Error>;
+}
+
+#[async_trait]
+impl PostRevisionRepositoryTrait for PostRevisionRepository {
+    type Error = DbErr;
+
+    async fn list_by_post(&self, post_id: i32) -> Result<Vec<Revision>, Self::Error> {
+        Entity::find()
+            .select_only()
+            .columns([
+                Col::RevId,
+                Col::RevPostId,
+                Col::RevTitle,
+                Col::RevFormat,
+                Col::RevOpNo,
+                Col::RevIpaddr,
+                Col::RevDate,
+                Col::RevComment,
+            ])
+            .filter(Col::RevPostId.eq(post_id))
+            .order_by_asc(Col::RevId)
+            .all(self.db_conn.get_db())
+            .await
+    }
+
+    async fn get(&self, post_id: i32, rev_id: i32) -> Result<Option<Revision>, Self::Error> {
+        Entity::find_by_id(rev_id)
+            .filter(Col::RevPostId.eq(post_id))
+            .one(self.db_conn.get_db())
+            .await
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Local;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::post_subscription::{self, Column as Col, Entity, Model as Subscription};
+
+#[derive(Debug, Clone)]
+pub struct PostSubscriptionRepository {
+    db_conn: Arc<Db>,
+}
+
+impl PostSubscriptionRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    fn active_model(post_id: i32, user_id: &str, mode: &str) -> post_subscription::ActiveModel {
+        post_subscription::ActiveModel {
+            sub_post_id: Set(post_id),
+            sub_sno: Set(user_id.to_string()),
+            sub_mode: Set(mode.to_string()),
+            sub_date: Set(Local::now().naive_local()),
+        }
+    }
+
+    /// 用户尚未订阅时以指定方式订阅，已有订阅时保持不变，可在事务中调用
+    pub async fn subscribe_if_absent<C: ConnectionTrait>(
+        conn: &C,
+        post_id: i32,
+        user_id: &str,
+        mode: &str,
+    ) -> Result<(), DbErr> {
+        Entity::insert(Self::active_model(post_id, user_id, mode))
+            .on_conflict(
+                OnConflict::columns([Col::SubPostId, Col::SubSno])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(conn)
+            .await
+            .map(|_| ())
+    }
+
+    /// 删除主题帖的所有订阅，可在事务中调用
+    pub async fn delete_by_posts<C: ConnectionTrait>(
+        conn: &C,
+        post_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        Entity::delete_many()
+            .filter(Col::SubPostId.is_in(post_ids.iter().copied()))
+            .exec(conn)
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+pub trait PostSubscriptionRepositoryTrait {
+    type Error;
+
+    /// 设置用户对主题帖的订阅方式
+    async fn set(&self, post_id: i32, user_id: &str, mode: &str) -> Result<(), Self::Error>;
+
+    /// 取消订阅，返回是否存在该订阅
+    async fn remove(&self, post_id: i32, user_id: &str) -> Result<bool, Self::Error>;
+
+    /// 获取主题帖的所有订阅
+    async fn get_by_post(&self, post_id: i32) -> Result<Vec<Subscription>, Self::Error>;
+
+    /// 获取用户的订阅，按订阅时间从新到旧
+    async fn get_by_user(
+        &self,
+        user_id: &str,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Subscription>, Self::Error>;
+
+    /// 用户的订阅数量
+    async fn count_by_user(&self, user_id: &str) -> Result<u64, Self::Error>;
+}
+
+#[async_trait]
+impl PostSubscriptionRepositoryTrait for PostSubscriptionRepository {
+    type Error = DbErr;
+
+    async fn set(&self, post_id: i32, user_id: &str, mode: &str) -> Result<(), Self::Error> {
+        Entity::insert(Self::active_model(post_id, user_id, mode))
+            .on_conflict(
+                OnConflict::columns([Col::SubPostId, Col::SubSno])
+                    .update_columns([Col::SubMode, Col::SubDate])
+                    .to_owned(),
+            )
+            .exec_without_returning(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+
+    async fn remove(&self, post_id: i32, user_id: &str) -> Result<bool, Self::Error> {
+        let res = Entity::delete_many()
+            .filter(Col::SubPostId.eq(post_id))
+            .filter(Col::SubSno.eq(user_id))
+            .exec(self.db_conn.get_db())
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    async fn get_by_post(&self, post_id: i32) -> Result<Vec<Subscription>, Self::Error> {
+        Entity::find()
+            .filter(Col::SubPostId.eq(post_id))
+            .all(self.db_conn.get_db())
+            .await
+    }
+
+    async fn get_by_user(
+        &self,
+        user_id: &str,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Subscription>, Self::Error> {
+        Entity::find()
+            .filter(Col::SubSno.eq(user_id))
+            .order_by_desc(Col::SubDate)
+            .order_by_desc(Col::SubPostId)
+            .offset(offset)
+            .limit(limit)
+            .all(self.db_conn.get_db())
+            .await
+    }
+
+    async fn count_by_user(&self, user_id: &str) -> Result<u64, Self::Error> {
+        Entity::find()
+            .filter(Col::SubSno.eq(user_id))
+            .count(self.db_conn.get_db())
+            .await
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sea_orm::{sea_query::OnConflict, DbErr, EntityTrait, Set};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::scheduled_job::{self, Column as Col, Entity, Model as ScheduledJob};
+
+#[derive(Debug, Clone)]
+pub struct ScheduledJobRepository {
+    db_conn: Arc<Db>,
+}
+
+impl ScheduledJobRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// 写入任务记录中的部分字段，记录不存在时新建
+    async fn upsert(
+        &self,
+        record: scheduled_job::ActiveModel,
+        columns: impl IntoIterator<Item = Col>,
+    ) -> Result<(), DbErr> {
+        Entity::insert(record)
+            .on_conflict(
+                OnConflict::column(Col::JobName)
+                    .update_columns(columns)
+                    .to_owned(),
+            )
+            .exec_without_returning(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+pub trait ScheduledJobRepositoryTrait {
+    type Error;
+
+    /// 获取所有任务记录
+    async fn get_all(&self) -> Result<Vec<ScheduledJob>, Self::Error>;
+
+    /// 记录下次计划运行的时间
+    async fn set_next_run(
+        &self,
+        name: &str,
+        status: &str,
+        next_run: Option<NaiveDateTime>,
+    ) -> Result<(), Self::Error>;
+
+    /// 记录任务开始运行
+    async fn mark_started(
+        &self,
+        name: &str,
+        status: &str,
+        at: NaiveDateTime,
+    ) -> Result<(), Self::Error>;
+
+    /// 记录任务结束运行及失败原因
+    async fn mark_finished(
+        &self,
+        name: &str,
+        status: &str,
+        at: NaiveDateTime,
+        error: Option<String>,
+    ) -> Result<(), Self::Error>;
+}
+
+#[async_trait]
+impl ScheduledJobRepositoryTrait for ScheduledJobRepository {
+    type Error = DbErr;
+
+    async fn get_all(&self) -> Result<Vec<ScheduledJob>, Self::Error> {
+        Entity::find().all(self.db_conn.get_db()).await
+    }
+
+    async fn set_next_run(
+        &self,
+        name: &str,
+        status: &str,
+        next_run: Option<NaiveDateTime>,
+    ) -> Result<(), Self::Error> {
+        let record = scheduled_job::ActiveModel {
+            job_name: Set(name.to_string()),
+            job_status: Set(status.to_string()),
+            job_next_run: Set(next_run),
+            ..Default::default()
+        };
+        self.upsert(record, [Col::JobNextRun]).await
+    }
+
+    async fn mark_started(
+        &self,
+        name: &str,
+        status: &str,
+        at: NaiveDateTime,
+    ) -> Result<(), Self::Error> {
+        let record = scheduled_job::ActiveModel {
+            job_name: Set(name.to_string()),
+            job_status: Set(status.to_string()),
+            job_last_start: Set(Some(at)),
+            ..Default::default()
+        };
+        self.upsert(record, [Col::JobStatus, Col::JobLastStart])
+            .await
+    }
+
+    async fn mark_finished(
+        &self,
+        name: &str,
+        status: &str,
+        at: NaiveDateTime,
+        error: Option<String>,
+    ) -> Result<(), Self::Error> {
+        let record = scheduled_job::ActiveModel {
+            job_name: Set(name.to_string()),
+            job_status: Set(status.to_string()),
+            job_last_finish: Set(Some(at)),
+            job_last_error: Set(error),
+            ..Default::default()
+        };
+        self.upsert(
+            record,
+            [Col::JobStatus, Col::JobLastFinish, Col::JobLastError],
+        )
+        .await
+    }
+}
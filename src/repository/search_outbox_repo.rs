@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Local, NaiveDateTime};
+use sea_orm::sea_query::{Expr, LockBehavior, LockType};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, NotSet,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::search_outbox::{self, Column as Col, Entity, Model as OutboxEntry};
+
+#[derive(Debug, Clone)]
+pub struct SearchOutboxRepository {
+    db_conn: Arc<Db>,
+}
+
+impl SearchOutboxRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// 将帖子加入同步队列，可在事务中调用
+    pub async fn enqueue<C: ConnectionTrait>(conn: &C, post_id: i32) -> Result<(), DbErr> {
+        let now = Local::now().naive_local();
+        search_outbox::ActiveModel {
+            outbox_id: NotSet,
+            outbox_post_id: Set(post_id),
+            outbox_attempts: Set(0),
+            outbox_next_attempt_at: Set(now),
+            outbox_last_error: Set(None),
+            outbox_created_at: Set(now),
+            outbox_locked_by: Set(None),
+            outbox_locked_until: Set(None),
+        }
+        .insert(conn)
+        .await
+        .map(|_| ())
+    }
+
+    /// 将多个帖子批量加入同步队列
+    pub async fn enqueue_many<C: ConnectionTrait>(conn: &C, post_ids: &[i32]) -> Result<(), DbErr> {
+        if post_ids.is_empty() {
+            return Ok(());
+        }
+
+        let now = Local::now().naive_local();
+        let entries = post_ids.iter().map(|&post_id| search_outbox::ActiveModel {
+            outbox_id: NotSet,
+            outbox_post_id: Set(post_id),
+            outbox_attempts: Set(0),
+            outbox_next_attempt_at: Set(now),
+            outbox_last_error: Set(None),
+            outbox_created_at: Set(now),
+            outbox_locked_by: Set(None),
+            outbox_locked_until: Set(None),
+        });
+        Entity::insert_many(entries).exec(conn).await.map(|_| ())
+    }
+}
+
+#[async_trait]
+pub trait SearchOutboxRepositoryTrait {
+    type Error;
+
+    /// 领取到期待同步的条目，租约到期前其他实例不会再领取这些条目
+    async fn claim_due(
+        &self,
+        owner: &str,
+        max_attempts: i32,
+        now: NaiveDateTime,
+        lease_until: NaiveDateTime,
+        limit: u64,
+    ) -> Result<Vec<OutboxEntry>, Self::Error>;
+
+    /// 同步成功后移除条目
+    async fn delete_by_id(&self, outbox_id: u64) -> Result<(), Self::Error>;
+
+    /// 记录同步失败并释放租约，租约已被其他实例领取时不做修改
+    async fn mark_failed(
+        &self,
+        owner: &str,
+        outbox_id: u64,
+        attempts: i32,
+        next_attempt_at: NaiveDateTime,
+        error: &str,
+    ) -> Result<(), Self::Error>;
+
+    /// 队列中的条目总数
+    async fn count(&self) -> Result<u64, Self::Error>;
+
+    /// 曾经同步失败的条目数
+    async fn count_failed(&self) -> Result<u64, Self::Error>;
+
+    /// 已放弃重试的条目数
+    async fn count_dead(&self, max_attempts: i32) -> Result<u64, Self::Error>;
+
+    /// 曾经同步失败的条目，按失败次数降序
+    async fn find_failed(&self, limit: u64) -> Result<Vec<OutboxEntry>, Self::Error>;
+}
+
+#[async_trait]
+impl SearchOutboxRepositoryTrait for SearchOutboxRepository {
+    type Error = DbErr;
+
+    async fn claim_due(
+        &self,
+        owner: &str,
+        max_attempts: i32,
+        now: NaiveDateTime,
+        lease_until: NaiveDateTime,
+        limit: u64,
+    ) -> Result<Vec<OutboxEntry>, Self::Error> {
+        // 跳过其他实例正在领取的行，领取后立即提交，同步期间不持有行锁
+        let txn = self.db_conn.get_db().begin().await?;
+        let entries = Entity::find()
+            .filter(Col::OutboxAttempts.lt(max_attempts))
+            .filter(Col::OutboxNextAttemptAt.lte(now))
+            .filter(
+                Condition::any()
+                    .add(Col::OutboxLockedUntil.is_null())
+                    .add(Col::OutboxLockedUntil.lt(now)),
+            )
+            .order_by_asc(Col::OutboxId)
+            .limit(limit)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(&txn)
+            .await?;
+        if entries.is_empty() {
+            txn.commit().await?;
+            return Ok(entries);
+        }
+
+        Entity::update_many()
+            .col_expr(Col::OutboxLockedBy, Expr::value(owner))
+            .col_expr(Col::OutboxLockedUntil, Expr::value(lease_until))
+            .filter(Col::OutboxId.is_in(entries.iter().map(|e| e.outbox_id)))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(entries)
+    }
+
+    async fn delete_by_id(&self, outbox_id: u64) -> Result<(), Self::Error> {
+        Entity::delete_by_id(outbox_id)
+            .exec(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+
+    async fn mark_failed(
+        &self,
+        owner: &str,
+        outbox_id: u64,
+        attempts: i32,
+        next_attempt_at: NaiveDateTime,
+        error: &str,
+    ) -> Result<(), Self::Error> {
+        Entity::update_many()
+            .col_expr(Col::OutboxAttempts, Expr::value(attempts))
+            .col_expr(Col::OutboxNextAttemptAt, Expr::value(next_attempt_at))
+            .col_expr(Col::OutboxLastError, Expr::value(error))
+            .col_expr(Col::OutboxLockedBy, Expr::value(Option::<String>::None))
+            .col_expr(
+                Col::OutboxLockedUntil,
+                Expr::value(Option::<NaiveDateTime>::None),
+            )
+            .filter(Col::OutboxId.eq(outbox_id))
+            .filter(Col::OutboxLockedBy.eq(owner))
+            .exec(self.db_conn.get_db())
+            .await
+            .map(|_| ())
+    }
+
+    async fn count(&self) -> Result<u64, Self::Error> {
+        Entity::find().count(self.db_conn.get_db()).await
+    }
+
+    async fn count_failed(&self) -> Result<u64, Self::Error> {
+        Entity::find()
+            .filter(Col::OutboxLastError.is_not_null())
+            .count(self.db_conn.get_db())
+            .await
+    }
+
+    async fn count_dead(&self, max_attempts: i32) -> Result<u64, Self::Error> {
+        Entity::find()
+            .filter(Col::OutboxAttempts.gte(max_attempts))
+            .count(self.db_conn.get_db())
+            .await
+    }
+
+    async fn find_failed(&self, limit: u64) -> Result<Vec<OutboxEntry>, Self::Error> {
+        Entity::find()
+            .filter(Col::OutboxLastError.is_not_null())
+            .order_by_desc(Col::OutboxAttempts)
+            .order_by_asc(Col::OutboxId)
+            .limit(limit)
+            .all(self.db_conn.get_db())
+            .await
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, NotSet,
+    QueryFilter, QuerySelect, Set, Statement,
+};
+
+use crate::config::database::{DatabaseTrait, Db};
+use crate::entity::search_rebuild::{self, Column as Col, Entity};
+use crate::entity::search_rebuild_dirty::{Column as DirtyCol, Entity as DirtyEntity};
+
+#[derive(Debug, Clone)]
+pub struct SearchRebuildRepository {
+    db_conn: Arc<Db>,
+}
+
+impl SearchRebuildRepository {
+    pub fn new(db_conn: &Arc<Db>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+}
+
+#[async_trait]
+pub trait SearchRebuildRepositoryTrait {
+    type Error;
+
+    /// 开始记录重建窗口，返回重建记录id
+    ///
+    /// 同一时间只有一个重建任务，之前未正常结束的窗口在此关闭
+    async fn begin(&self, at: NaiveDateTime) -> Result<u64, Self::Error>;
+
+    /// 帖子已同步到正式索引，有正在进行的重建时记录该帖子
+    async fn mark_dirty(&self, post_id: i32) -> Result<(), Self::Error>;
+
+    /// 关闭重建窗口，返回窗口内同步过的帖子id
+    async fn finish(&self, rebuild_id: u64, at: NaiveDateTime) -> Result<Vec<i32>, Self::Error>;
+}
+
+#[async_trait]
+impl SearchRebuildRepositoryTrait for SearchRebuildRepository {
+    type Error = DbErr;
+
+    async fn begin(&self, at: NaiveDateTime) -> Result<u64, Self::Error> {
+        let db = self.db_conn.get_db();
+
+        Entity::update_many()
+            .col_expr(Col::RebuildFinishedAt, Expr::value(at))
+            .filter(Col::RebuildFinishedAt.is_null())
+            .exec(db)
+            .await?;
+        DirtyEntity::delete_many().exec(db).await?;
+
+        let rebuild = search_rebuild::ActiveModel {
+            rebuild_id: NotSet,
+            rebuild_started_at: Set(at),
+            rebuild_finished_at: Set(None),
+        }
+        .insert(db)
+        .await?;
+        Ok(rebuild.rebuild_id)
+    }
+
+    async fn mark_dirty(&self, post_id: i32) -> Result<(), Self::Error> {
+        // 没有正在进行的重建时不插入任何记录
+        self.db_conn
+            .get_db()
+            .execute(Statement::from_sql_and_values(
+                DbBackend::MySql,
+                r#"
+            insert ignore into search_rebuild_dirty (dirty_rebuild_id, dirty_post_id)
+            select rebuild_id, ?
+            from search_rebuild
+            where rebuild_finished_at is null
+            "#,
+                [post_id.into()],
+            ))
+            .await
+            .map(|_| ())
+    }
+
+    async fn finish(&self, rebuild_id: u64, at: NaiveDateTime) -> Result<Vec<i32>, Self::Error> {
+        let db = self.db_conn.get_db();
+
+        // 先关闭窗口，之后同步的帖子已写入替换后的索引，不再记录
+        Entity::update_many()
+            .col_expr(Col::RebuildFinishedAt, Expr::value(at))
+            .filter(Col::RebuildId.eq(rebuild_id))
+            .exec(db)
+            .await?;
+
+        let post_ids = DirtyEntity::find()
+            .select_only()
+            .column(DirtyCol::DirtyPostId)
+            .filter(DirtyCol::DirtyRebuildId.eq(rebuild_id))
+            .into_tuple()
+            .all(db)
+            .await?;
+        DirtyEntity::delete_many()
+            .filter(DirtyCol::DirtyRebuildId.eq(rebuild_id))
+            .exec(db)
+            .await?;
+
+        Ok(post_ids)
+    }
+}
@@ -7,7 +7,7 @@ use sea_orm::{
 
 use crate::{
     config::database::{DatabaseTrait, Db},
-    dto::student_short_info::StudentShortInfo,
+    dto::{announcement::AnnouncementReader, student_short_info::StudentShortInfo},
     entity::student,
     error::api_error::ApiError,
 };
@@ -23,6 +23,16 @@ pub trait StudentInfoRepositoryTrait {
         &self,
         stu_no: &str,
     ) -> Result<Option<StudentShortInfo>, Self::Error>;
+
+    /// 查询学号或昵称为这些名称的用户学号
+    async fn get_stu_nos_by_names(&self, names: &[String]) -> Result<Vec<String>, Self::Error>;
+
+    /// 查询选修该课程且未退课的学生，按学号排列
+    async fn get_enrolled_students(
+        &self,
+        term: &str,
+        course_code: &str,
+    ) -> Result<Vec<AnnouncementReader>, Self::Error>;
 }
 
 #[derive(Debug, Clone)]
@@ -78,4 +88,79 @@ impl StudentInfoRepositoryTrait for StudentInfoRepository {
         .one(self.db_conn.get_db())
         .await
     }
+
+    async fn get_stu_nos_by_names(&self, names: &[String]) -> Result<Vec<String>, Self::Error> {
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let placeholders = vec!["?"; names.len()].join(",");
+        let sql = format!(
+            r#"
+        select distinct s.stu_no as stu_no
+        from student s
+                 left join student_info si on s.stu_no = si.stu_no
+        where s.stu_no in ({0})
+           or si.nickname in ({0})
+        "#,
+            placeholders
+        );
+
+        let values: Vec<sea_orm::Value> = names
+            .iter()
+            .chain(names.iter())
+            .map(|name| name.as_str().into())
+            .collect();
+        let rows = JsonValue::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            sql,
+            values,
+        ))
+        .all(self.db_conn.get_db())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| row.get("stu_no")?.as_str().map(String::from))
+            .collect())
+    }
+
+    async fn get_enrolled_students(
+        &self,
+        term: &str,
+        course_code: &str,
+    ) -> Result<Vec<AnnouncementReader>, Self::Error> {
+        // 学生表中的课号对应course表中同一课程的某个班级
+        let sql = r#"
+        select distinct s.stu_no as stu_no, s.stu_name as stu_name
+        from student s
+                 join course c on c.course_term = s.stu_term
+        where c.course_term = ?
+          and c.course_code = ?
+          and s.stu_is_del = '0'
+          and ((s.stu_cno_1 = c.course_no and s.stu_cno_1_is_del = '0')
+            or (s.stu_cno_2 = c.course_no and s.stu_cno_2_is_del = '0')
+            or (s.stu_cno_3 = c.course_no and s.stu_cno_3_is_del = '0'))
+        order by s.stu_no
+        "#;
+
+        let rows = JsonValue::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::MySql,
+            sql,
+            [term.into(), course_code.into()],
+        ))
+        .all(self.db_conn.get_db())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                Some(AnnouncementReader {
+                    stu_no: row.get("stu_no")?.as_str()?.to_string(),
+                    stu_name: row.get("stu_name")?.as_str().map(String::from),
+                    read_date: None,
+                })
+            })
+            .collect())
+    }
 }
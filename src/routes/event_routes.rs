@@ -0,0 +1,9 @@
+use axum::routing::get;
+use axum::Router;
+
+use crate::handler::event_handler as handler;
+use crate::state::event_state::EventState;
+
+pub fn routes() -> Router<EventState> {
+    Router::new().route("/stream", get(handler::stream_events))
+}
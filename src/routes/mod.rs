@@ -1,10 +1,13 @@
 pub mod auth_routes;
 pub mod board_routes;
 pub mod course_routes;
+pub mod event_routes;
 pub mod homework_routes;
 pub mod metadata_routes;
 pub mod notification_routes;
 pub mod post_routes;
 pub mod root;
+pub mod scheduler_routes;
+pub mod search_routes;
 pub mod upload_routes;
 pub mod user_routes;
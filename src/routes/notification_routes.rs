@@ -1,4 +1,4 @@
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, post, put};
 use axum::Router;
 
 use crate::handler::notification_handler as handler;
@@ -7,7 +7,11 @@ use crate::state::notification_state::NotificationState;
 pub fn routes() -> Router<NotificationState> {
     Router::new()
         .route("/", get(handler::get_my_notifications))
+        .route("/unreadCount", get(handler::get_my_unread_count))
+        .route("/preference", get(handler::get_my_preferences))
+        .route("/preference", put(handler::set_my_preference))
         .route("/read", post(handler::read_my_notifications))
+        .route("/readTarget", post(handler::read_target_notifications))
         .route("/readAll", post(handler::read_all_my_notifications))
         .route("/all", delete(handler::delete_all_my_notifications))
 }
@@ -11,15 +11,44 @@ pub fn routes() -> Router<PostState> {
     let ta_router = Router::new()
         .route("/tag", put(handler::set_post_tags))
         .route("/priority", put(handler::set_post_priority))
+        .route("/restore", put(handler::restore_post))
+        .route("/move", put(handler::move_post))
+        .route("/close", put(handler::close_post))
+        .route("/publish", put(handler::publish_post))
+        .route(
+            "/announcement/receipt",
+            get(handler::get_announcement_receipt),
+        )
+        .route("/unanswered", get(handler::list_unanswered_questions))
+        .route("/claim", put(handler::claim_post))
+        .route("/claim", delete(handler::unclaim_post))
+        .route("/revision/rollback", put(handler::rollback_post))
         .route_layer(permission_required!(AuthBackend, Permission::TA));
 
+    let super_router = Router::new()
+        .route("/purge", delete(handler::purge_deleted_posts))
+        .route_layer(permission_required!(AuthBackend, Permission::SUPER));
+
     Router::new()
         .merge(ta_router)
+        .merge(super_router)
         .route("/", post(handler::add_post))
         .route("/reply", post(handler::add_reply))
         .route("/", put(handler::edit_post))
         .route("/", delete(handler::delete_posts))
         .route("/list", get(handler::list_posts))
         .route("/", get(handler::get_posts))
+        .route("/tree", get(handler::get_post_tree))
+        .route("/tree/context", get(handler::get_post_context))
         .route("/parent", get(handler::get_post_parent))
+        .route("/search", get(handler::search_posts))
+        .route("/accept", put(handler::accept_answer))
+        .route("/accept", delete(handler::unaccept_answer))
+        .route("/subscription", put(handler::subscribe_post))
+        .route("/subscription", delete(handler::unsubscribe_post))
+        .route("/subscription", get(handler::list_subscriptions))
+        .route("/read", put(handler::mark_board_read))
+        .route("/unread", get(handler::list_board_unread_counts))
+        .route("/revision", get(handler::list_post_revisions))
+        .route("/revision/diff", get(handler::diff_post_revisions))
 }
@@ -13,8 +13,8 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::config::database::Db;
-use crate::config::meili::Meili;
 use crate::config::redis::Redis;
+use crate::search::SearchBackend;
 
 use crate::config::s3::S3Conn;
 use crate::config::APP_CONFIG;
@@ -24,24 +24,27 @@ use crate::service::auth_service::AuthBackend;
 use crate::state::auth_state::AuthState;
 use crate::state::board_state::BoardState;
 use crate::state::course_state::CourseState;
+use crate::state::event_state::EventState;
 use crate::state::homework_state::HomeworkState;
 use crate::state::limit_state::LimitState;
 use crate::state::metadata_state::MetadataState;
 use crate::state::notification_state::NotificationState;
 use crate::state::post_state::PostState;
+use crate::state::scheduler_state::SchedulerState;
+use crate::state::search_state::SearchState;
 use crate::state::upload_state::UploadState;
 use crate::state::user_state::UserState;
 
 use super::{
-    board_routes, course_routes, homework_routes, metadata_routes, notification_routes,
-    post_routes, upload_routes,
+    board_routes, course_routes, event_routes, homework_routes, metadata_routes,
+    notification_routes, post_routes, scheduler_routes, search_routes, upload_routes,
 };
 
 pub fn routes(
     db_conn: Arc<Db>,
     redis: Arc<Redis>,
     s3_client: Arc<S3Conn>,
-    meili_client: Arc<Meili>,
+    search_backend: Arc<dyn SearchBackend>,
     auth_layer: AuthManagerLayer<AuthBackend, impl SessionStore + Clone>,
 ) -> IntoMakeServiceWithConnectInfo<Router, SocketAddr> {
     let production = std::env::var("PROD").map(|_| true).unwrap_or(false);
@@ -56,11 +59,14 @@ pub fn routes(
         let auth_state = AuthState::new(&db_conn);
         let board_state = BoardState::new(&db_conn);
         let course_state = CourseState::new(&db_conn, &app_config);
+        let event_state = EventState::new(&db_conn, &redis, &app_config, &search_backend);
         let homework_state = HomeworkState::new(&db_conn, &s3_client, &app_config);
         let limit_state = LimitState::new(&redis);
         let metadata_state = MetadataState::new(&db_conn);
-        let notification_state = NotificationState::new(&db_conn);
-        let post_state = PostState::new(&db_conn, &app_config, &meili_client);
+        let notification_state = NotificationState::new(&db_conn, &redis, &app_config);
+        let post_state = PostState::new(&db_conn, &redis, &app_config, &search_backend);
+        let scheduler_state = SchedulerState::new(&db_conn, &redis, &app_config, &search_backend);
+        let search_state = SearchState::new(&db_conn, &search_backend);
         let user_state = UserState::new(&db_conn, &s3_client, &app_config);
         let upload_state = UploadState::new(&s3_client, &app_config);
 
@@ -68,6 +74,7 @@ pub fn routes(
             .nest("/user", user_routes::routes().with_state(user_state))
             .nest("/board", board_routes::routes().with_state(board_state))
             .nest("/course", course_routes::routes().with_state(course_state))
+            .nest("/event", event_routes::routes().with_state(event_state))
             .nest(
                 "/homework",
                 homework_routes::routes().with_state(homework_state),
@@ -81,6 +88,11 @@ pub fn routes(
                 notification_routes::routes().with_state(notification_state),
             )
             .nest("/post", post_routes::routes().with_state(post_state))
+            .nest(
+                "/scheduler",
+                scheduler_routes::routes().with_state(scheduler_state),
+            )
+            .nest("/search", search_routes::routes().with_state(search_state))
             .nest("/upload", upload_routes::routes().with_state(upload_state))
             .route_layer(login_required!(AuthBackend))
             .merge(auth_routes::routes(limit_state).with_state(auth_state))
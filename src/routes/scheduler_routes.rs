@@ -0,0 +1,19 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use axum_login::permission_required;
+
+use crate::{
+    config::permission::Permission, service::auth_service::AuthBackend,
+    state::scheduler_state::SchedulerState,
+};
+
+pub fn routes() -> Router<SchedulerState> {
+    use crate::handler::scheduler_handler as handler;
+
+    Router::new()
+        .route("/job", get(handler::list_jobs))
+        .route("/job/run", post(handler::trigger_job))
+        .route_layer(permission_required!(AuthBackend, Permission::ADMIN))
+}
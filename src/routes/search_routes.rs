@@ -0,0 +1,20 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use axum_login::permission_required;
+
+use crate::{
+    config::permission::Permission, service::auth_service::AuthBackend,
+    state::search_state::SearchState,
+};
+
+pub fn routes() -> Router<SearchState> {
+    use crate::handler::search_handler as handler;
+
+    Router::new()
+        .route("/outbox", get(handler::get_outbox_status))
+        .route("/index-job", post(handler::start_index_job))
+        .route("/index-job", get(handler::get_index_job_progress))
+        .route_layer(permission_required!(AuthBackend, Permission::ADMIN))
+}
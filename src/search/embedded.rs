@@ -0,0 +1,910 @@
+//! 内嵌的磁盘全文索引
+//!
+//! 基于tantivy，无需外部服务，适合本地开发与CI。中文按相邻两字切分（bigram），
+//! 查询时多字词按短语匹配，英文与数字按单词切分并转为小写。
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use log::warn;
+use parking_lot::{Mutex, RwLock};
+use tantivy::aggregation::agg_req::{Aggregation, AggregationVariants, Aggregations};
+use tantivy::aggregation::agg_result::{AggregationResult, BucketResult};
+use tantivy::aggregation::bucket::TermsAggregation;
+use tantivy::aggregation::{AggregationCollector, Key};
+use tantivy::collector::{Count, TopDocs};
+use tantivy::query::{
+    AllQuery, BooleanQuery, EmptyQuery, Occur, Query, QueryParser, RangeQuery, TermQuery,
+};
+use tantivy::schema::{
+    Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, FAST, INDEXED, STORED,
+    STRING,
+};
+use tantivy::snippet::{Snippet, SnippetGenerator};
+use tantivy::tokenizer::{TextAnalyzer, Token, TokenStream, Tokenizer};
+use tantivy::{
+    DocAddress, Index, IndexReader, IndexWriter, Order, ReloadPolicy, TantivyDocument,
+    TantivyError, Term,
+};
+
+use crate::{
+    dto::post_search::{PostSearchHit, PostSearchResult, PostSearchSort},
+    entity::post,
+    error::proc_error::ProcessError,
+};
+
+use super::{
+    escape_html, post_timestamp, FilterValue, IndexSlot, SearchBackend, SearchFilter,
+    SearchRequest, FACET_ATTRIBUTES, FILTERABLE_ATTRIBUTES, HIGHLIGHT_POST_TAG, HIGHLIGHT_PRE_TAG,
+    SNIPPET_CROP_LENGTH, TIMESTAMP_ATTRIBUTE,
+};
+
+/// 分词器名称
+const CJK_TOKENIZER: &str = "cjk_bigram";
+
+/// 索引写入缓冲区大小
+const WRITER_MEMORY_BUDGET: usize = 50_000_000;
+
+/// 每个分面字段最多返回的取值数，与Meilisearch的默认值一致
+const FACET_MAX_VALUES: u32 = 100;
+
+/// 正文片段的最大字符数
+const SNIPPET_MAX_CHARS: usize = SNIPPET_CROP_LENGTH * 3;
+
+const FIELD_ID: &str = "postId";
+const FIELD_TITLE: &str = "postTitle";
+const FIELD_CONTENT: &str = "postContent";
+/// 除正文外的完整帖子信息（JSON）
+const FIELD_DOCUMENT: &str = "document";
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{2fa1f}')
+}
+
+/// 中日韩文字按相邻两字切分，其余文字按单词切分
+#[derive(Clone, Default)]
+struct CjkBigramTokenizer {
+    tokens: Vec<Token>,
+}
+
+struct CjkBigramTokenStream<'a> {
+    tokens: &'a mut [Token],
+    index: Option<usize>,
+}
+
+impl CjkBigramTokenizer {
+    fn push(&mut self, text: &str, from: usize, to: usize) {
+        let position = self.tokens.len();
+        self.tokens.push(Token {
+            offset_from: from,
+            offset_to: to,
+            position,
+            text: text[from..to].to_lowercase(),
+            position_length: 1,
+        });
+    }
+
+    /// 单字的文字单独成词，否则切分为相邻的两字
+    fn push_cjk_run(&mut self, text: &str, run: &[(usize, char)]) {
+        if let [(from, c)] = run {
+            self.push(text, *from, from + c.len_utf8());
+            return;
+        }
+        for pair in run.windows(2) {
+            let (from, _) = pair[0];
+            let (start, c) = pair[1];
+            self.push(text, from, start + c.len_utf8());
+        }
+    }
+}
+
+impl Tokenizer for CjkBigramTokenizer {
+    type TokenStream<'a> = CjkBigramTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        self.tokens.clear();
+
+        let mut word_start = None;
+        let mut cjk_run = vec![];
+        for (offset, c) in text.char_indices() {
+            if is_cjk(c) {
+                if let Some(from) = word_start.take() {
+                    self.push(text, from, offset);
+                }
+                cjk_run.push((offset, c));
+                continue;
+            }
+
+            if !cjk_run.is_empty() {
+                self.push_cjk_run(text, &cjk_run);
+                cjk_run.clear();
+            }
+            if c.is_alphanumeric() {
+                word_start.get_or_insert(offset);
+            } else if let Some(from) = word_start.take() {
+                self.push(text, from, offset);
+            }
+        }
+        if let Some(from) = word_start {
+            self.push(text, from, text.len());
+        }
+        if !cjk_run.is_empty() {
+            self.push_cjk_run(text, &cjk_run);
+        }
+
+        CjkBigramTokenStream {
+            tokens: &mut self.tokens,
+            index: None,
+        }
+    }
+}
+
+impl TokenStream for CjkBigramTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        let next = self.index.map_or(0, |i| i + 1);
+        self.index = Some(next);
+        next < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index.unwrap_or_default()]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index.unwrap_or_default()]
+    }
+}
+
+fn build_schema() -> Schema {
+    let text_options = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CJK_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+        .set_stored();
+
+    let mut builder = Schema::builder();
+    builder.add_i64_field(FIELD_ID, INDEXED | STORED | FAST);
+    builder.add_text_field(FIELD_TITLE, text_options.clone());
+    builder.add_text_field(FIELD_CONTENT, text_options);
+    builder.add_text_field(FIELD_DOCUMENT, STORED);
+    builder.add_i64_field(TIMESTAMP_ATTRIBUTE, INDEXED | FAST);
+    for &attribute in FILTERABLE_ATTRIBUTES {
+        if attribute == TIMESTAMP_ATTRIBUTE {
+            continue;
+        }
+        // 分面字段同时存为列式存储，统计时不需要读取文档
+        match FACET_ATTRIBUTES.contains(&attribute) {
+            true => builder.add_text_field(attribute, STRING | FAST),
+            false => builder.add_text_field(attribute, STRING),
+        };
+    }
+    builder.build()
+}
+
+/// 高亮片段，没有命中时返回原文开头的一段
+fn highlight(generator: Option<&SnippetGenerator>, text: &str, max_chars: usize) -> String {
+    let snippet = generator.map_or_else(Snippet::empty, |g| g.snippet(text));
+    if snippet.is_empty() {
+        return escape_html(&text.chars().take(max_chars).collect::<String>());
+    }
+
+    let mut snippet = snippet;
+    snippet.set_snippet_prefix_postfix(HIGHLIGHT_PRE_TAG, HIGHLIGHT_POST_TAG);
+    snippet.to_html()
+}
+
+/// 过滤字段的值统一按字符串索引
+fn filter_term_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+struct EmbeddedIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    id: Field,
+    title: Field,
+    content: Field,
+    document: Field,
+    timestamp: Field,
+}
+
+impl EmbeddedIndex {
+    fn open(path: &Path) -> tantivy::Result<Self> {
+        fs::create_dir_all(path)?;
+        let directory = tantivy::directory::MmapDirectory::open(path)?;
+        let index = match Index::open_or_create(directory, build_schema()) {
+            Err(TantivyError::SchemaError(e)) => {
+                // 索引字段有变更，旧索引无法继续使用
+                warn!("内嵌搜索索引结构已变更，将创建空索引，请执行reindex：{}", e);
+                fs::remove_dir_all(path)?;
+                fs::create_dir_all(path)?;
+                Index::create_in_dir(path, build_schema())?
+            }
+            index => index?,
+        };
+        index.tokenizers().register(
+            CJK_TOKENIZER,
+            TextAnalyzer::from(CjkBigramTokenizer::default()),
+        );
+
+        let schema = index.schema();
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        // 同一索引只能有一个写入者，服务端运行时命令行进程无法打开索引
+        let writer = index.writer(WRITER_MEMORY_BUDGET).map_err(|e| match e {
+            TantivyError::LockFailure(e, _) => TantivyError::LockFailure(
+                e,
+                Some(String::from(
+                    "索引正被其他进程使用，服务端运行时请通过/search/index-job接口执行索引维护任务",
+                )),
+            ),
+            e => e,
+        })?;
+        Ok(Self {
+            id: schema.get_field(FIELD_ID)?,
+            title: schema.get_field(FIELD_TITLE)?,
+            content: schema.get_field(FIELD_CONTENT)?,
+            document: schema.get_field(FIELD_DOCUMENT)?,
+            timestamp: schema.get_field(TIMESTAMP_ATTRIBUTE)?,
+            index,
+            reader,
+            writer: Mutex::new(writer),
+        })
+    }
+
+    fn commit(&self, writer: &mut IndexWriter) -> tantivy::Result<()> {
+        writer.commit()?;
+        self.reader.reload()
+    }
+
+    fn upsert(&self, posts: Vec<post::Model>) -> tantivy::Result<()> {
+        let schema = self.index.schema();
+        let mut writer = self.writer.lock();
+
+        for post in posts {
+            let id = i64::from(post.post_id);
+            let json = serde_json::to_value(&post)?;
+
+            let mut doc = TantivyDocument::default();
+            doc.add_i64(self.id, id);
+            doc.add_text(self.title, post.post_title.as_deref().unwrap_or_default());
+            doc.add_text(
+                self.content,
+                post.post_content.as_deref().unwrap_or_default(),
+            );
+            if let Some(timestamp) = post_timestamp(&post) {
+                doc.add_i64(self.timestamp, timestamp);
+            }
+            for &attribute in FILTERABLE_ATTRIBUTES {
+                if let Some(text) = json.get(attribute).and_then(filter_term_text) {
+                    doc.add_text(schema.get_field(attribute)?, text);
+                }
+            }
+            let stored = post::Model {
+                post_content: None,
+                ..post
+            };
+            doc.add_text(self.document, serde_json::to_string(&stored)?);
+
+            writer.delete_term(Term::from_field_i64(self.id, id));
+            writer.add_document(doc)?;
+        }
+        self.commit(&mut writer)
+    }
+
+    fn delete(&self, post_ids: &[i32]) -> tantivy::Result<()> {
+        let mut writer = self.writer.lock();
+        for &post_id in post_ids {
+            writer.delete_term(Term::from_field_i64(self.id, post_id.into()));
+        }
+        self.commit(&mut writer)
+    }
+
+    fn list_ids(&self) -> tantivy::Result<HashSet<i32>> {
+        let searcher = self.reader.searcher();
+
+        let mut ids = HashSet::new();
+        for segment in searcher.segment_readers() {
+            let column = segment.fast_fields().i64(FIELD_ID)?;
+            let alive = segment.alive_bitset();
+            for doc in 0..segment.max_doc() {
+                if alive.is_none_or(|bitset| bitset.is_alive(doc)) {
+                    ids.extend(column.values_for_doc(doc).map(|id| id as i32));
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    fn filter_query(&self, filter: &SearchFilter) -> tantivy::Result<Box<dyn Query>> {
+        let schema = self.index.schema();
+        Ok(match filter {
+            SearchFilter::Eq(field, value) => {
+                let text = match value {
+                    FilterValue::Str(s) => s.clone(),
+                    FilterValue::Int(i) => i.to_string(),
+                };
+                Box::new(TermQuery::new(
+                    Term::from_field_text(schema.get_field(field)?, &text),
+                    IndexRecordOption::Basic,
+                ))
+            }
+            SearchFilter::Range(_, None, None) => Box::new(AllQuery),
+            SearchFilter::Range(field, min, max) => {
+                let bound = |b: &Option<i64>| b.map_or(Bound::Unbounded, Bound::Included);
+                Box::new(RangeQuery::new_i64_bounds(
+                    field.to_string(),
+                    bound(min),
+                    bound(max),
+                ))
+            }
+            SearchFilter::All(filters) if filters.is_empty() => Box::new(AllQuery),
+            SearchFilter::All(filters) | SearchFilter::Any(filters) => {
+                let occur = match filter {
+                    SearchFilter::All(_) => Occur::Must,
+                    _ => Occur::Should,
+                };
+                let clauses = filters
+                    .iter()
+                    .map(|f| Ok((occur, self.filter_query(f)?)))
+                    .collect::<tantivy::Result<Vec<_>>>()?;
+                if clauses.is_empty() {
+                    return Ok(Box::new(EmptyQuery));
+                }
+                Box::new(BooleanQuery::new(clauses))
+            }
+        })
+    }
+
+    /// 统计命中文档在各字段上的取值分布，每个字段最多返回命中最多的`FACET_MAX_VALUES`个取值
+    fn count_facets(
+        &self,
+        searcher: &tantivy::Searcher,
+        query: &dyn Query,
+        facets: &[&str],
+    ) -> tantivy::Result<HashMap<String, HashMap<String, usize>>> {
+        if facets.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let aggregations: Aggregations = facets
+            .iter()
+            .map(|&facet| {
+                let terms = TermsAggregation {
+                    field: facet.to_string(),
+                    size: Some(FACET_MAX_VALUES),
+                    ..Default::default()
+                };
+                let aggregation = Aggregation {
+                    agg: AggregationVariants::Terms(terms),
+                    sub_aggregation: Default::default(),
+                };
+                (facet.to_string(), aggregation)
+            })
+            .collect();
+        let collector = AggregationCollector::from_aggs(aggregations, Default::default());
+        let results = searcher.search(query, &collector)?;
+
+        Ok(results
+            .0
+            .into_iter()
+            .filter_map(|(facet, result)| match result {
+                AggregationResult::BucketResult(BucketResult::Terms { buckets, .. })
+                    if !buckets.is_empty() =>
+                {
+                    let values = buckets
+                        .into_iter()
+                        .map(|bucket| {
+                            let value = match bucket.key {
+                                Key::Str(s) => s,
+                                Key::F64(f) => f.to_string(),
+                            };
+                            (value, bucket.doc_count as usize)
+                        })
+                        .collect();
+                    Some((facet, values))
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn search(&self, request: &OwnedSearchRequest) -> tantivy::Result<PostSearchResult> {
+        let OwnedSearchRequest {
+            query,
+            filter,
+            sort,
+            facets,
+            page_size,
+            page_index,
+        } = request;
+        let (page_size, page_index) = (*page_size, *page_index);
+        let started = Instant::now();
+        let searcher = self.reader.searcher();
+
+        let text_query = if query.trim().is_empty() {
+            None
+        } else {
+            let mut parser = QueryParser::for_index(&self.index, vec![self.title, self.content]);
+            parser.set_conjunction_by_default();
+            parser.set_field_boost(self.title, 2.0);
+            Some(parser.parse_query_lenient(query).0)
+        };
+        let full_query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                text_query
+                    .as_ref()
+                    .map_or_else(|| Box::new(AllQuery) as Box<dyn Query>, |q| q.box_clone()),
+            ),
+            (Occur::Must, self.filter_query(filter)?),
+        ]);
+
+        let top_docs = TopDocs::with_limit(page_size).and_offset((page_index - 1) * page_size);
+        let order_by = match (sort, &text_query) {
+            (PostSearchSort::Newest, _) => Some((TIMESTAMP_ATTRIBUTE, Order::Desc)),
+            (PostSearchSort::Oldest, _) => Some((TIMESTAMP_ATTRIBUTE, Order::Asc)),
+            (PostSearchSort::Relevance, Some(_)) => None,
+            // 没有关键词时按发帖顺序倒序
+            (PostSearchSort::Relevance, None) => Some((FIELD_ID, Order::Desc)),
+        };
+        let (addresses, total_hits): (Vec<DocAddress>, usize) = match order_by {
+            None => {
+                let (docs, count) = searcher.search(&full_query, &(top_docs, Count))?;
+                (docs.into_iter().map(|(_, addr)| addr).collect(), count)
+            }
+            Some((field, order)) => {
+                let top_docs = top_docs.order_by_fast_field::<i64>(field, order);
+                let (docs, count) = searcher.search(&full_query, &(top_docs, Count))?;
+                (docs.into_iter().map(|(_, addr)| addr).collect(), count)
+            }
+        };
+        let facets = self.count_facets(&searcher, &full_query, facets)?;
+
+        let generators = match &text_query {
+            Some(q) => {
+                let mut title = SnippetGenerator::create(&searcher, q.as_ref(), self.title)?;
+                title.set_max_num_chars(usize::MAX);
+                let mut content = SnippetGenerator::create(&searcher, q.as_ref(), self.content)?;
+                content.set_max_num_chars(SNIPPET_MAX_CHARS);
+                Some((title, content))
+            }
+            None => None,
+        };
+
+        let mut hits = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let field_text = |field| {
+                doc.get_first(field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+            };
+            let post: post::Model = serde_json::from_str(field_text(self.document))?;
+
+            hits.push(PostSearchHit {
+                highlighted_title: Some(highlight(
+                    generators.as_ref().map(|(g, _)| g),
+                    field_text(self.title),
+                    usize::MAX,
+                )),
+                snippet: Some(highlight(
+                    generators.as_ref().map(|(_, g)| g),
+                    field_text(self.content),
+                    SNIPPET_MAX_CHARS,
+                )),
+                post,
+            });
+        }
+
+        Ok(PostSearchResult {
+            hits,
+            total_hits,
+            total_pages: total_hits.div_ceil(page_size),
+            page_size,
+            page_index,
+            processing_time_ms: started.elapsed().as_millis() as usize,
+            facets,
+        })
+    }
+}
+
+/// 两个索引目录轮流作为正式索引与重建索引
+const SLOT_DIRS: [&str; 2] = ["post", "post_rebuild"];
+
+/// 记录正式索引所在目录的文件
+const LIVE_POINTER_FILE: &str = "live";
+
+struct Slots {
+    root: PathBuf,
+    indexes: RwLock<HashMap<IndexSlot, Arc<EmbeddedIndex>>>,
+}
+
+impl Slots {
+    /// 槽位所在的目录名，没有记录时正式索引在`post`目录
+    fn dir(&self, slot: IndexSlot) -> &'static str {
+        let pointer = fs::read_to_string(self.root.join(LIVE_POINTER_FILE)).unwrap_or_default();
+        let live = SLOT_DIRS
+            .iter()
+            .position(|&dir| dir == pointer.trim())
+            .unwrap_or(0);
+        match slot {
+            IndexSlot::Live => SLOT_DIRS[live],
+            IndexSlot::Rebuild => SLOT_DIRS[1 - live],
+        }
+    }
+
+    fn path(&self, slot: IndexSlot) -> PathBuf {
+        self.root.join(self.dir(slot))
+    }
+
+    /// 获取槽位对应的索引，尚未打开时打开或创建
+    fn get(&self, slot: IndexSlot) -> tantivy::Result<Arc<EmbeddedIndex>> {
+        if let Some(index) = self.indexes.read().get(&slot) {
+            return Ok(Arc::clone(index));
+        }
+
+        let mut indexes = self.indexes.write();
+        if let Some(index) = indexes.get(&slot) {
+            return Ok(Arc::clone(index));
+        }
+        let index = Arc::new(EmbeddedIndex::open(&self.path(slot))?);
+        indexes.insert(slot, Arc::clone(&index));
+        Ok(index)
+    }
+
+    fn reset(&self, slot: IndexSlot) -> tantivy::Result<()> {
+        let mut indexes = self.indexes.write();
+        // 先释放写锁文件再删除目录
+        indexes.remove(&slot);
+
+        let path = self.path(slot);
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        indexes.insert(slot, Arc::new(EmbeddedIndex::open(&path)?));
+        Ok(())
+    }
+
+    /// 用重建好的索引替换正式索引
+    ///
+    /// 目录不做移动，已打开的重建索引直接作为正式索引使用；记录文件替换成功后才切换内存中的索引，
+    /// 失败时正式索引保持不变
+    fn promote_rebuild(&self) -> tantivy::Result<()> {
+        let mut indexes = self.indexes.write();
+        let Some(rebuild) = indexes.get(&IndexSlot::Rebuild).cloned() else {
+            return Err(TantivyError::InvalidArgument(String::from(
+                "重建索引不存在",
+            )));
+        };
+
+        let retired = self.path(IndexSlot::Live);
+        let pending = self.root.join(format!("{}.tmp", LIVE_POINTER_FILE));
+        fs::write(&pending, self.dir(IndexSlot::Rebuild))?;
+        fs::rename(&pending, self.root.join(LIVE_POINTER_FILE))?;
+
+        indexes.remove(&IndexSlot::Rebuild);
+        // 旧索引的写锁随旧索引释放
+        drop(indexes.insert(IndexSlot::Live, rebuild));
+        drop(indexes);
+
+        // 旧目录会在下次重建时清空，这里删除失败不影响使用
+        if retired.exists() {
+            if let Err(e) = fs::remove_dir_all(&retired) {
+                warn!("删除替换下来的内嵌搜索索引失败：{}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 搜索请求，在阻塞线程中使用
+struct OwnedSearchRequest {
+    query: String,
+    filter: SearchFilter,
+    sort: PostSearchSort,
+    facets: Vec<&'static str>,
+    page_size: usize,
+    page_index: usize,
+}
+
+pub struct EmbeddedBackend {
+    slots: Arc<Slots>,
+}
+
+impl EmbeddedBackend {
+    pub fn open(path: &str) -> Result<Self, ProcessError> {
+        let slots = Slots {
+            root: PathBuf::from(path),
+            indexes: RwLock::new(HashMap::new()),
+        };
+        slots.get(IndexSlot::Live)?;
+        Ok(Self {
+            slots: Arc::new(slots),
+        })
+    }
+
+    /// 索引读写均为阻塞操作，放到阻塞线程池中执行
+    async fn blocking<T, F>(&self, f: F) -> Result<T, ProcessError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Slots) -> tantivy::Result<T> + Send + 'static,
+    {
+        let slots = Arc::clone(&self.slots);
+        tokio::task::spawn_blocking(move || f(&slots))
+            .await
+            .map_err(|e| TantivyError::ErrorInThread(e.to_string()))?
+            .map_err(Into::into)
+    }
+}
+
+#[async_trait]
+impl SearchBackend for EmbeddedBackend {
+    async fn prepare(&self, slot: IndexSlot) -> Result<(), ProcessError> {
+        self.blocking(move |slots| slots.get(slot).map(|_| ()))
+            .await
+    }
+
+    async fn upsert_posts(
+        &self,
+        slot: IndexSlot,
+        posts: Vec<post::Model>,
+    ) -> Result<(), ProcessError> {
+        if posts.is_empty() {
+            return Ok(());
+        }
+        self.blocking(move |slots| slots.get(slot)?.upsert(posts))
+            .await
+    }
+
+    async fn delete_posts(&self, slot: IndexSlot, post_ids: &[i32]) -> Result<(), ProcessError> {
+        if post_ids.is_empty() {
+            return Ok(());
+        }
+        let post_ids = post_ids.to_vec();
+        self.blocking(move |slots| slots.get(slot)?.delete(&post_ids))
+            .await
+    }
+
+    async fn list_post_ids(&self, slot: IndexSlot) -> Result<HashSet<i32>, ProcessError> {
+        self.blocking(move |slots| slots.get(slot)?.list_ids())
+            .await
+    }
+
+    async fn reset(&self, slot: IndexSlot) -> Result<(), ProcessError> {
+        self.blocking(move |slots| slots.reset(slot)).await
+    }
+
+    async fn promote_rebuild(&self) -> Result<(), ProcessError> {
+        self.blocking(|slots| slots.promote_rebuild()).await
+    }
+
+    async fn search_posts(
+        &self,
+        request: SearchRequest<'_>,
+    ) -> Result<PostSearchResult, ProcessError> {
+        let request = OwnedSearchRequest {
+            query: request.query.to_string(),
+            filter: request.filter,
+            sort: request.sort,
+            facets: request.facets.to_vec(),
+            page_size: request.page_size,
+            page_index: request.page_index,
+        };
+        self.blocking(move |slots| slots.get(IndexSlot::Live)?.search(&request))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn tokens(text: &str) -> Vec<String> {
+        let mut tokenizer = CjkBigramTokenizer::default();
+        let mut stream = tokenizer.token_stream(text);
+        let mut tokens = vec![];
+        while stream.advance() {
+            tokens.push(stream.token().text.clone());
+        }
+        tokens
+    }
+
+    fn post(post_id: i32, title: &str, tag: &str, day: u32) -> post::Model {
+        post::Model {
+            post_id,
+            post_term: Some("2023/2024/2".into()),
+            post_title: Some(title.into()),
+            post_content: Some(format!("{}的正文", title)),
+            post_tag_01: Some(tag.into()),
+            post_date: NaiveDate::from_ymd_opt(2024, 3, day)
+                .and_then(|date| date.and_hms_opt(12, 0, 0)),
+            ..Default::default()
+        }
+    }
+
+    /// 写入4个帖子：id为1..=4，发帖日期为3月1..=4日，只有偶数id的帖子带有标签1
+    fn sample_index() -> (tempfile::TempDir, EmbeddedIndex) {
+        let dir = tempfile::tempdir().unwrap();
+        let index = EmbeddedIndex::open(dir.path()).unwrap();
+        index
+            .upsert(
+                (1..=4)
+                    .map(|id| {
+                        let tag = if id % 2 == 0 { "1" } else { "0" };
+                        post(id, &format!("第{}周作业", id), tag, id as u32)
+                    })
+                    .collect(),
+            )
+            .unwrap();
+        (dir, index)
+    }
+
+    fn search_ids(index: &EmbeddedIndex, query: &str, filter: SearchFilter) -> Vec<i32> {
+        let request = OwnedSearchRequest {
+            query: query.into(),
+            filter,
+            sort: PostSearchSort::Oldest,
+            facets: vec![],
+            page_size: 10,
+            page_index: 1,
+        };
+        let result = index.search(&request).unwrap();
+        result.hits.iter().map(|hit| hit.post.post_id).collect()
+    }
+
+    fn day_timestamp(day: u32) -> Option<i64> {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .and_then(super::super::local_timestamp)
+    }
+
+    #[test]
+    fn test_cjk_bigrams() {
+        assert_eq!(tokens("数据结构"), ["数据", "据结", "结构"]);
+        assert_eq!(tokens("题"), ["题"]);
+        assert_eq!(tokens("作业，第一题"), ["作业", "第一", "一题"]);
+    }
+
+    #[test]
+    fn test_mixed_ascii_and_cjk() {
+        assert_eq!(
+            tokens("C++的Vector用法 HW2"),
+            ["c", "的", "vector", "用法", "hw2"]
+        );
+        assert_eq!(tokens("用STL实现"), ["用", "stl", "实现"]);
+    }
+
+    #[test]
+    fn test_token_offsets() {
+        let mut tokenizer = CjkBigramTokenizer::default();
+        let mut stream = tokenizer.token_stream("a作业");
+        let mut offsets = vec![];
+        while stream.advance() {
+            let token = stream.token();
+            offsets.push((token.offset_from, token.offset_to, token.position));
+        }
+        assert_eq!(offsets, [(0, 1, 0), (1, 7, 1)]);
+    }
+
+    #[test]
+    fn test_phrase_query_matches_bigrams() {
+        let (_dir, index) = sample_index();
+        assert_eq!(
+            search_ids(&index, "第3周作业", SearchFilter::All(vec![])),
+            [3]
+        );
+        assert_eq!(
+            search_ids(&index, "作业", SearchFilter::All(vec![])),
+            [1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let (_dir, index) = sample_index();
+        assert_eq!(
+            search_ids(&index, "", SearchFilter::eq("postTag01", "1")),
+            [2, 4]
+        );
+    }
+
+    #[test]
+    fn test_range_filter() {
+        let (_dir, index) = sample_index();
+        let between = SearchFilter::Range(TIMESTAMP_ATTRIBUTE, day_timestamp(2), day_timestamp(4));
+        assert_eq!(search_ids(&index, "", between), [2, 3]);
+        let since = SearchFilter::Range(TIMESTAMP_ATTRIBUTE, day_timestamp(3), None);
+        assert_eq!(search_ids(&index, "", since), [3, 4]);
+        let unbounded = SearchFilter::Range(TIMESTAMP_ATTRIBUTE, None, None);
+        assert_eq!(search_ids(&index, "", unbounded), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_all_and_any_filters() {
+        let (_dir, index) = sample_index();
+        let all = SearchFilter::All(vec![
+            SearchFilter::eq("postTag01", "1"),
+            SearchFilter::Range(TIMESTAMP_ATTRIBUTE, day_timestamp(3), None),
+        ]);
+        assert_eq!(search_ids(&index, "", all), [4]);
+
+        let any = SearchFilter::Any(vec![
+            SearchFilter::eq("postTag01", "1"),
+            SearchFilter::Range(TIMESTAMP_ATTRIBUTE, None, day_timestamp(2)),
+        ]);
+        assert_eq!(search_ids(&index, "", any), [1, 2, 4]);
+        assert!(search_ids(&index, "", SearchFilter::Any(vec![])).is_empty());
+    }
+
+    #[test]
+    fn test_any_filter_with_unconstrained_branch_matches_all() {
+        let (_dir, index) = sample_index();
+        let any = SearchFilter::Any(vec![
+            SearchFilter::Range(TIMESTAMP_ATTRIBUTE, None, None),
+            SearchFilter::eq("postTag01", "1"),
+        ]);
+        assert_eq!(search_ids(&index, "", any), [1, 2, 3, 4]);
+        let any = SearchFilter::Any(vec![SearchFilter::All(vec![])]);
+        assert_eq!(search_ids(&index, "", any), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_promote_rebuild_replaces_live_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let slots = Slots {
+            root: dir.path().to_path_buf(),
+            indexes: RwLock::new(HashMap::new()),
+        };
+        let ids = |slot| slots.get(slot).unwrap().list_ids().unwrap();
+
+        slots
+            .get(IndexSlot::Live)
+            .unwrap()
+            .upsert(vec![post(1, "旧", "0", 1)])
+            .unwrap();
+        assert!(slots.promote_rebuild().is_err());
+        assert_eq!(ids(IndexSlot::Live), HashSet::from([1]));
+
+        // 两个目录轮流作为正式索引
+        for (round, expected_dir) in [(2, "post_rebuild"), (3, "post")] {
+            slots.reset(IndexSlot::Rebuild).unwrap();
+            slots
+                .get(IndexSlot::Rebuild)
+                .unwrap()
+                .upsert(vec![post(round, "新", "0", round as u32)])
+                .unwrap();
+            slots.promote_rebuild().unwrap();
+
+            assert_eq!(ids(IndexSlot::Live), HashSet::from([round]));
+            assert_eq!(slots.dir(IndexSlot::Live), expected_dir);
+            assert!(!slots.path(IndexSlot::Rebuild).exists());
+        }
+
+        // 重新打开时使用记录的正式索引目录
+        drop(slots.indexes.write().remove(&IndexSlot::Live));
+        assert_eq!(ids(IndexSlot::Live), HashSet::from([3]));
+    }
+}
@@ -0,0 +1,377 @@
+//! Meilisearch搜索后端
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use meilisearch_sdk::client::{Client, SwapIndexes};
+use meilisearch_sdk::documents::DocumentsQuery;
+use meilisearch_sdk::errors::Error as MeiliErr;
+use meilisearch_sdk::indexes::Index;
+use meilisearch_sdk::search::Selectors;
+use meilisearch_sdk::task_info::TaskInfo;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    config::meili::Meili,
+    dto::post_search::{PostSearchHit, PostSearchResult, PostSearchSort},
+    entity::post,
+    error::proc_error::ProcessError,
+};
+
+use super::{
+    post_timestamp, render_marked_highlight, FilterValue, IndexSlot, SearchBackend, SearchFilter,
+    SearchRequest, FILTERABLE_ATTRIBUTES, HIGHLIGHT_POST_MARK, HIGHLIGHT_PRE_MARK,
+    SNIPPET_CROP_LENGTH, SORTABLE_ATTRIBUTES,
+};
+
+/// 等待搜索引擎任务完成的超时时间
+const TASK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 重建索引时临时索引名的后缀
+const REBUILD_INDEX_SUFFIX: &str = "_rebuild";
+
+/// 列出文档id时每页读取的文档数
+const LIST_PAGE_SIZE: usize = 1000;
+
+/// 按发帖时间排序的规则
+const SORT_NEWEST: &[&str] = &["postTimestamp:desc"];
+const SORT_OLDEST: &[&str] = &["postTimestamp:asc"];
+
+/// 写入索引的文档，附加派生字段
+#[derive(Debug, Serialize)]
+struct PostDocument {
+    #[serde(flatten)]
+    post: post::Model,
+
+    #[serde(rename = "postTimestamp")]
+    post_timestamp: Option<i64>,
+}
+
+/// 只读取文档的id
+#[derive(Debug, Deserialize)]
+struct IndexedPostId {
+    #[serde(rename = "postId")]
+    post_id: i32,
+}
+
+pub struct MeiliBackend {
+    meili: Meili,
+    index_uid: String,
+    rebuild_uid: String,
+}
+
+impl MeiliBackend {
+    pub fn new(meili: Meili, index_uid: &str) -> Self {
+        Self {
+            meili,
+            index_uid: index_uid.to_string(),
+            rebuild_uid: format!("{}{}", index_uid, REBUILD_INDEX_SUFFIX),
+        }
+    }
+
+    fn client(&self) -> &Client {
+        self.meili.get_client()
+    }
+
+    fn uid(&self, slot: IndexSlot) -> &str {
+        match slot {
+            IndexSlot::Live => &self.index_uid,
+            IndexSlot::Rebuild => &self.rebuild_uid,
+        }
+    }
+
+    fn index(&self, slot: IndexSlot) -> Index {
+        self.client().index(self.uid(slot))
+    }
+
+    /// 等待搜索引擎任务完成，任务失败时返回错误
+    async fn wait_task(&self, task: TaskInfo) -> Result<(), ProcessError> {
+        let task = task
+            .wait_for_completion(self.client(), None, Some(TASK_TIMEOUT))
+            .await?;
+        if task.is_failure() {
+            return Err(MeiliErr::Meilisearch(task.unwrap_failure()).into());
+        }
+        Ok(())
+    }
+
+    fn escape(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+
+    /// 转换为Meilisearch的过滤表达式，`None`表示不过滤
+    fn to_filter_expr(filter: &SearchFilter) -> Option<String> {
+        match filter {
+            SearchFilter::Eq(field, FilterValue::Str(value)) => {
+                Some(format!("{} = \"{}\"", field, Self::escape(value)))
+            }
+            SearchFilter::Eq(field, FilterValue::Int(value)) => {
+                Some(format!("{} = {}", field, value))
+            }
+            SearchFilter::Range(field, min, max) => {
+                let exprs: Vec<_> = [(">=", min), ("<=", max)]
+                    .into_iter()
+                    .filter_map(|(op, bound)| bound.map(|b| format!("{} {} {}", field, op, b)))
+                    .collect();
+                match exprs.len() {
+                    0 => None,
+                    _ => Some(format!("({})", exprs.join(" AND "))),
+                }
+            }
+            SearchFilter::All(filters) => {
+                let exprs: Vec<_> = filters.iter().filter_map(Self::to_filter_expr).collect();
+                match exprs.len() {
+                    0 => None,
+                    _ => Some(format!("({})", exprs.join(" AND "))),
+                }
+            }
+            SearchFilter::Any(filters) => {
+                // 任一子条件不限制时整体不限制，没有子条件时不匹配任何文档
+                let exprs = filters
+                    .iter()
+                    .map(Self::to_filter_expr)
+                    .collect::<Option<Vec<_>>>()?;
+                Some(match exprs.len() {
+                    0 => format!("({0} EXISTS AND NOT {0} EXISTS)", FILTERABLE_ATTRIBUTES[0]),
+                    _ => format!("({})", exprs.join(" OR ")),
+                })
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SearchBackend for MeiliBackend {
+    async fn prepare(&self, slot: IndexSlot) -> Result<(), ProcessError> {
+        let index = self.index(slot);
+        self.wait_task(
+            index
+                .set_filterable_attributes(FILTERABLE_ATTRIBUTES)
+                .await?,
+        )
+        .await?;
+        self.wait_task(index.set_sortable_attributes(SORTABLE_ATTRIBUTES).await?)
+            .await
+    }
+
+    async fn upsert_posts(
+        &self,
+        slot: IndexSlot,
+        posts: Vec<post::Model>,
+    ) -> Result<(), ProcessError> {
+        if posts.is_empty() {
+            return Ok(());
+        }
+        let documents: Vec<_> = posts
+            .into_iter()
+            .map(|post| PostDocument {
+                post_timestamp: post_timestamp(&post),
+                post,
+            })
+            .collect();
+        let task = self
+            .index(slot)
+            .add_documents(&documents, Some("postId"))
+            .await?;
+        self.wait_task(task).await
+    }
+
+    async fn delete_posts(&self, slot: IndexSlot, post_ids: &[i32]) -> Result<(), ProcessError> {
+        if post_ids.is_empty() {
+            return Ok(());
+        }
+        let task = self.index(slot).delete_documents(post_ids).await?;
+        self.wait_task(task).await
+    }
+
+    async fn list_post_ids(&self, slot: IndexSlot) -> Result<HashSet<i32>, ProcessError> {
+        let index = self.index(slot);
+
+        let mut ids = HashSet::new();
+        let mut offset = 0;
+        loop {
+            let page = DocumentsQuery::new(&index)
+                .with_offset(offset)
+                .with_limit(LIST_PAGE_SIZE)
+                .with_fields(["postId"])
+                .execute::<IndexedPostId>()
+                .await?;
+            let count = page.results.len();
+            offset += count;
+            ids.extend(page.results.into_iter().map(|doc| doc.post_id));
+            if count < LIST_PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(ids)
+    }
+
+    async fn reset(&self, slot: IndexSlot) -> Result<(), ProcessError> {
+        let client = self.client();
+        let uid = self.uid(slot);
+
+        // 索引不存在时删除任务失败，忽略即可
+        let _ = self.wait_task(client.delete_index(uid).await?).await;
+        self.wait_task(client.create_index(uid, Some("postId")).await?)
+            .await?;
+        self.prepare(slot).await
+    }
+
+    async fn promote_rebuild(&self) -> Result<(), ProcessError> {
+        let client = self.client();
+
+        // 交换要求两个索引都存在，正式索引已存在时创建任务失败，忽略即可
+        let _ = self
+            .wait_task(client.create_index(&self.index_uid, Some("postId")).await?)
+            .await;
+        self.wait_task(
+            client
+                .swap_indexes([&SwapIndexes {
+                    indexes: (self.index_uid.clone(), self.rebuild_uid.clone()),
+                }])
+                .await?,
+        )
+        .await?;
+
+        // 交换后临时索引中是旧数据
+        self.wait_task(client.delete_index(&self.rebuild_uid).await?)
+            .await
+    }
+
+    async fn search_posts(
+        &self,
+        request: SearchRequest<'_>,
+    ) -> Result<PostSearchResult, ProcessError> {
+        let index = self.index(IndexSlot::Live);
+        let filter = Self::to_filter_expr(&request.filter);
+
+        let mut search = index.search();
+        search
+            .with_query(request.query)
+            .with_page(request.page_index)
+            .with_hits_per_page(request.page_size)
+            .with_attributes_to_highlight(Selectors::Some(&["postTitle", "postContent"]))
+            .with_attributes_to_crop(Selectors::Some(&[("postContent", None)]))
+            .with_crop_length(SNIPPET_CROP_LENGTH)
+            .with_highlight_pre_tag(HIGHLIGHT_PRE_MARK)
+            .with_highlight_post_tag(HIGHLIGHT_POST_MARK)
+            .with_facets(Selectors::Some(request.facets));
+        if let Some(filter) = &filter {
+            search.with_filter(filter);
+        }
+        match request.sort {
+            PostSearchSort::Relevance => {}
+            PostSearchSort::Newest => {
+                search.with_sort(SORT_NEWEST);
+            }
+            PostSearchSort::Oldest => {
+                search.with_sort(SORT_OLDEST);
+            }
+        }
+        let results = search.execute::<post::Model>().await?;
+
+        let hits = results
+            .hits
+            .into_iter()
+            .map(|hit| {
+                let formatted = hit.formatted_result.unwrap_or_default();
+
+                PostSearchHit {
+                    highlighted_title: formatted_field(&formatted, "postTitle"),
+                    snippet: formatted_field(&formatted, "postContent"),
+                    post: post::Model {
+                        post_content: None,
+                        ..hit.result
+                    },
+                }
+            })
+            .collect();
+
+        Ok(PostSearchResult {
+            hits,
+            total_hits: results.total_hits.unwrap_or_default(),
+            total_pages: results.total_pages.unwrap_or_default(),
+            page_size: request.page_size,
+            page_index: request.page_index,
+            processing_time_ms: results.processing_time_ms,
+            facets: results.facet_distribution.unwrap_or_default(),
+        })
+    }
+}
+
+/// 高亮结果中的字段，Meilisearch只插入高亮标记而不转义原文，需要在此转义
+fn formatted_field(formatted: &Map<String, Value>, key: &str) -> Option<String> {
+    formatted
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(render_marked_highlight)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn formatted(title: &str) -> Map<String, Value> {
+        match json!({ "postTitle": title }) {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_formatted_title_is_escaped() {
+        let title = format!(
+            "<script>alert(1)</script> {}作业{}",
+            HIGHLIGHT_PRE_MARK, HIGHLIGHT_POST_MARK
+        );
+        assert_eq!(
+            formatted_field(&formatted(&title), "postTitle").as_deref(),
+            Some("&lt;script&gt;alert(1)&lt;/script&gt; <em>作业</em>")
+        );
+    }
+
+    #[test]
+    fn test_formatted_field_missing() {
+        assert_eq!(formatted_field(&formatted("作业"), "postContent"), None);
+    }
+
+    #[test]
+    fn test_any_filter() {
+        let filter = SearchFilter::Any(vec![
+            SearchFilter::eq("postSno", "2150000"),
+            SearchFilter::eq("postWeek", 3i64),
+        ]);
+        assert_eq!(
+            MeiliBackend::to_filter_expr(&filter).as_deref(),
+            Some("(postSno = \"2150000\" OR postWeek = 3)")
+        );
+    }
+
+    #[test]
+    fn test_any_filter_with_unconstrained_branch_is_unconstrained() {
+        let filter = SearchFilter::Any(vec![
+            SearchFilter::Range("postTimestamp", None, None),
+            SearchFilter::eq("postSno", "2150000"),
+        ]);
+        assert_eq!(MeiliBackend::to_filter_expr(&filter), None);
+        let filter = SearchFilter::Any(vec![SearchFilter::All(vec![])]);
+        assert_eq!(MeiliBackend::to_filter_expr(&filter), None);
+    }
+
+    #[test]
+    fn test_empty_any_filter_matches_nothing() {
+        let filter = SearchFilter::Any(vec![]);
+        assert_eq!(
+            MeiliBackend::to_filter_expr(&filter).as_deref(),
+            Some("(postTerm EXISTS AND NOT postTerm EXISTS)")
+        );
+        let filter = SearchFilter::All(vec![SearchFilter::Any(vec![]), SearchFilter::All(vec![])]);
+        assert_eq!(
+            MeiliBackend::to_filter_expr(&filter).as_deref(),
+            Some("((postTerm EXISTS AND NOT postTerm EXISTS))")
+        );
+    }
+}
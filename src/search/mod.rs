@@ -0,0 +1,271 @@
+//! 搜索后端
+//!
+//! 帖子的索引与查询通过`SearchBackend`完成，具体使用哪个后端由配置中的`search.backend`决定：
+//! `meili`使用外部的Meilisearch服务，`embedded`使用进程内的磁盘全文索引，无需任何外部服务。
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Local, NaiveDateTime};
+
+use crate::{
+    config::{meili::Meili, search::SearchBackendKind},
+    dto::post_search::{PostSearchResult, PostSearchSort},
+    entity::post,
+    error::proc_error::ProcessError,
+};
+
+pub mod embedded;
+pub mod meili;
+
+/// 可用于过滤的帖子字段（索引文档中的字段名）
+pub const FILTERABLE_ATTRIBUTES: &[&str] = &[
+    "postTerm",
+    "postCcode",
+    "postHwId",
+    "postWeek",
+    "postChapter",
+    "postAnswerId",
+    "postIsDel",
+    "postType",
+    "postSno",
+    "postAnonymous",
+    "postStatus",
+    "postVisibility",
+    "postPrivateSno",
+    "postTag01",
+    "postTag02",
+    "postTag03",
+    "postTag04",
+    "postTag05",
+    "postTag06",
+    "postTag07",
+    "postTag08",
+    "postTag09",
+    "postTag10",
+    TIMESTAMP_ATTRIBUTE,
+];
+
+/// 可用于排序的字段
+pub const SORTABLE_ATTRIBUTES: &[&str] = &[TIMESTAMP_ATTRIBUTE];
+
+/// 返回分面统计的字段
+pub const FACET_ATTRIBUTES: &[&str] = &[
+    "postWeek",
+    "postHwId",
+    "postType",
+    "postSno",
+    "postStatus",
+    "postTag01",
+    "postTag02",
+    "postTag03",
+    "postTag04",
+    "postTag05",
+    "postTag06",
+    "postTag07",
+    "postTag08",
+    "postTag09",
+    "postTag10",
+];
+
+/// 帖子的10个标签字段
+pub const TAG_ATTRIBUTES: [&str; 10] = [
+    "postTag01",
+    "postTag02",
+    "postTag03",
+    "postTag04",
+    "postTag05",
+    "postTag06",
+    "postTag07",
+    "postTag08",
+    "postTag09",
+    "postTag10",
+];
+
+/// 发帖时间的时间戳（秒），由`post_date`派生，用于时间范围过滤与排序
+pub const TIMESTAMP_ATTRIBUTE: &str = "postTimestamp";
+
+/// 搜索结果中正文片段的长度（词数）
+pub const SNIPPET_CROP_LENGTH: usize = 40;
+
+/// 高亮标签
+pub const HIGHLIGHT_PRE_TAG: &str = "<em>";
+pub const HIGHLIGHT_POST_TAG: &str = "</em>";
+
+/// 搜索引擎标记高亮位置使用的控制字符，转义文本后再替换为高亮标签
+pub const HIGHLIGHT_PRE_MARK: &str = "\u{2}";
+pub const HIGHLIGHT_POST_MARK: &str = "\u{3}";
+
+/// 索引槽位，重建索引时先写入`Rebuild`，完成后替换`Live`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexSlot {
+    Live,
+    Rebuild,
+}
+
+/// 过滤条件中的值
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Str(String),
+    Int(i64),
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_string())
+    }
+}
+
+impl From<i64> for FilterValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<i16> for FilterValue {
+    fn from(value: i16) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i8> for FilterValue {
+    fn from(value: i8) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+/// 搜索过滤条件
+#[derive(Debug, Clone)]
+pub enum SearchFilter {
+    /// 字段等于某值
+    Eq(&'static str, FilterValue),
+    /// 数值字段在闭区间内，边界为空表示不限
+    Range(&'static str, Option<i64>, Option<i64>),
+    /// 同时满足所有条件（为空时不过滤）
+    All(Vec<SearchFilter>),
+    /// 满足任一条件（为空时不匹配任何文档）
+    Any(Vec<SearchFilter>),
+}
+
+impl SearchFilter {
+    pub fn eq(field: &'static str, value: impl Into<FilterValue>) -> Self {
+        Self::Eq(field, value.into())
+    }
+
+    /// 是否不限制任何文档
+    pub fn is_unconstrained(&self) -> bool {
+        match self {
+            Self::Eq(..) => false,
+            Self::Range(_, min, max) => min.is_none() && max.is_none(),
+            Self::All(filters) => filters.iter().all(Self::is_unconstrained),
+            Self::Any(filters) => filters.iter().any(Self::is_unconstrained),
+        }
+    }
+}
+
+/// 帖子的标签字段名（如`post_tag_01`）对应的索引字段
+pub fn tag_attribute(tag_field_name: &str) -> Option<&'static str> {
+    let index = tag_field_name
+        .strip_prefix("post_tag_")?
+        .parse::<usize>()
+        .ok()?
+        .checked_sub(1)?;
+    TAG_ATTRIBUTES.get(index).copied()
+}
+
+/// 转义HTML特殊字符，帖子标题与索引中的正文都是未转义的纯文本
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 转义以高亮标记标出命中位置的文本，并将标记替换为高亮标签
+pub fn render_marked_highlight(marked: &str) -> String {
+    escape_html(marked)
+        .replace(HIGHLIGHT_PRE_MARK, HIGHLIGHT_PRE_TAG)
+        .replace(HIGHLIGHT_POST_MARK, HIGHLIGHT_POST_TAG)
+}
+
+/// 本地时间对应的时间戳
+pub fn local_timestamp(date: NaiveDateTime) -> Option<i64> {
+    date.and_local_timezone(Local)
+        .earliest()
+        .map(|date| date.timestamp())
+}
+
+/// 发帖时间的时间戳
+pub fn post_timestamp(post: &post::Model) -> Option<i64> {
+    post.post_date.and_then(local_timestamp)
+}
+
+/// 搜索请求
+#[derive(Debug, Clone)]
+pub struct SearchRequest<'a> {
+    /// 搜索关键词
+    pub query: &'a str,
+
+    /// 过滤条件，包含权限限制
+    pub filter: SearchFilter,
+
+    /// 排序方式
+    pub sort: PostSearchSort,
+
+    /// 需要统计分面的字段
+    pub facets: &'a [&'static str],
+
+    /// 分页: 页面大小
+    pub page_size: usize,
+
+    /// 分页: 页面编号（从1开始）
+    pub page_index: usize,
+}
+
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// 初始化索引，应用可过滤字段等设置
+    async fn prepare(&self, slot: IndexSlot) -> Result<(), ProcessError>;
+
+    /// 写入或更新帖子文档，正文应已转换为纯文本
+    async fn upsert_posts(
+        &self,
+        slot: IndexSlot,
+        posts: Vec<post::Model>,
+    ) -> Result<(), ProcessError>;
+
+    /// 删除帖子文档
+    async fn delete_posts(&self, slot: IndexSlot, post_ids: &[i32]) -> Result<(), ProcessError>;
+
+    /// 列出索引中的全部帖子id
+    async fn list_post_ids(&self, slot: IndexSlot) -> Result<HashSet<i32>, ProcessError>;
+
+    /// 清空并重新初始化索引
+    async fn reset(&self, slot: IndexSlot) -> Result<(), ProcessError>;
+
+    /// 用重建好的索引原子地替换正式索引
+    async fn promote_rebuild(&self) -> Result<(), ProcessError>;
+
+    /// 在正式索引中搜索帖子
+    async fn search_posts(
+        &self,
+        request: SearchRequest<'_>,
+    ) -> Result<PostSearchResult, ProcessError>;
+}
+
+/// 根据配置创建搜索后端
+pub fn init_backend() -> Result<Arc<dyn SearchBackend>, ProcessError> {
+    let config = crate::config::get_config();
+    let guard = config.read().unwrap();
+
+    Ok(match guard.search.backend {
+        SearchBackendKind::Meili => Arc::new(meili::MeiliBackend::new(
+            Meili::init(),
+            &guard.meili.index.post,
+        )),
+        SearchBackendKind::Embedded => Arc::new(embedded::EmbeddedBackend::open(
+            &guard.search.embedded_path,
+        )?),
+    })
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use fred::clients::SubscriberClient;
+use fred::interfaces::{ClientLike, EventInterface, PubsubInterface};
+use fred::types::{Message, ReconnectPolicy};
+use log::warn;
+use once_cell::sync::OnceCell;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::config::redis::{Redis, RedisTrait};
+use crate::dto::forum_event::ForumEvent;
+use crate::error::proc_error::ProcessError;
+
+/// 实时事件的Redis频道，所有实例都发布到该频道，再推送给各自的连接
+const EVENT_CHANNEL: &str = "forum-events";
+
+/// 本实例缓冲的事件数，推送较慢的连接落后超过该数量时会丢失事件
+const EVENT_BUFFER_SIZE: usize = 1024;
+
+/// 订阅失败后重新订阅的间隔
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[async_trait]
+pub trait EventServiceTrait {
+    /// 将事件发布给所有实例，发布失败只记录日志
+    async fn publish(&self, event: ForumEvent);
+
+    /// 订阅本实例收到的事件
+    fn subscribe(&self) -> broadcast::Receiver<Arc<ForumEvent>>;
+}
+
+static SERVICE_RUNNER: OnceCell<Arc<EventServiceRunner>> = OnceCell::new();
+
+/// 订阅Redis频道，将其他实例发布的事件转发给本实例的连接
+pub struct EventServiceRunner {
+    redis: Arc<Redis>,
+    sender: broadcast::Sender<Arc<ForumEvent>>,
+}
+
+impl EventServiceRunner {
+    pub fn init(redis: &Arc<Redis>) {
+        if SERVICE_RUNNER.get().is_none() {
+            let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+            let runner = Arc::new(EventServiceRunner {
+                redis: Arc::clone(redis),
+                sender,
+            });
+            if SERVICE_RUNNER.set(runner.clone()).is_ok() {
+                runner.run();
+            }
+        }
+    }
+
+    fn run(&self) {
+        let config = self.redis.get_pool().next().client_config();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            // 订阅需要独占连接，不能使用连接池；断线后由客户端重新连接并恢复订阅
+            let subscriber =
+                SubscriberClient::new(config, None, None, Some(ReconnectPolicy::default()));
+            let _connection = subscriber.connect();
+            let _resubscription = subscriber.manage_subscriptions();
+            let messages = subscriber.message_rx();
+
+            while let Err(e) = Self::subscribe_channel(&subscriber).await {
+                warn!("订阅实时事件失败：{}", e);
+                tokio::time::sleep(RESUBSCRIBE_INTERVAL).await;
+            }
+            Self::forward(messages, &sender).await;
+
+            if let Err(e) = subscriber.quit().await {
+                warn!("关闭实时事件订阅失败：{}", e);
+            }
+        });
+    }
+
+    async fn subscribe_channel(subscriber: &SubscriberClient) -> Result<(), ProcessError> {
+        subscriber.wait_for_connect().await?;
+        subscriber.subscribe(EVENT_CHANNEL).await?;
+        Ok(())
+    }
+
+    async fn forward(
+        mut messages: broadcast::Receiver<Message>,
+        sender: &broadcast::Sender<Arc<ForumEvent>>,
+    ) {
+        loop {
+            let message = match messages.recv().await {
+                Ok(message) => message,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("实时事件处理过慢，丢失{}个事件", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let Some(payload) = message.value.as_str() else {
+                continue;
+            };
+            match serde_json::from_str::<ForumEvent>(&payload) {
+                // 没有连接时发送失败，忽略即可
+                Ok(event) => {
+                    let _ = sender.send(Arc::new(event));
+                }
+                Err(e) => warn!("无法解析实时事件：{}", e),
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventService {
+    redis: Arc<Redis>,
+}
+
+impl EventService {
+    pub fn new(redis: &Arc<Redis>) -> Self {
+        EventServiceRunner::init(redis);
+        Self {
+            redis: Arc::clone(redis),
+        }
+    }
+}
+
+#[async_trait]
+impl EventServiceTrait for EventService {
+    async fn publish(&self, event: ForumEvent) {
+        let payload = match serde_json::to_string(&event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("无法序列化实时事件：{}", e);
+                return;
+            }
+        };
+        if let Err(e) = self
+            .redis
+            .get_pool()
+            .next()
+            .publish::<(), _, _>(EVENT_CHANNEL, payload)
+            .await
+        {
+            warn!("发布实时事件失败：{}", e);
+        }
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<Arc<ForumEvent>> {
+        SERVICE_RUNNER.get().unwrap().sender.subscribe()
+    }
+}
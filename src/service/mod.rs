@@ -1,11 +1,14 @@
 pub mod auth_service;
 pub mod board_service;
 pub mod course_service;
+pub mod event_service;
 pub mod homework_service;
 pub mod log_service;
 pub mod metadata_service;
 pub mod notification_service;
 pub mod post_service;
+pub mod read_state_service;
+pub mod scheduler_service;
 pub mod search_engine_service;
 pub mod student_info_service;
 pub mod upload_service;
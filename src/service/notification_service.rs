@@ -1,111 +1,408 @@
 use crate::config::database::{DatabaseTrait, Db};
+use crate::config::redis::Redis;
+use crate::config::AppConfig;
+use crate::dto::forum_event::ForumEvent;
+use crate::dto::notification_page::NotificationPage;
+use crate::dto::notification_preference::{
+    NotificationMode, NotificationPreference, NOTIFICATION_TYPES,
+};
 use crate::entity::notification;
 use crate::entity::notification::{Column, Entity, Model as Notification};
+use crate::entity::notification_pending::Model as PendingNotification;
 use crate::error::api_error::ApiError;
 use crate::error::param_error::ParameterError;
+use crate::repository::notification_pending_repo::{
+    NotificationPendingRepository, NotificationPendingRepositoryTrait,
+};
+use crate::repository::notification_preference_repo::{
+    NotificationPreferenceRepository, NotificationPreferenceRepositoryTrait,
+};
 use crate::repository::notification_repo::NotificationRepository;
+use crate::service::event_service::{EventService, EventServiceTrait};
 use async_trait::async_trait;
-use chrono::{Days, Local};
-use once_cell::sync::OnceCell;
+use chrono::{Days, Local, NaiveDateTime, Timelike};
 use sea_orm::sea_query::Expr;
 use sea_orm::ActiveValue::Set;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, NotSet, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, NotSet,
+    QueryFilter, TransactionTrait,
+};
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::time::interval;
+
+/// 每批合并摘要的接收者数
+const DIGEST_BATCH_SIZE: u64 = 200;
+
+/// 群发通知时每批写入的接收者数
+const BROADCAST_BATCH_SIZE: usize = 500;
 
 #[async_trait]
 pub trait NotificationServiceTrait {
-    /// 发送通知
+    /// 按接收者对该类通知的设置发送通知：即时发送、留待合并为摘要或不发送
     async fn send_notification(&self, notification: Notification) -> Result<(), ApiError>;
 
-    /// 用户获取通知
-    async fn get_notifications(&self, user_id: &str) -> Result<Vec<Notification>, ApiError>;
+    /// 向多个接收者发送相同的通知，按各自的设置分批写入，`ntf_receiver`字段会被忽略
+    async fn broadcast_notification(
+        &self,
+        notification: Notification,
+        receivers: &[String],
+    ) -> Result<(), ApiError>;
+
+    /// 用户对各类通知的接收方式，未设置的类型为即时接收
+    async fn get_preferences(&self, user_id: &str)
+        -> Result<Vec<NotificationPreference>, ApiError>;
+
+    /// 设置用户对一类通知的接收方式
+    async fn set_preference(
+        &self,
+        user_id: &str,
+        ntf_type: &str,
+        mode: NotificationMode,
+    ) -> Result<(), ApiError>;
+
+    /// 用户分页获取通知，`cursor`为上一页返回的游标
+    async fn get_notifications(
+        &self,
+        user_id: &str,
+        unread_only: bool,
+        cursor: Option<u64>,
+        page_size: u64,
+    ) -> Result<NotificationPage, ApiError>;
+
+    /// 用户未读通知数
+    async fn get_unread_count(&self, user_id: &str) -> Result<u64, ApiError>;
 
     /// 用户已读通知
     async fn user_read_notification(&self, ntf_id: u64, user_id: &str) -> Result<(), ApiError>;
 
+    /// 用户已读指向某个帖子或主题帖的所有通知，返回标为已读的通知数
+    async fn user_read_target_notification(
+        &self,
+        user_id: &str,
+        post_id: Option<i32>,
+        root_id: Option<i32>,
+    ) -> Result<u64, ApiError>;
+
     /// 用户已读所有通知
     async fn user_read_all_notification(&self, user_id: &str) -> Result<(), ApiError>;
 
     /// 用户删除所有通知
     async fn user_delete_all_notification(&self, user_id: &str) -> Result<(), ApiError>;
-}
 
-static SERVICE_RUNNER: OnceCell<Arc<NotificationServiceRunner>> = OnceCell::new();
-
-pub struct NotificationServiceRunner {
-    notification_repository: NotificationRepository,
-}
-
-impl NotificationServiceRunner {
-    pub fn init(db_conn: &Arc<Db>) {
-        if SERVICE_RUNNER.get().is_none() {
-            let runner = Arc::new(NotificationServiceRunner {
-                notification_repository: NotificationRepository::new(db_conn),
-            });
-            if SERVICE_RUNNER.set(runner.clone()).is_ok() {
-                runner.run();
-            }
-        }
-    }
-
-    fn run(&self) {
-        let repo = self.notification_repository.clone();
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(60 * 60));
-
-            {
-                let _ = repo
-                    .delete_all_by_date_time_before(Local::now().naive_local() - Days::new(7))
-                    .await;
-                let _ = repo
-                    .delete_all_by_date_time_before_and_read(
-                        Local::now().naive_local() - Days::new(1),
-                    )
-                    .await;
-            }
-
-            interval.tick().await;
-        });
-    }
+    /// 将到期的待发送通知按接收者合并为摘要发送，返回发送的摘要数，由定时任务调用
+    async fn send_digests(&self) -> Result<u64, ApiError>;
 }
 
 #[derive(Clone)]
 pub struct NotificationService {
     notification_repository: NotificationRepository,
+    notification_preference_repository: NotificationPreferenceRepository,
+    notification_pending_repository: NotificationPendingRepository,
+    event_service: EventService,
     db_conn: Arc<Db>,
+    digest_hour: u32,
 }
 
 impl NotificationService {
-    pub fn new(db_conn: &Arc<Db>) -> Self {
-        NotificationServiceRunner::init(db_conn);
+    pub fn new(db_conn: &Arc<Db>, redis: &Arc<Redis>, app_config: &AppConfig) -> Self {
         Self {
             notification_repository: NotificationRepository::new(db_conn),
+            notification_preference_repository: NotificationPreferenceRepository::new(db_conn),
+            notification_pending_repository: NotificationPendingRepository::new(db_conn),
+            event_service: EventService::new(redis),
             db_conn: db_conn.clone(),
+            digest_hour: app_config.notification.digest_hour.min(23),
         }
     }
-}
 
-#[async_trait]
-impl NotificationServiceTrait for NotificationService {
-    async fn send_notification(&self, notification: Notification) -> Result<(), ApiError> {
+    /// 立即保存通知并推送给在线的接收者
+    async fn deliver(&self, notification: Notification) -> Result<(), ApiError> {
+        let notification = Self::save(self.db_conn.get_db(), notification).await?;
+
+        self.event_service
+            .publish(ForumEvent::Notification { notification })
+            .await;
+
+        Ok(())
+    }
+
+    /// 保存一条新通知，可在事务中调用，提交后再推送
+    async fn save<C: ConnectionTrait>(
+        conn: &C,
+        notification: Notification,
+    ) -> Result<Notification, DbErr> {
         let mut notification = notification;
         notification.ntf_datetime = Local::now().naive_local();
         notification.ntf_read = false;
 
-        let notification = notification::ActiveModel {
+        notification::ActiveModel {
             ntf_id: NotSet,
             ..notification.into_active_model()
+        }
+        .insert(conn)
+        .await
+    }
+
+    /// 将一个接收者的待发送通知合并为一条，只有一条时原样发送
+    fn digest_of(receiver: String, mut items: Vec<PendingNotification>) -> Notification {
+        if items.len() == 1 {
+            let pending = items.pop().unwrap();
+            return Notification {
+                ntf_type: pending.pend_type,
+                ntf_title: pending.pend_title,
+                ntf_content: pending.pend_content,
+                ntf_receiver: receiver,
+                ntf_post_id: pending.pend_post_id,
+                ntf_root_id: pending.pend_root_id,
+                ntf_board_id: pending.pend_board_id,
+                ntf_actor: pending.pend_actor,
+                ..Default::default()
+            };
+        }
+
+        // 都在同一个主题帖中时指向该主题帖
+        let roots: HashSet<_> = items.iter().map(|p| p.pend_root_id).collect();
+        let (root_id, board_id) = match roots.len() {
+            1 => (items[0].pend_root_id, items[0].pend_board_id.clone()),
+            _ => (None, None),
         };
-        notification.save(self.db_conn.get_db()).await?;
+        Notification {
+            ntf_type: "DIGEST".to_string(),
+            ntf_title: "通知摘要".to_string(),
+            ntf_content: Self::digest_content(&items),
+            ntf_receiver: receiver,
+            ntf_root_id: root_id,
+            ntf_board_id: board_id,
+            ..Default::default()
+        }
+    }
 
+    /// 摘要内容，按类型统计，如“3个主题帖中有5条新回复，2条提及”
+    fn digest_content(items: &[PendingNotification]) -> String {
+        let name_of = |ntf_type: &str| {
+            NOTIFICATION_TYPES
+                .iter()
+                .find(|&&(t, _)| t == ntf_type)
+                .map_or("通知", |&(_, name)| name)
+        };
+
+        // 类型名称、通知数与涉及的主题帖，按首次出现的顺序排列
+        let mut groups: Vec<(&str, usize, HashSet<i32>)> = vec![];
+        for pending in items {
+            let name = name_of(&pending.pend_type);
+            match groups.iter_mut().find(|(n, ..)| *n == name) {
+                Some((_, count, threads)) => {
+                    *count += 1;
+                    threads.extend(pending.pend_root_id);
+                }
+                None => groups.push((name, 1, pending.pend_root_id.into_iter().collect())),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(name, count, threads)| match threads.len() {
+                0 | 1 => format!("{}条{}", count, name),
+                threads => format!("{}个主题帖中有{}条{}", threads, count, name),
+            })
+            .collect::<Vec<_>>()
+            .join("，")
+    }
+
+    /// 留待合并为摘要的通知
+    fn pending_of(
+        &self,
+        notification: Notification,
+        mode: NotificationMode,
+        now: NaiveDateTime,
+    ) -> PendingNotification {
+        PendingNotification {
+            pend_id: 0,
+            pend_receiver: notification.ntf_receiver,
+            pend_type: notification.ntf_type,
+            pend_title: notification.ntf_title,
+            pend_content: notification.ntf_content,
+            pend_post_id: notification.ntf_post_id,
+            pend_root_id: notification.ntf_root_id,
+            pend_board_id: notification.ntf_board_id,
+            pend_actor: notification.ntf_actor,
+            pend_datetime: now,
+            pend_due: Self::digest_due(mode, self.digest_hour, now),
+        }
+    }
+
+    /// 以该方式接收的通知随摘要发送的时间：下一个整点，或下一个每日摘要时间（`digest_hour`点）
+    fn digest_due(mode: NotificationMode, digest_hour: u32, now: NaiveDateTime) -> NaiveDateTime {
+        let hour_start = now.date().and_hms_opt(now.hour(), 0, 0).unwrap();
+        match mode {
+            NotificationMode::DailyDigest => {
+                let due = now.date().and_hms_opt(digest_hour, 0, 0).unwrap();
+                if due > now {
+                    due
+                } else {
+                    due + Days::new(1)
+                }
+            }
+            _ => hour_start + chrono::Duration::hours(1),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationServiceTrait for NotificationService {
+    async fn send_notification(&self, notification: Notification) -> Result<(), ApiError> {
+        let preference = self
+            .notification_preference_repository
+            .get(&notification.ntf_receiver, &notification.ntf_type)
+            .await?;
+        let mode = NotificationMode::from_column(preference.as_ref().map(|p| p.pref_mode.as_str()));
+
+        match mode {
+            NotificationMode::Instant => self.deliver(notification).await,
+            NotificationMode::Off => Ok(()),
+            NotificationMode::HourlyDigest | NotificationMode::DailyDigest => {
+                let pending = self.pending_of(notification, mode, Local::now().naive_local());
+                Ok(self.notification_pending_repository.insert(pending).await?)
+            }
+        }
+    }
+
+    async fn broadcast_notification(
+        &self,
+        notification: Notification,
+        receivers: &[String],
+    ) -> Result<(), ApiError> {
+        for chunk in receivers.chunks(BROADCAST_BATCH_SIZE) {
+            let preferences = self
+                .notification_preference_repository
+                .get_by_users(chunk, &notification.ntf_type)
+                .await?;
+            let mode_of = |receiver: &str| {
+                NotificationMode::from_column(
+                    preferences
+                        .iter()
+                        .find(|p| p.pref_sno == receiver)
+                        .map(|p| p.pref_mode.as_str()),
+                )
+            };
+
+            let now = Local::now().naive_local();
+            let mut instant = vec![];
+            let mut pending = vec![];
+            for receiver in chunk {
+                let notification = Notification {
+                    ntf_receiver: receiver.clone(),
+                    ntf_datetime: now,
+                    ntf_read: false,
+                    ..notification.clone()
+                };
+                match mode_of(receiver) {
+                    NotificationMode::Instant => instant.push(notification),
+                    NotificationMode::Off => {}
+                    mode => pending.push(self.pending_of(notification, mode, now)),
+                }
+            }
+
+            let instant_receivers: Vec<_> =
+                instant.iter().map(|n| n.ntf_receiver.clone()).collect();
+            self.notification_repository.insert_many(instant).await?;
+            self.notification_pending_repository
+                .insert_many(pending)
+                .await?;
+
+            // 批量写入不返回通知id，重新查询后推送给在线的接收者
+            if let Some(post_id) = notification
+                .ntf_post_id
+                .filter(|_| !instant_receivers.is_empty())
+            {
+                let delivered = self
+                    .notification_repository
+                    .find_all_by_receivers_and_post(
+                        &instant_receivers,
+                        &notification.ntf_type,
+                        post_id,
+                    )
+                    .await?;
+                for notification in delivered {
+                    self.event_service
+                        .publish(ForumEvent::Notification { notification })
+                        .await;
+                }
+            }
+        }
         Ok(())
     }
 
-    async fn get_notifications(&self, user_id: &str) -> Result<Vec<Notification>, ApiError> {
+    async fn get_preferences(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<NotificationPreference>, ApiError> {
+        let preferences = self
+            .notification_preference_repository
+            .get_by_user(user_id)
+            .await?;
+        Ok(NOTIFICATION_TYPES
+            .iter()
+            .map(|&(ntf_type, name)| NotificationPreference {
+                ntf_type: ntf_type.to_string(),
+                name: name.to_string(),
+                mode: NotificationMode::from_column(
+                    preferences
+                        .iter()
+                        .find(|p| p.pref_type == ntf_type)
+                        .map(|p| p.pref_mode.as_str()),
+                ),
+            })
+            .collect())
+    }
+
+    async fn set_preference(
+        &self,
+        user_id: &str,
+        ntf_type: &str,
+        mode: NotificationMode,
+    ) -> Result<(), ApiError> {
+        if NOTIFICATION_TYPES.iter().all(|&(t, _)| t != ntf_type) {
+            return Err(ParameterError::InvalidParameter("不支持的通知类型").into());
+        }
+        Ok(self
+            .notification_preference_repository
+            .set(user_id, ntf_type, mode.as_str())
+            .await?)
+    }
+
+    async fn get_notifications(
+        &self,
+        user_id: &str,
+        unread_only: bool,
+        cursor: Option<u64>,
+        page_size: u64,
+    ) -> Result<NotificationPage, ApiError> {
+        let mut notifications = self
+            .notification_repository
+            .find_all_by_receiver_order_by_date_time_desc(
+                user_id,
+                unread_only,
+                cursor,
+                page_size + 1,
+            )
+            .await?;
+
+        let next_cursor = if notifications.len() as u64 > page_size {
+            notifications.truncate(page_size as usize);
+            notifications.last().map(|n| n.ntf_id)
+        } else {
+            None
+        };
+        Ok(NotificationPage {
+            notifications,
+            next_cursor,
+        })
+    }
+
+    async fn get_unread_count(&self, user_id: &str) -> Result<u64, ApiError> {
         self.notification_repository
-            .find_all_by_receiver_order_by_date_time_desc(user_id)
+            .count_unread_by_receiver(user_id)
             .await
     }
 
@@ -126,6 +423,17 @@ impl NotificationServiceTrait for NotificationService {
         Err(ParameterError::InvalidParameter("无效的通知id").into())
     }
 
+    async fn user_read_target_notification(
+        &self,
+        user_id: &str,
+        post_id: Option<i32>,
+        root_id: Option<i32>,
+    ) -> Result<u64, ApiError> {
+        self.notification_repository
+            .read_all_by_receiver_and_target(user_id, post_id, root_id)
+            .await
+    }
+
     async fn user_read_all_notification(&self, user_id: &str) -> Result<(), ApiError> {
         Entity::update_many()
             .col_expr(Column::NtfRead, Expr::value(true))
@@ -142,4 +450,143 @@ impl NotificationServiceTrait for NotificationService {
             .delete_all_by_receiver(user_id)
             .await
     }
+
+    async fn send_digests(&self) -> Result<u64, ApiError> {
+        let repo = &self.notification_pending_repository;
+        let mut sent = 0;
+        loop {
+            let now = Local::now().naive_local();
+            let receivers = repo.get_due_receivers(now, DIGEST_BATCH_SIZE).await?;
+            if receivers.is_empty() {
+                return Ok(sent);
+            }
+
+            let mut by_receiver: BTreeMap<String, Vec<PendingNotification>> = BTreeMap::new();
+            for pending in repo.get_due_by_receivers(&receivers, now).await? {
+                by_receiver
+                    .entry(pending.pend_receiver.clone())
+                    .or_default()
+                    .push(pending);
+            }
+            for (receiver, items) in by_receiver {
+                let ids: Vec<_> = items.iter().map(|p| p.pend_id).collect();
+
+                // 摘要与删除待发送通知在同一事务中，失败时不会重复发送
+                let txn = self.db_conn.get_db().begin().await?;
+                let deleted = NotificationPendingRepository::delete_by_ids(&txn, &ids).await?;
+                if deleted != ids.len() as u64 {
+                    // 部分通知已被删除，下一轮按剩余的通知重新合并
+                    txn.rollback().await?;
+                    continue;
+                }
+                let notification = Self::save(&txn, Self::digest_of(receiver, items)).await?;
+                txn.commit().await?;
+                sent += 1;
+
+                self.event_service
+                    .publish(ForumEvent::Notification { notification })
+                    .await;
+            }
+
+            if (receivers.len() as u64) < DIGEST_BATCH_SIZE {
+                return Ok(sent);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .and_then(|date| date.and_hms_opt(hour, min, 0))
+            .unwrap()
+    }
+
+    fn pending(ntf_type: &str, root_id: Option<i32>) -> PendingNotification {
+        PendingNotification {
+            pend_receiver: "2150001".to_string(),
+            pend_type: ntf_type.to_string(),
+            pend_title: format!("{}标题", ntf_type),
+            pend_content: format!("{}内容", ntf_type),
+            pend_post_id: root_id.map(|id| id * 10),
+            pend_root_id: root_id,
+            pend_board_id: root_id.map(|id| format!("board-{}", id)),
+            pend_actor: Some("2150002".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_daily_digest_rolls_over() {
+        let due = |now| NotificationService::digest_due(NotificationMode::DailyDigest, 8, now);
+        assert_eq!(due(at(1, 7, 30)), at(1, 8, 0));
+        assert_eq!(due(at(1, 8, 0)), at(2, 8, 0));
+        assert_eq!(due(at(1, 23, 59)), at(2, 8, 0));
+        assert_eq!(
+            NotificationService::digest_due(NotificationMode::DailyDigest, 20, at(31, 21, 0)),
+            NaiveDate::from_ymd_opt(2024, 2, 1)
+                .and_then(|date| date.and_hms_opt(20, 0, 0))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hourly_digest_due_next_hour() {
+        let due = |now| NotificationService::digest_due(NotificationMode::HourlyDigest, 8, now);
+        assert_eq!(due(at(1, 10, 0)), at(1, 11, 0));
+        assert_eq!(due(at(1, 10, 59)), at(1, 11, 0));
+        assert_eq!(due(at(1, 23, 30)), at(2, 0, 0));
+    }
+
+    #[test]
+    fn test_single_item_sent_as_is() {
+        let notification =
+            NotificationService::digest_of("2150001".to_string(), vec![pending("REPLY", Some(1))]);
+        assert_eq!(notification.ntf_type, "REPLY");
+        assert_eq!(notification.ntf_title, "REPLY标题");
+        assert_eq!(notification.ntf_content, "REPLY内容");
+        assert_eq!(notification.ntf_post_id, Some(10));
+        assert_eq!(notification.ntf_root_id, Some(1));
+        assert_eq!(notification.ntf_actor.as_deref(), Some("2150002"));
+    }
+
+    #[test]
+    fn test_digest_of_one_thread_points_to_thread() {
+        let notification = NotificationService::digest_of(
+            "2150001".to_string(),
+            vec![pending("REPLY", Some(1)), pending("MENTION", Some(1))],
+        );
+        assert_eq!(notification.ntf_type, "DIGEST");
+        assert_eq!(notification.ntf_content, "1条新回复，1条提及");
+        assert_eq!(notification.ntf_post_id, None);
+        assert_eq!(notification.ntf_root_id, Some(1));
+        assert_eq!(notification.ntf_board_id.as_deref(), Some("board-1"));
+        assert_eq!(notification.ntf_actor, None);
+    }
+
+    #[test]
+    fn test_digest_of_mixed_threads() {
+        let notification = NotificationService::digest_of(
+            "2150001".to_string(),
+            vec![
+                pending("REPLY", Some(1)),
+                pending("MENTION", Some(2)),
+                pending("REPLY", Some(2)),
+                pending("REPLY", Some(3)),
+                pending("UNKNOWN", None),
+            ],
+        );
+        assert_eq!(notification.ntf_type, "DIGEST");
+        assert_eq!(
+            notification.ntf_content,
+            "3个主题帖中有3条新回复，1条提及，1条通知"
+        );
+        assert_eq!(notification.ntf_root_id, None);
+        assert_eq!(notification.ntf_board_id, None);
+    }
 }
@@ -1,31 +1,66 @@
-use std::{net::IpAddr, sync::Arc};
+use std::{collections::HashMap, net::IpAddr, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use chrono::Local;
+use chrono::{Local, NaiveDateTime};
 use forum_utils::html_cleaner::HtmlCleaner;
+use forum_utils::html_sanitizer::HtmlSanitizer;
+use forum_utils::markdown_renderer::MarkdownRenderer;
+use forum_utils::mention_parser::MentionParser;
+use forum_utils::text_diff::TextDiffer;
+use log::warn;
+use moka::future::{Cache, CacheBuilder};
+use sea_orm::sea_query::Expr;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, NotSet, QueryFilter, QuerySelect,
+    TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::entity::notification;
 use crate::error::param_error::ParameterError::InvalidParameter;
 use crate::service::notification_service::{NotificationService, NotificationServiceTrait};
+use crate::utils::random_utils::random_token;
 use crate::utils::string_utils::StringUtilsExt;
 use crate::{
     config::{
         database::{DatabaseTrait, Db},
-        meili::Meili,
+        redis::Redis,
         AppConfig,
     },
-    dto::board::PostLocation,
+    dto::{
+        announcement::{AnnouncementReader, AnnouncementReceipt},
+        board::{Board, PostLocation},
+        forum_event::ForumEvent,
+        post_anonymity::{Redact, Viewer, ALIAS_PREFIX},
+        post_claim::{UnansweredQuestion, UnansweredQuestions},
+        post_format::ContentFormat,
+        post_list::{PostCursor, PostPage, PostSort, ThreadSummary},
+        post_read::BoardUnread,
+        post_revision::PostRevisionDiff,
+        post_search::{PostSearchFilters, PostSearchResult, PostSearchSort},
+        post_status::PostStatus,
+        post_subscription::{SubscribedPost, SubscribedPosts, SubscriptionMode},
+        post_tree::{PostTreeContext, PostTreeNode},
+        post_visibility::PostVisibility,
+    },
     entity::{
         post::{self, Column as Cols, Entity},
-        student,
+        post_revision, student,
     },
     error::{api_error::ApiError, auth_error::AuthError},
-    repository::post_repo::{PostRepository, PostRepositoryTrait},
+    repository::{
+        announcement_read_repo::{AnnouncementReadRepository, AnnouncementReadRepositoryTrait},
+        notification_pending_repo::NotificationPendingRepository,
+        notification_repo::NotificationRepository,
+        post_claim_repo::{PostClaimRepository, PostClaimRepositoryTrait},
+        post_read_repo::PostReadRepository,
+        post_repo::{PostRepository, PostRepositoryTrait},
+        post_revision_repo::{PostRevisionRepository, PostRevisionRepositoryTrait},
+        post_subscription_repo::{PostSubscriptionRepository, PostSubscriptionRepositoryTrait},
+        student_info_repo::{StudentInfoRepository, StudentInfoRepositoryTrait},
+    },
+    search::{self, SearchBackend, SearchFilter, TIMESTAMP_ATTRIBUTE},
     service::{
         board_service::BoardServiceTrait, log_service::LogServiceTrait,
         search_engine_service::SearchEngineServiceTrait,
@@ -35,12 +70,27 @@ use crate::{
 use super::{
     board_service::BoardService,
     course_service::{CourseService, CourseServiceTrait},
+    event_service::{EventService, EventServiceTrait},
     log_service::LogService,
     metadata_service::{MetadataService, MetadataServiceTrait},
+    read_state_service::{ReadStateService, ReadStateServiceTrait},
     search_engine_service::SearchEngineService,
     user_service::UserService,
 };
 
+/// 一个帖子中最多通知的提及人数
+const MAX_MENTIONS_PER_POST: usize = 10;
+
+/// 板块帖子数量的缓存键：板块、标签、是否含隐藏帖子、私密帖子的可见用户、状态、是否含回帖
+type PostsCountKey = (
+    String,
+    String,
+    bool,
+    Option<String>,
+    Option<PostStatus>,
+    bool,
+);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetPostsResult {
     pub posts: Vec<post::Model>,
@@ -76,27 +126,50 @@ pub trait PostServiceTrait {
         post_ids: &Vec<i32>,
     ) -> Result<bool, ApiError>;
 
-    /// 获取板块内的帖子
+    /// 获取板块内的帖子，有游标时从游标之后开始，否则按页码
     async fn get_posts(
         &self,
+        user_id: &str,
         board_id: &str,
         tags: &str,
         show_hidden: bool,
+        status: Option<PostStatus>,
         with_content: bool,
         with_replies: bool,
+        sort: PostSort,
+        cursor: Option<&str>,
         page_size: u64,
         page_index: u64,
-    ) -> Result<Vec<post::Model>, ApiError>;
+    ) -> Result<PostPage, ApiError>;
 
-    /// 获取板块内的帖子数量
+    /// 获取板块内的帖子数量，结果会缓存一段时间
     async fn get_posts_count(
         &self,
+        user_id: &str,
         board_id: &str,
         tags: &str,
         show_hidden: bool,
+        status: Option<PostStatus>,
         with_replies: bool,
     ) -> Result<u64, ApiError>;
 
+    /// 获取主题帖的回帖概况及用户的未读情况，与传入的主题帖一一对应（回帖会被忽略）
+    async fn get_thread_summaries(
+        &self,
+        user_id: &str,
+        posts: &[post::Model],
+    ) -> Result<Vec<ThreadSummary>, ApiError>;
+
+    /// 将板块内的所有主题帖标记为已读
+    async fn mark_board_read(&self, user_id: &str, board_id: &str) -> Result<(), ApiError>;
+
+    /// 统计课程各板块中有未读帖子的主题帖数，没有未读的板块不返回
+    async fn get_board_unread_counts(
+        &self,
+        user_id: &str,
+        board_id: &str,
+    ) -> Result<Vec<BoardUnread>, ApiError>;
+
     /// 添加帖子
     async fn add_post(
         &self,
@@ -105,6 +178,9 @@ pub trait PostServiceTrait {
         board_id: &str,
         title: &str,
         content: &str,
+        format: ContentFormat,
+        anonymous: bool,
+        visibility: PostVisibility,
     ) -> Result<i32, ApiError>;
 
     /// 添加回复
@@ -114,15 +190,28 @@ pub trait PostServiceTrait {
         ip_addr: &IpAddr,
         father_post: i32,
         content: &str,
+        format: ContentFormat,
+        anonymous: bool,
     ) -> Result<(), ApiError>;
 
-    /// 编辑帖子
+    /// 编辑帖子，标题或内容为空表示不修改，格式为空表示沿用原格式
     async fn edit_post(
         &self,
         user_id: &str,
         ip_addr: &IpAddr,
         post_id: i32,
-        new_content: &str,
+        new_title: Option<&str>,
+        new_content: Option<&str>,
+        format: Option<ContentFormat>,
+    ) -> Result<(), ApiError>;
+
+    /// 将主题帖连同所有回帖移动到另一个板块
+    async fn move_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        board_id: &str,
     ) -> Result<(), ApiError>;
 
     /// 设置帖子标签
@@ -143,7 +232,7 @@ pub trait PostServiceTrait {
         priority: i32,
     ) -> Result<(), ApiError>;
 
-    /// 删除帖子
+    /// 删除帖子，连同所有回帖一起隐藏，可以恢复
     async fn delete_post(
         &self,
         user_id: &str,
@@ -151,11 +240,171 @@ pub trait PostServiceTrait {
         post_id: i32,
     ) -> Result<(), ApiError>;
 
+    /// 恢复被删除的帖子，以及同一次删除的所有回帖
+    async fn restore_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+    ) -> Result<(), ApiError>;
+
+    /// 彻底删除超过保留期限的已删除帖子，返回删除的数量
+    async fn purge_deleted_posts(&self, user_id: &str, ip_addr: &IpAddr) -> Result<u64, ApiError>;
+
+    /// 获取帖子的修改历史（不含内容）
+    async fn list_post_revisions(
+        &self,
+        user_id: &str,
+        post_id: i32,
+    ) -> Result<Vec<post_revision::Model>, ApiError>;
+
+    /// 比较帖子的两个版本，`to_rev_id`为空时与当前版本比较
+    async fn diff_post_revisions(
+        &self,
+        post_id: i32,
+        from_rev_id: i32,
+        to_rev_id: Option<i32>,
+    ) -> Result<PostRevisionDiff, ApiError>;
+
+    /// 将帖子回滚到某个历史版本，当前版本会先保存为历史版本
+    async fn rollback_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        rev_id: i32,
+    ) -> Result<(), ApiError>;
+
+    /// 采纳回帖作为主题帖的答案，仅提问者或助教可以操作
+    async fn accept_answer(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        answer_id: i32,
+        is_staff: bool,
+    ) -> Result<(), ApiError>;
+
+    /// 取消采纳主题帖的答案，仅提问者或助教可以操作
+    async fn unaccept_answer(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        is_staff: bool,
+    ) -> Result<(), ApiError>;
+
+    /// 关闭或重新开放主题帖，关闭后不能再回帖
+    async fn set_post_closed(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        closed: bool,
+    ) -> Result<(), ApiError>;
+
+    /// 公开私密提问及其所有回帖，`anonymize`为真时将提问者在该主题帖中的帖子改为匿名
+    async fn publish_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        anonymize: bool,
+    ) -> Result<(), ApiError>;
+
+    /// 课程公告的阅读回执，列出选课学生中已读与未读的学生
+    async fn get_announcement_receipt(&self, post_id: i32)
+        -> Result<AnnouncementReceipt, ApiError>;
+
+    /// 获取用户有权查看的课程中没有助教回帖的提问，可以限定于某个板块所属的课程
+    async fn get_unanswered_questions(
+        &self,
+        user_id: &str,
+        board_id: Option<&str>,
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<UnansweredQuestions, ApiError>;
+
+    /// 认领主题帖，已被他人认领时失败
+    async fn claim_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+    ) -> Result<(), ApiError>;
+
+    /// 取消自己对主题帖的认领
+    async fn unclaim_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+    ) -> Result<(), ApiError>;
+
+    /// 关注或屏蔽帖子所在的主题帖
+    async fn subscribe_post(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        mode: SubscriptionMode,
+    ) -> Result<(), ApiError>;
+
+    /// 取消对帖子所在主题帖的订阅
+    async fn unsubscribe_post(&self, user_id: &str, post_id: i32) -> Result<(), ApiError>;
+
+    /// 获取用户订阅的主题帖
+    async fn get_user_subscriptions(
+        &self,
+        user_id: &str,
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<SubscribedPosts, ApiError>;
+
     /// 查询帖子，包括所有回帖及回帖的回帖
-    async fn get_post(&self, post_id: i32, with_hidden: bool) -> Result<GetPostsResult, ApiError>;
+    async fn get_post(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        with_hidden: bool,
+    ) -> Result<GetPostsResult, ApiError>;
+
+    /// 以树形结构查询帖子及其回帖，每个帖子的子回帖分页返回
+    ///
+    /// `after`为上一页最后一个子回帖的id，只对请求的帖子生效；超过`max_depth`的回帖不返回
+    async fn get_post_tree(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        with_hidden: bool,
+        page_size: u64,
+        after: Option<i32>,
+        max_depth: u32,
+    ) -> Result<PostTreeNode, ApiError>;
+
+    /// 查询从主题帖到某个回帖的路径，以及该回帖前后各`siblings`个相邻回帖与第一页子回帖
+    async fn get_post_context(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        with_hidden: bool,
+        siblings: u64,
+        page_size: u64,
+    ) -> Result<PostTreeContext, ApiError>;
 
     /// 查询帖子的父帖子
-    async fn get_parent_post(&self, post_id: i32) -> Result<Option<i32>, ApiError>;
+    async fn get_parent_post(&self, user_id: &str, post_id: i32) -> Result<Option<i32>, ApiError>;
+
+    /// 在用户有权查看的课程中全文搜索帖子
+    async fn search_posts(
+        &self,
+        user_id: &str,
+        query: &str,
+        board_id: Option<&str>,
+        show_hidden: bool,
+        filters: PostSearchFilters,
+        sort: PostSearchSort,
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<PostSearchResult, ApiError>;
 }
 
 #[derive(Clone)]
@@ -168,12 +417,26 @@ pub struct PostService {
     pub board_service: BoardService,
     pub search_engine_service: SearchEngineService,
     pub notification_service: NotificationService,
+    pub event_service: EventService,
     pub log_service: LogService,
+    pub read_state_service: ReadStateService,
     pub post_repository: PostRepository,
+    pub post_revision_repository: PostRevisionRepository,
+    pub post_claim_repository: PostClaimRepository,
+    pub post_subscription_repository: PostSubscriptionRepository,
+    pub student_info_repository: StudentInfoRepository,
+    pub announcement_read_repository: AnnouncementReadRepository,
+    pub html_sanitizer: Arc<HtmlSanitizer>,
+    posts_count_cache: Cache<PostsCountKey, u64>,
 }
 
 impl PostService {
-    pub fn new(db_conn: &Arc<Db>, app_config: &Arc<AppConfig>, meili_client: &Arc<Meili>) -> Self {
+    pub fn new(
+        db_conn: &Arc<Db>,
+        redis: &Arc<Redis>,
+        app_config: &Arc<AppConfig>,
+        search_backend: &Arc<dyn SearchBackend>,
+    ) -> Self {
         PostService {
             db_conn: Arc::clone(db_conn),
             app_config: Arc::clone(app_config),
@@ -181,10 +444,21 @@ impl PostService {
             user_service: UserService::new(db_conn),
             course_service: CourseService::new(db_conn, app_config),
             board_service: BoardService::new(db_conn),
-            search_engine_service: SearchEngineService::new(meili_client, db_conn, app_config),
-            notification_service: NotificationService::new(db_conn),
+            search_engine_service: SearchEngineService::new(search_backend, db_conn),
+            notification_service: NotificationService::new(db_conn, redis, app_config),
+            event_service: EventService::new(redis),
             log_service: LogService::new(db_conn),
+            read_state_service: ReadStateService::new(db_conn, redis, app_config),
             post_repository: PostRepository::new(db_conn),
+            post_revision_repository: PostRevisionRepository::new(db_conn),
+            post_claim_repository: PostClaimRepository::new(db_conn),
+            post_subscription_repository: PostSubscriptionRepository::new(db_conn),
+            student_info_repository: StudentInfoRepository::new(db_conn),
+            announcement_read_repository: AnnouncementReadRepository::new(db_conn),
+            html_sanitizer: Arc::new(HtmlSanitizer::new(app_config.sanitize.to_policy())),
+            posts_count_cache: CacheBuilder::new(1000)
+                .time_to_live(Duration::from_secs(app_config.post.count_cache_secs))
+                .build(),
         }
     }
 
@@ -204,446 +478,1986 @@ impl PostService {
 
         Ok(tag_indexes)
     }
-}
 
-#[async_trait]
-impl PostServiceTrait for PostService {
-    /// 确认用户可以查询该帖子
-    async fn ensure_query_post_permission(
-        &self,
-        user_id: &str,
-        post_id: i32,
-    ) -> Result<bool, ApiError> {
-        let stu_course_codes = self.course_service.get_user_course_codes(user_id).await?;
+    /// 是否为课程公告，即课程板块中的主题帖
+    fn is_announcement(post: &post::Model) -> bool {
+        post.post_answer_id.is_none()
+            && post.post_hw_id == Some(-1)
+            && post.post_week == Some(-1)
+            && post.post_chapter == Some(-1)
+    }
 
-        let post = Entity::find()
-            .select_only()
-            .columns([Cols::PostId, Cols::PostTerm, Cols::PostCourseCode])
-            .filter(Cols::PostId.eq(post_id))
-            .one(self.db_conn.get_db())
-            .await?;
+    /// 在板块中发帖时帖子的作业序号、周次与章节，汇总板块不能发帖
+    fn post_location(board: &Board) -> Option<(i16, i8, i8)> {
+        match board.location {
+            PostLocation::Course => Some((-1, -1, -1)),
+            PostLocation::Weekly => Some((-1, board.week, -1)),
+            PostLocation::Homework => {
+                let homework = board.homework.as_ref()?;
+                Some((homework.hw_id, board.week, homework.hw_chapter?))
+            }
+            PostLocation::WeekSummary | PostLocation::CourseSummary => None,
+        }
+    }
 
-        Ok(post.is_some()
-            && stu_course_codes.into_iter().any(|codes| {
-                post.as_ref().unwrap().post_term.as_ref().unwrap() == &codes.0
-                    && post.as_ref().unwrap().post_course_code.as_ref().unwrap() == &codes.1
-            }))
+    /// 主题帖是否出现在板块的帖子列表中，与列出帖子时的过滤条件一致
+    fn board_contains(board: &Board, post: &post::Model) -> bool {
+        let hw_id = post.post_hw_id.unwrap_or(-1);
+        let week = post.post_week.unwrap_or(-1);
+        match board.location {
+            PostLocation::Course => hw_id == -1 && week == -1 && post.post_chapter == Some(-1),
+            PostLocation::Weekly => hw_id == -1 && week == board.week,
+            PostLocation::Homework => board
+                .homework
+                .as_ref()
+                .is_some_and(|homework| homework.hw_id == hw_id),
+            PostLocation::WeekSummary => week == board.week,
+            PostLocation::CourseSummary => true,
+        }
     }
 
-    /// 确认用户可以查询该板块
-    async fn ensure_query_board_permission(
-        &self,
-        user_id: &str,
-        board_id: &str,
-    ) -> Result<bool, ApiError> {
-        let stu_course_codes = self.course_service.get_user_course_codes(user_id).await?;
+    /// 主题帖所在的所有板块id，包括汇总板块
+    fn thread_board_ids(post: &post::Model) -> Vec<String> {
+        let course = format!(
+            "{}_{}",
+            post.post_term.as_deref().unwrap_or_default(),
+            post.post_course_code.as_deref().unwrap_or_default()
+        );
+        let hw_id = post.post_hw_id.unwrap_or(-1);
+        let week = post.post_week.unwrap_or(-1);
+
+        let mut board_ids = vec![course.clone()];
+        if week == -1 {
+            if hw_id == -1 && post.post_chapter == Some(-1) {
+                board_ids.push(format!("{}_general", course));
+            }
+        } else {
+            board_ids.push(format!("{}_w{}_p", course, week));
+            board_ids.push(match hw_id {
+                -1 => format!("{}_w{}", course, week),
+                hw_id => format!("{}_w{}_{}", course, week, hw_id),
+            });
+        }
+        board_ids
+    }
 
-        let board = self.board_service.parse_id(board_id)?;
+    /// 查看帖子的用户及其是否为助教，用于隐去匿名帖子的发帖人
+    async fn viewer<'a>(&self, user_id: &'a str) -> Result<Viewer<'a>, ApiError> {
+        let is_staff = self
+            .user_service
+            .guard_user_level(user_id, self.app_config.permission.ta)
+            .await?;
+        Ok(Viewer { user_id, is_staff })
+    }
 
-        Ok(stu_course_codes.into_iter().any(|codes| {
-            board.course.as_ref().unwrap().course_term == codes.0
-                && board.course.as_ref().unwrap().course_code.as_ref().unwrap() == &codes.1
-        }))
+    /// 帖子所在的课程或板块是否允许匿名发帖
+    fn anonymous_allowed(&self, post: &post::Model) -> bool {
+        let allowed = &self.app_config.post.anonymous_boards;
+        !allowed.is_empty()
+            && Self::thread_board_ids(post)
+                .iter()
+                .any(|board_id| allowed.contains(board_id))
     }
 
-    /// 确认用户可以编辑该帖子
-    async fn ensure_edit_post_permission(
-        &self,
-        user_id: &str,
+    /// 通知中显示的发帖人，匿名帖子显示匿名称呼
+    fn sender_display(post: &post::Model) -> &str {
+        match post.post_anonymous.as_deref() {
+            Some("1") => post.post_alias.as_deref().unwrap_or_default(),
+            _ => post.post_sender_no.as_deref().unwrap_or_default(),
+        }
+    }
+
+    /// 指向帖子的通知，其余字段由调用者填写
+    fn post_notification(
+        root: &post::Model,
         post_id: i32,
-    ) -> Result<bool, ApiError> {
-        let post_level = self
-            .post_repository
-            .get_post_sender_user_level(post_id)
-            .await?
-            .ok_or(InvalidParameter("无效的帖子Id"))?;
+        actor: Option<&str>,
+    ) -> notification::Model {
+        notification::Model {
+            ntf_post_id: Some(post_id),
+            ntf_root_id: Some(root.post_id),
+            ntf_board_id: Self::thread_board_ids(root).pop(),
+            ntf_actor: actor.map(ToString::to_string),
+            ..Default::default()
+        }
+    }
 
-        let stu_level: String = student::Entity::find()
-            .select_only()
-            .column(student::Column::StuUserLevel)
-            .filter(student::Column::StuNo.eq(user_id))
-            .into_tuple()
-            .one(self.db_conn.get_db())
-            .await?
-            .ok_or(InvalidParameter("无效的用户id"))?;
+    /// 由该帖子触发的通知的触发者，匿名帖子为空
+    fn notification_actor(post: &post::Model) -> Option<&str> {
+        match post.post_anonymous.as_deref() {
+            Some("1") => None,
+            _ => post.post_sender_no.as_deref(),
+        }
+    }
 
-        Ok(stu_level.parse::<i64>().unwrap() >= post_level)
+    /// 实时事件中的帖子，不含内容
+    fn event_post(post: &post::Model) -> post::Model {
+        post::Model {
+            post_content: None,
+            post_source: None,
+            ..post.clone()
+        }
     }
 
-    async fn ensure_edit_posts_permission(
+    /// 按父帖子分组回帖，组内保持查询时的发帖时间顺序；不显示隐藏帖子时删除的帖子及其回帖都不返回
+    fn group_replies(
+        posts: Vec<post::Model>,
+        with_hidden: bool,
+    ) -> (HashMap<i32, post::Model>, HashMap<i32, Vec<post::Model>>) {
+        let mut by_id = HashMap::new();
+        let mut replies: HashMap<i32, Vec<post::Model>> = HashMap::new();
+        for post in posts {
+            if !with_hidden && post.post_is_del.as_deref() != Some("0") {
+                continue;
+            }
+            if let Some(parent_id) = post.post_answer_id {
+                replies.entry(parent_id).or_default().push(post.clone());
+            }
+            by_id.insert(post.post_id, post);
+        }
+        (by_id, replies)
+    }
+
+    /// 不含子回帖的树节点
+    fn tree_leaf(
+        post: post::Model,
+        depth: u32,
+        replies: &HashMap<i32, Vec<post::Model>>,
+    ) -> PostTreeNode {
+        PostTreeNode {
+            total_children: replies.get(&post.post_id).map_or(0, Vec::len) as u64,
+            post,
+            depth,
+            children: vec![],
+            next_cursor: None,
+        }
+    }
+
+    /// 构建帖子的子树，每层最多返回`page_size`个子回帖
+    fn build_tree(
+        post: post::Model,
+        depth: u32,
+        replies: &HashMap<i32, Vec<post::Model>>,
+        page_size: usize,
+        after: Option<i32>,
+        max_depth: u32,
+    ) -> PostTreeNode {
+        let mut node = Self::tree_leaf(post, depth, replies);
+        if depth >= max_depth {
+            return node;
+        }
+
+        // 帖子id随发帖时间递增，游标之后的回帖即id更大的回帖，游标对应的回帖被删除也不影响
+        let mut remaining = replies
+            .get(&node.post.post_id)
+            .into_iter()
+            .flatten()
+            .filter(|p| after.is_none_or(|after| p.post_id > after))
+            .peekable();
+        for _ in 0..page_size {
+            match remaining.next() {
+                Some(child) => node.children.push(Self::build_tree(
+                    child.clone(),
+                    depth + 1,
+                    replies,
+                    page_size,
+                    None,
+                    max_depth,
+                )),
+                None => break,
+            }
+        }
+        if remaining.peek().is_some() {
+            node.next_cursor = node.children.last().map(|child| child.post.post_id);
+        }
+        node
+    }
+
+    /// 打开主题帖时记录阅读位置并已读该主题帖的通知，失败不影响查看
+    async fn mark_thread_read<'a>(
         &self,
         user_id: &str,
-        post_ids: &Vec<i32>,
-    ) -> Result<bool, ApiError> {
-        let post_level = self
+        root: &post::Model,
+        posts: impl Iterator<Item = &'a post::Model>,
+    ) {
+        if root.post_answer_id.is_some() {
+            return;
+        }
+        if let Err(e) = self
+            .notification_service
+            .user_read_target_notification(user_id, None, Some(root.post_id))
+            .await
+        {
+            warn!("已读主题帖通知失败：{}", e);
+        }
+        if Self::is_announcement(root) {
+            if let Err(e) = self
+                .announcement_read_repository
+                .mark_read(root.post_id, user_id)
+                .await
+            {
+                warn!("记录公告阅读回执失败：{}", e);
+            }
+        }
+
+        let last_id = posts
+            .filter(|p| p.post_is_del.as_deref() == Some("0"))
+            .map(|p| p.post_id)
+            .max();
+        if let Some(last_id) = last_id {
+            if let Err(e) = self
+                .read_state_service
+                .mark_read(user_id, &[(root.post_id, last_id)])
+                .await
+            {
+                warn!("记录阅读位置失败：{}", e);
+            }
+        }
+    }
+
+    /// 查询帖子所在的主题帖
+    async fn get_thread_root(&self, post_id: i32) -> Result<post::Model, ApiError> {
+        let root_id = self
             .post_repository
-            .get_posts_sender_max_user_level(post_ids)
+            .get_parent_post_recursively(post_id)
             .await?
-            .ok_or(InvalidParameter("无效的帖子Id"))?;
+            .ok_or(InvalidParameter("帖子不存在"))?
+            .post_id;
+        Ok(self
+            .post_repository
+            .get_post_without_content(root_id)
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?)
+    }
 
-        let stu_level: String = student::Entity::find()
-            .select_only()
-            .column(student::Column::StuUserLevel)
-            .filter(student::Column::StuNo.eq(user_id))
-            .into_tuple()
-            .one(self.db_conn.get_db())
+    /// 根据采纳情况与回帖推导主题帖未关闭时的状态
+    async fn derive_thread_status(&self, root: &post::Model) -> Result<PostStatus, ApiError> {
+        if root.post_accepted_id.is_some() {
+            return Ok(PostStatus::Resolved);
+        }
+
+        let answered = self
+            .post_repository
+            .get_posts_recursively(root.post_id)
             .await?
-            .ok_or(InvalidParameter("无效的用户id"))?;
+            .iter()
+            .any(|p| {
+                p.post_id != root.post_id
+                    && p.post_is_del.as_deref() == Some("0")
+                    && p.post_sender_no != root.post_sender_no
+            });
+        Ok(match answered {
+            true => PostStatus::Answered,
+            false => PostStatus::Open,
+        })
+    }
 
-        Ok(stu_level.parse::<i64>().unwrap() >= post_level)
+    /// 解析内容中提及的用户学号
+    async fn resolve_mentions(&self, content: Option<&str>) -> Result<Vec<String>, ApiError> {
+        let names = match content {
+            Some(content) => MentionParser::parse(&HtmlCleaner::html_to_text(content)),
+            None => return Ok(vec![]),
+        };
+        Ok(self
+            .student_info_repository
+            .get_stu_nos_by_names(&names)
+            .await?)
     }
 
-    /// 获取板块内的帖子
-    async fn get_posts(
+    /// 通知帖子中新提及的用户，`old_content`为编辑前的内容，其中已提及的用户不再通知
+    ///
+    /// 只通知能够查看该帖子所在课程的用户
+    async fn notify_mentions(
         &self,
-        board_id: &str,
-        tags: &str,
-        show_hidden: bool,
-        with_content: bool,
-        with_replies: bool,
-        page_size: u64,
-        page_index: u64,
-    ) -> Result<Vec<post::Model>, ApiError> {
-        // 解析Tags
-        let tag_names = self.resolve_tags(tags).await?;
-        let tag_names_ref: Vec<_> = tag_names.iter().map(AsRef::as_ref).collect();
+        user_id: &str,
+        post: &post::Model,
+        old_content: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let mentioned = self.resolve_mentions(post.post_content.as_deref()).await?;
+        if mentioned.is_empty() {
+            return Ok(());
+        }
+        let already_mentioned = self.resolve_mentions(old_content).await?;
+
+        let course = (
+            post.post_term.clone().unwrap_or_default(),
+            post.post_course_code.clone().unwrap_or_default(),
+        );
+        let root = match post.post_answer_id {
+            Some(_) => self.get_thread_root(post.post_id).await?,
+            None => post.clone(),
+        };
 
-        // 计算Offset
-        let offset = page_size * (page_index - 1);
+        let receivers = mentioned
+            .into_iter()
+            .filter(|stu_no| stu_no != user_id && !already_mentioned.contains(stu_no))
+            .take(MAX_MENTIONS_PER_POST);
+        for receiver in receivers {
+            let courses = self.course_service.get_user_course_codes(&receiver).await?;
+            if !courses.contains(&course) || !self.viewer(&receiver).await?.can_see_post(post) {
+                continue;
+            }
 
-        let board = self.board_service.parse_id(board_id)?;
-        let course = board.course.as_ref().unwrap();
-        match board.location {
-            PostLocation::Weekly => self
-                .post_repository
-                .get_week_posts(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    board.week,
-                    tag_names_ref,
-                    show_hidden,
-                    with_content,
-                    with_replies,
-                    page_size,
-                    offset,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::Homework => self
-                .post_repository
-                .get_homework_posts(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    board.homework.as_ref().unwrap().hw_id,
-                    tag_names_ref,
-                    show_hidden,
-                    with_content,
-                    with_replies,
-                    page_size,
-                    offset,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::Course => self
-                .post_repository
-                .get_course_posts(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    tag_names_ref,
-                    show_hidden,
-                    with_content,
-                    with_replies,
-                    page_size,
-                    offset,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::WeekSummary => self
-                .post_repository
-                .get_week_summary_posts(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    board.week,
-                    tag_names_ref,
-                    show_hidden,
-                    with_content,
-                    with_replies,
-                    page_size,
-                    offset,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::CourseSummary => self
-                .post_repository
-                .get_course_summary_posts(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    tag_names_ref,
-                    show_hidden,
-                    with_content,
-                    with_replies,
-                    page_size,
-                    offset,
-                )
-                .await
-                .map_err(Into::into),
+            let notification = notification::Model {
+                ntf_type: "MENTION".to_string(),
+                ntf_title: "有人提到了你".to_string(),
+                ntf_content: format!(
+                    "{}在“{}”中提到了你",
+                    Self::sender_display(post),
+                    root.post_title.as_deref().unwrap_or_default()
+                ),
+                ntf_receiver: receiver,
+                ..Self::post_notification(&root, post.post_id, Self::notification_actor(post))
+            };
+
+            self.notification_service
+                .send_notification(notification)
+                .await?;
         }
+
+        Ok(())
     }
 
-    /// 获取板块内的帖子数量
-    async fn get_posts_count(
+    /// 向选修该课程的所有学生发送课程公告通知，发帖人除外
+    async fn notify_announcement(
         &self,
-        board_id: &str,
-        tags: &str,
-        show_hidden: bool,
-        with_replies: bool,
-    ) -> Result<u64, ApiError> {
-        // 解析Tags
-        let tag_names = self.resolve_tags(tags).await?;
-        let tag_names_ref: Vec<_> = tag_names.iter().map(AsRef::as_ref).collect();
-
-        let board = self.board_service.parse_id(board_id)?;
-        let course = board.course.as_ref().unwrap();
+        post: &post::Model,
+        course_name: &str,
+    ) -> Result<(), ApiError> {
+        let receivers: Vec<_> = self
+            .student_info_repository
+            .get_enrolled_students(
+                post.post_term.as_deref().unwrap_or_default(),
+                post.post_course_code.as_deref().unwrap_or_default(),
+            )
+            .await?
+            .into_iter()
+            .map(|student| student.stu_no)
+            .filter(|stu_no| post.post_sender_no.as_ref() != Some(stu_no))
+            .collect();
 
-        match board.location {
-            PostLocation::Weekly => self
-                .post_repository
-                .get_week_posts_count(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    board.week,
-                    tag_names_ref,
-                    show_hidden,
-                    with_replies,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::Homework => self
-                .post_repository
-                .get_homework_posys_count(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    board.homework.as_ref().unwrap().hw_id,
-                    tag_names_ref,
-                    show_hidden,
-                    with_replies,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::Course => self
-                .post_repository
-                .get_course_posts_count(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    tag_names_ref,
-                    show_hidden,
-                    with_replies,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::WeekSummary => self
-                .post_repository
-                .get_week_summary_posts_count(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    board.week,
-                    tag_names_ref,
-                    show_hidden,
-                    with_replies,
-                )
-                .await
-                .map_err(Into::into),
-            PostLocation::CourseSummary => self
-                .post_repository
-                .get_course_posts_count(
-                    &course.course_term,
-                    course.course_code.as_ref().unwrap(),
-                    tag_names_ref,
-                    show_hidden,
-                    with_replies,
-                )
-                .await
-                .map_err(Into::into),
-        }
+        let notification = notification::Model {
+            ntf_type: "ANNOUNCEMENT".to_string(),
+            ntf_title: "课程公告".to_string(),
+            ntf_content: format!(
+                "{}发布了公告“{}”",
+                course_name,
+                post.post_title.as_deref().unwrap_or_default()
+            ),
+            ..Self::post_notification(post, post.post_id, Self::notification_actor(post))
+        };
+        self.notification_service
+            .broadcast_notification(notification, &receivers)
+            .await
     }
 
-    /// 添加帖子
-    async fn add_post(
-        &self,
+    /// 帖子当前的标题与内容，作为修改前的历史版本
+    fn revision_of(
+        post: &post::Model,
         user_id: &str,
         ip_addr: &IpAddr,
-        board_id: &str,
-        title: &str,
-        content: &str,
-    ) -> Result<i32, ApiError> {
-        let board = self.board_service.parse_id_and_fetch(board_id).await?;
+        comment: &str,
+    ) -> post_revision::Model {
+        post_revision::Model {
+            rev_id: 0,
+            rev_post_id: post.post_id,
+            rev_title: post.post_title.clone(),
+            rev_content: post.post_content.clone(),
+            rev_format: post.post_format.clone(),
+            rev_source: post.post_source.clone(),
+            rev_op_no: user_id.to_string(),
+            rev_ipaddr: ip_addr.to_string(),
+            rev_date: Local::now().naive_local(),
+            rev_comment: comment.to_string(),
+        }
+    }
 
-        let post_term = Some(board.course.as_ref().unwrap().course_term.clone());
-        let post_course_code = board.course.as_ref().unwrap().course_code.clone();
+    /// 按格式生成帖子内容，返回清理后的HTML与需要保存的Markdown原文
+    fn render_content(&self, content: &str, format: ContentFormat) -> (String, Option<String>) {
+        match format {
+            ContentFormat::Html => (self.html_sanitizer.sanitize(content), None),
+            ContentFormat::Markdown => (
+                self.html_sanitizer
+                    .sanitize(&MarkdownRenderer::render(content)),
+                Some(content.to_string()),
+            ),
+        }
+    }
 
-        let post_hw_id: i16;
-        let post_week: i8;
-        let post_chapter: i8;
+    /// 获取帖子的某个历史版本
+    async fn get_revision(
+        &self,
+        post_id: i32,
+        rev_id: i32,
+    ) -> Result<post_revision::Model, ApiError> {
+        Ok(self
+            .post_revision_repository
+            .get(post_id, rev_id)
+            .await?
+            .ok_or(InvalidParameter("历史版本不存在"))?)
+    }
 
-        match board.location {
-            PostLocation::Course => {
-                if !self
-                    .user_service
-                    .guard_user_level(user_id, self.app_config.permission.admin)
-                    .await?
-                {
-                    return Err(AuthError::PermissionDenied("权限不足，无法发帖。").into());
+    /// 将结构化过滤条件转换为搜索过滤条件
+    async fn structured_search_filter(
+        &self,
+        filters: PostSearchFilters,
+    ) -> Result<Vec<SearchFilter>, ApiError> {
+        let mut conditions = vec![];
+
+        if let Some(tags) = &filters.tags {
+            for tag in self.resolve_tags(tags).await? {
+                if let Some(attribute) = search::tag_attribute(&tag) {
+                    conditions.push(SearchFilter::eq(attribute, "1"));
                 }
-                post_hw_id = -1;
-                post_week = -1;
-                post_chapter = -1;
-            }
-            PostLocation::Weekly => {
-                post_hw_id = -1;
-                post_week = board.week;
-                post_chapter = -1;
-            }
-            PostLocation::Homework => {
-                post_hw_id = board.homework.as_ref().unwrap().hw_id;
-                post_week = board.week;
-                post_chapter = board.homework.as_ref().unwrap().hw_chapter.unwrap();
-            }
-            _ => {
-                return Err(
-                    AuthError::PermissionDenied("错误的传入参数，为保护系统不允许发帖。").into(),
-                )
             }
         }
+        if let Some(week) = filters.week {
+            conditions.push(SearchFilter::eq("postWeek", week));
+        }
+        if let Some(hw_id) = filters.hw_id {
+            conditions.push(SearchFilter::eq("postHwId", hw_id));
+        }
+        if let Some(author) = &filters.author {
+            conditions.push(SearchFilter::eq("postSno", author.as_str()));
+        }
+        if let Some(post_type) = &filters.post_type {
+            conditions.push(SearchFilter::eq("postType", post_type.as_str()));
+        }
+        if let Some(status) = filters.status {
+            conditions.push(SearchFilter::eq("postStatus", status.as_str()));
+        }
+        if filters.date_from.is_some() || filters.date_to.is_some() {
+            conditions.push(SearchFilter::Range(
+                TIMESTAMP_ATTRIBUTE,
+                filters.date_from.and_then(search::local_timestamp),
+                filters.date_to.and_then(search::local_timestamp),
+            ));
+        }
 
-        let post_hw_id = Some(post_hw_id);
-        let post_week = Some(post_week);
-        let post_chapter = Some(post_chapter);
+        Ok(conditions)
+    }
 
-        let post_answer_id = None;
-        let post_type = Some("Question".into());
-        let post_sender_no = Some(user_id.into());
-        let post_priority = Some("0".into());
+    /// 用户能否查询帖子：帖子须在用户的课程中，私密提问及其回帖只对提问者与助教可见
+    fn can_query_post(
+        post: &post::Model,
+        course_codes: &[(String, String)],
+        viewer: &Viewer,
+    ) -> bool {
+        let in_course = course_codes.iter().any(|(term, code)| {
+            post.post_term.as_ref() == Some(term) && post.post_course_code.as_ref() == Some(code)
+        });
+        in_course && viewer.can_see_post(post)
+    }
 
-        let post_title = Some(title.into());
-        let post_content = Some(content.into());
-        let post_date = Some(Local::now().naive_local());
+    /// 权限相关的搜索过滤条件，必须限制搜索范围，否则会放开所有帖子
+    fn permission_search_filter(filter: SearchFilter) -> SearchFilter {
+        debug_assert!(!filter.is_unconstrained(), "权限过滤条件没有限制搜索范围");
+        filter
+    }
 
-        let post = post::Model {
-            post_term,
-            post_course_code,
-            post_hw_id,
-            post_week,
-            post_chapter,
-            post_answer_id,
-            post_type,
-            post_sender_no,
-            post_priority,
-            post_title,
-            post_content,
-            post_date,
-            ..Default::default()
+    /// 限定于某门课程的搜索过滤条件
+    fn course_search_filter(term: &str, course_code: &str) -> SearchFilter {
+        Self::permission_search_filter(SearchFilter::All(vec![
+            SearchFilter::eq("postTerm", term),
+            SearchFilter::eq("postCcode", course_code),
+        ]))
+    }
+
+    /// 私密帖子的搜索过滤条件，学生只能搜到公开帖子与自己的私密提问，助教不过滤
+    fn visibility_search_filter(viewer: &Viewer) -> Option<SearchFilter> {
+        viewer.visible_to().map(|user_id| {
+            Self::permission_search_filter(SearchFilter::Any(vec![
+                SearchFilter::eq("postVisibility", PostVisibility::Public.as_str()),
+                SearchFilter::eq("postPrivateSno", user_id),
+            ]))
+        })
+    }
+
+    /// 限定于某个板块的搜索过滤条件，与`get_posts`中各板块的查询条件一致
+    fn board_search_filter(board: &Board) -> SearchFilter {
+        let course = board.course.as_ref().unwrap();
+        let mut filters = vec![Self::course_search_filter(
+            &course.course_term,
+            course.course_code.as_ref().unwrap(),
+        )];
+
+        match board.location {
+            PostLocation::Weekly => filters.extend([
+                SearchFilter::eq("postHwId", -1i16),
+                SearchFilter::eq("postWeek", board.week),
+            ]),
+            PostLocation::Homework => filters.push(SearchFilter::eq(
+                "postHwId",
+                board.homework.as_ref().unwrap().hw_id,
+            )),
+            PostLocation::Course => filters.extend([
+                SearchFilter::eq("postHwId", -1i16),
+                SearchFilter::eq("postChapter", -1i8),
+                SearchFilter::eq("postWeek", -1i8),
+            ]),
+            PostLocation::WeekSummary => filters.push(SearchFilter::eq("postWeek", board.week)),
+            PostLocation::CourseSummary => {}
+        }
+        SearchFilter::All(filters)
+    }
+}
+
+#[async_trait]
+impl PostServiceTrait for PostService {
+    /// 确认用户可以查询该帖子
+    async fn ensure_query_post_permission(
+        &self,
+        user_id: &str,
+        post_id: i32,
+    ) -> Result<bool, ApiError> {
+        let stu_course_codes = self.course_service.get_user_course_codes(user_id).await?;
+
+        let post = Entity::find()
+            .select_only()
+            .columns([
+                Cols::PostId,
+                Cols::PostTerm,
+                Cols::PostCourseCode,
+                Cols::PostVisibility,
+                Cols::PostPrivateSno,
+            ])
+            .filter(Cols::PostId.eq(post_id))
+            .one(self.db_conn.get_db())
+            .await?;
+
+        let Some(post) = post else {
+            return Ok(false);
         };
 
-        let post = post::ActiveModel {
-            post_id: NotSet,
-            ..post.into_active_model()
+        // 公开帖子与用户身份无关，只有私密提问需要查询用户是否为助教
+        let viewer = match PostVisibility::from_column(post.post_visibility.as_deref()) {
+            PostVisibility::Public => Viewer {
+                user_id,
+                is_staff: false,
+            },
+            PostVisibility::Private => self.viewer(user_id).await?,
         };
-        let post = post.insert(self.db_conn.get_db()).await?;
+        Ok(Self::can_query_post(&post, &stu_course_codes, &viewer))
+    }
 
-        // 添加到搜索引擎
-        self.search_engine_service.add_post(post.post_id).await?;
+    /// 确认用户可以查询该板块
+    async fn ensure_query_board_permission(
+        &self,
+        user_id: &str,
+        board_id: &str,
+    ) -> Result<bool, ApiError> {
+        let stu_course_codes = self.course_service.get_user_course_codes(user_id).await?;
 
-        // 记录日志
-        let comment = "POST 发表帖子";
-        self.log_service
-            .log_post(post.post_id, user_id, ip_addr, comment)
-            .await;
+        let board = self.board_service.parse_id(board_id)?;
 
-        Ok(post.post_id)
+        Ok(stu_course_codes.into_iter().any(|codes| {
+            board.course.as_ref().unwrap().course_term == codes.0
+                && board.course.as_ref().unwrap().course_code.as_ref().unwrap() == &codes.1
+        }))
     }
 
-    /// 添加回复
-    async fn add_reply(
+    /// 确认用户可以编辑该帖子
+    async fn ensure_edit_post_permission(
         &self,
         user_id: &str,
-        ip_addr: &IpAddr,
-        father_post_id: i32,
-        content: &str,
-    ) -> Result<(), ApiError> {
-        let father_post = self
+        post_id: i32,
+    ) -> Result<bool, ApiError> {
+        let post_level = self
             .post_repository
-            .get_post_without_content(father_post_id)
+            .get_post_sender_user_level(post_id)
             .await?
-            .ok_or(InvalidParameter("不存在的回帖对象"))?;
+            .ok_or(InvalidParameter("无效的帖子Id"))?;
 
-        let post_term = father_post.post_term;
-        let post_course_code = father_post.post_course_code;
-        let post_hw_id = father_post.post_hw_id;
-        let post_week = father_post.post_week;
-        let post_chapter = father_post.post_chapter;
+        let stu_level: String = student::Entity::find()
+            .select_only()
+            .column(student::Column::StuUserLevel)
+            .filter(student::Column::StuNo.eq(user_id))
+            .into_tuple()
+            .one(self.db_conn.get_db())
+            .await?
+            .ok_or(InvalidParameter("无效的用户id"))?;
 
-        let post_title = None;
-        let post_sender_no = Some(user_id.into());
-        let post_answer_id = Some(father_post_id);
-        let post_content = Some(content.into());
-        let post_date = Some(Local::now().naive_local());
+        Ok(stu_level.parse::<i64>().unwrap() >= post_level)
+    }
 
-        let post_type: String;
-        if user_id == father_post.post_sender_no.as_ref().unwrap() {
-            post_type = "Answer".into();
-        } else {
-            post_type = "QuestionsAdditional".into();
-        }
-        let post_type = Some(post_type);
+    async fn ensure_edit_posts_permission(
+        &self,
+        user_id: &str,
+        post_ids: &Vec<i32>,
+    ) -> Result<bool, ApiError> {
+        let post_level = self
+            .post_repository
+            .get_posts_sender_max_user_level(post_ids)
+            .await?
+            .ok_or(InvalidParameter("无效的帖子Id"))?;
 
-        let new_post = post::Model {
-            post_term,
-            post_course_code,
-            post_hw_id,
-            post_week,
-            post_chapter,
-            post_answer_id,
-            post_type,
-            post_sender_no,
-            post_title,
-            post_content,
-            post_date,
-            ..Default::default()
-        };
-        let new_post = post::ActiveModel {
-            post_id: NotSet,
-            ..new_post.into_active_model()
-        };
+        let stu_level: String = student::Entity::find()
+            .select_only()
+            .column(student::Column::StuUserLevel)
+            .filter(student::Column::StuNo.eq(user_id))
+            .into_tuple()
+            .one(self.db_conn.get_db())
+            .await?
+            .ok_or(InvalidParameter("无效的用户id"))?;
+
+        Ok(stu_level.parse::<i64>().unwrap() >= post_level)
+    }
+
+    /// 获取板块内的帖子，有游标时从游标之后开始，否则按页码
+    async fn get_posts(
+        &self,
+        user_id: &str,
+        board_id: &str,
+        tags: &str,
+        show_hidden: bool,
+        status: Option<PostStatus>,
+        with_content: bool,
+        with_replies: bool,
+        sort: PostSort,
+        cursor: Option<&str>,
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<PostPage, ApiError> {
+        let after = cursor
+            .map(|cursor| {
+                PostCursor::decode(cursor)
+                    .filter(|cursor| cursor.sort == sort)
+                    .ok_or(InvalidParameter("无效的分页游标"))
+            })
+            .transpose()?;
+
+        // 解析Tags
+        let tag_names = self.resolve_tags(tags).await?;
+        let tag_names_ref: Vec<_> = tag_names.iter().map(AsRef::as_ref).collect();
+        let status = status.as_ref().map(PostStatus::as_str);
+
+        // 计算Offset
+        let offset = page_size * page_index.saturating_sub(1);
+        let viewer = self.viewer(user_id).await?;
+
+        let board = self.board_service.parse_id(board_id)?;
+        let course = board.course.as_ref().unwrap();
+        let posts = match board.location {
+            PostLocation::Weekly => {
+                self.post_repository
+                    .get_week_posts(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        board.week,
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_content,
+                        with_replies,
+                        sort,
+                        after.as_ref(),
+                        page_size,
+                        offset,
+                    )
+                    .await?
+            }
+            PostLocation::Homework => {
+                self.post_repository
+                    .get_homework_posts(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        board.homework.as_ref().unwrap().hw_id,
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_content,
+                        with_replies,
+                        sort,
+                        after.as_ref(),
+                        page_size,
+                        offset,
+                    )
+                    .await?
+            }
+            PostLocation::Course => {
+                self.post_repository
+                    .get_course_posts(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_content,
+                        with_replies,
+                        sort,
+                        after.as_ref(),
+                        page_size,
+                        offset,
+                    )
+                    .await?
+            }
+            PostLocation::WeekSummary => {
+                self.post_repository
+                    .get_week_summary_posts(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        board.week,
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_content,
+                        with_replies,
+                        sort,
+                        after.as_ref(),
+                        page_size,
+                        offset,
+                    )
+                    .await?
+            }
+            PostLocation::CourseSummary => {
+                self.post_repository
+                    .get_course_summary_posts(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_content,
+                        with_replies,
+                        sort,
+                        after.as_ref(),
+                        page_size,
+                        offset,
+                    )
+                    .await?
+            }
+        };
+
+        // 取满一页时才可能有下一页
+        let next_cursor = match posts.len() as u64 == page_size {
+            true => posts.last().map(|post| PostCursor::of(sort, post).encode()),
+            false => None,
+        };
+
+        let mut page = PostPage { posts, next_cursor };
+        page.redact(&viewer);
+        Ok(page)
+    }
+
+    /// 获取板块内的帖子数量，结果会缓存一段时间
+    async fn get_posts_count(
+        &self,
+        user_id: &str,
+        board_id: &str,
+        tags: &str,
+        show_hidden: bool,
+        status: Option<PostStatus>,
+        with_replies: bool,
+    ) -> Result<u64, ApiError> {
+        // 学生能看到的私密帖子各不相同，按用户分别缓存
+        let viewer = self.viewer(user_id).await?;
+        let cache_key = (
+            board_id.to_string(),
+            tags.to_string(),
+            show_hidden,
+            viewer.visible_to().map(ToString::to_string),
+            status,
+            with_replies,
+        );
+        if let Some(count) = self.posts_count_cache.get(&cache_key).await {
+            return Ok(count);
+        }
+
+        // 解析Tags
+        let tag_names = self.resolve_tags(tags).await?;
+        let tag_names_ref: Vec<_> = tag_names.iter().map(AsRef::as_ref).collect();
+        let status = status.as_ref().map(PostStatus::as_str);
+
+        let board = self.board_service.parse_id(board_id)?;
+        let course = board.course.as_ref().unwrap();
+
+        let count = match board.location {
+            PostLocation::Weekly => {
+                self.post_repository
+                    .get_week_posts_count(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        board.week,
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_replies,
+                    )
+                    .await?
+            }
+            PostLocation::Homework => {
+                self.post_repository
+                    .get_homework_posys_count(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        board.homework.as_ref().unwrap().hw_id,
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_replies,
+                    )
+                    .await?
+            }
+            PostLocation::Course => {
+                self.post_repository
+                    .get_course_posts_count(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_replies,
+                    )
+                    .await?
+            }
+            PostLocation::WeekSummary => {
+                self.post_repository
+                    .get_week_summary_posts_count(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        board.week,
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_replies,
+                    )
+                    .await?
+            }
+            PostLocation::CourseSummary => {
+                self.post_repository
+                    .get_course_summary_posts_count(
+                        &course.course_term,
+                        course.course_code.as_ref().unwrap(),
+                        tag_names_ref,
+                        show_hidden,
+                        viewer.visible_to(),
+                        status,
+                        with_replies,
+                    )
+                    .await?
+            }
+        };
+
+        self.posts_count_cache.insert(cache_key, count).await;
+        Ok(count)
+    }
+
+    /// 获取主题帖的回帖概况及用户的未读情况，与传入的主题帖一一对应（回帖会被忽略）
+    async fn get_thread_summaries(
+        &self,
+        user_id: &str,
+        posts: &[post::Model],
+    ) -> Result<Vec<ThreadSummary>, ApiError> {
+        let roots: Vec<_> = posts
+            .iter()
+            .filter(|post| post.post_answer_id.is_none())
+            .collect();
+        let root_ids: Vec<_> = roots.iter().map(|post| post.post_id).collect();
+        let mut summaries: HashMap<_, _> = self
+            .post_repository
+            .get_thread_summaries(&root_ids, self.app_config.permission.ta)
+            .await?
+            .into_iter()
+            .map(|summary| (summary.post_id, summary))
+            .collect();
+
+        // 只有已读位置之后还有新帖子的主题帖需要统计未读回帖数
+        let read_positions = self
+            .read_state_service
+            .get_read_positions(user_id, &root_ids)
+            .await?;
+        let partially_read: Vec<_> = roots
+            .iter()
+            .filter_map(|post| {
+                let last_read = *read_positions.get(&post.post_id)?;
+                (post.post_last_id.unwrap_or(post.post_id) > last_read)
+                    .then_some((post.post_id, last_read))
+            })
+            .collect();
+        let unread_counts: HashMap<_, _> = self
+            .post_repository
+            .count_replies_after(&partially_read)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut summaries: Vec<_> = roots
+            .into_iter()
+            .map(|post| {
+                let mut summary = summaries.remove(&post.post_id).unwrap_or(ThreadSummary {
+                    post_id: post.post_id,
+                    ..Default::default()
+                });
+                match read_positions.get(&post.post_id) {
+                    Some(&last_read) => {
+                        summary.unread = post.post_last_id.unwrap_or(post.post_id) > last_read;
+                        summary.unread_count = unread_counts
+                            .get(&post.post_id)
+                            .copied()
+                            .unwrap_or_default();
+                    }
+                    None => {
+                        summary.unread_count = summary.reply_count;
+                        summary.unread = true;
+                    }
+                }
+                summary
+            })
+            .collect();
+        summaries.redact(&self.viewer(user_id).await?);
+        Ok(summaries)
+    }
+
+    /// 将板块内的所有主题帖标记为已读
+    async fn mark_board_read(&self, user_id: &str, board_id: &str) -> Result<(), ApiError> {
+        let board = self.board_service.parse_id(board_id)?;
+        let course = board.course.as_ref().unwrap();
+        let viewer = self.viewer(user_id).await?;
+        let positions: Vec<_> = self
+            .post_repository
+            .get_course_threads(&course.course_term, course.course_code.as_ref().unwrap())
+            .await?
+            .into_iter()
+            .filter(|post| Self::board_contains(&board, post) && viewer.can_see_post(post))
+            .map(|post| (post.post_id, post.post_last_id.unwrap_or(post.post_id)))
+            .collect();
+
+        Ok(self
+            .read_state_service
+            .mark_read(user_id, &positions)
+            .await?)
+    }
+
+    /// 统计课程各板块中有未读帖子的主题帖数，没有未读的板块不返回
+    async fn get_board_unread_counts(
+        &self,
+        user_id: &str,
+        board_id: &str,
+    ) -> Result<Vec<BoardUnread>, ApiError> {
+        let board = self.board_service.parse_id(board_id)?;
+        let course = board.course.as_ref().unwrap();
+        let viewer = self.viewer(user_id).await?;
+        let threads: Vec<_> = self
+            .post_repository
+            .get_course_threads(&course.course_term, course.course_code.as_ref().unwrap())
+            .await?
+            .into_iter()
+            .filter(|post| viewer.can_see_post(post))
+            .collect();
+        let root_ids: Vec<_> = threads.iter().map(|post| post.post_id).collect();
+        let read_positions = self
+            .read_state_service
+            .get_read_positions(user_id, &root_ids)
+            .await?;
+
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for post in &threads {
+            let unread = read_positions
+                .get(&post.post_id)
+                .is_none_or(|&last_read| post.post_last_id.unwrap_or(post.post_id) > last_read);
+            if unread {
+                for board_id in Self::thread_board_ids(post) {
+                    *counts.entry(board_id).or_default() += 1;
+                }
+            }
+        }
+
+        let mut counts: Vec<_> = counts
+            .into_iter()
+            .map(|(board_id, unread_threads)| BoardUnread {
+                board_id,
+                unread_threads,
+            })
+            .collect();
+        counts.sort_by(|a, b| a.board_id.cmp(&b.board_id));
+        Ok(counts)
+    }
+
+    /// 添加帖子
+    async fn add_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        board_id: &str,
+        title: &str,
+        content: &str,
+        format: ContentFormat,
+        anonymous: bool,
+        visibility: PostVisibility,
+    ) -> Result<i32, ApiError> {
+        let board = self.board_service.parse_id_and_fetch(board_id).await?;
+
+        let post_term = Some(board.course.as_ref().unwrap().course_term.clone());
+        let post_course_code = board.course.as_ref().unwrap().course_code.clone();
+
+        if board.location == PostLocation::Course
+            && !self
+                .user_service
+                .guard_user_level(user_id, self.app_config.permission.admin)
+                .await?
+        {
+            return Err(AuthError::PermissionDenied("权限不足，无法发帖。").into());
+        }
+        let (post_hw_id, post_week, post_chapter) = Self::post_location(&board).ok_or(
+            AuthError::PermissionDenied("错误的传入参数，为保护系统不允许发帖。"),
+        )?;
+
+        let post_hw_id = Some(post_hw_id);
+        let post_week = Some(post_week);
+        let post_chapter = Some(post_chapter);
+
+        let post_answer_id = None;
+        let post_type = Some("Question".into());
+        let post_sender_no = Some(user_id.into());
+        let post_priority = Some("0".into());
+
+        let post_title = Some(title.into());
+        let (post_content, post_source) = self.render_content(content, format);
+        let post_content = Some(post_content);
+        let post_format = Some(format.as_str().into());
+        let post_date = Some(Local::now().naive_local());
+
+        let post = post::Model {
+            post_term,
+            post_course_code,
+            post_hw_id,
+            post_week,
+            post_chapter,
+            post_answer_id,
+            post_type,
+            post_sender_no,
+            post_priority,
+            post_title,
+            post_content,
+            post_format,
+            post_source,
+            post_date,
+            post_active_date: post_date,
+            post_status: Some(PostStatus::Open.as_str().into()),
+            post_visibility: Some(visibility.as_str().into()),
+            post_private_sno: (visibility == PostVisibility::Private).then(|| user_id.into()),
+            post_anonymous: Some(if anonymous { "1" } else { "0" }.into()),
+            // 新的主题帖中还没有其他匿名发帖人
+            post_alias: anonymous.then(|| format!("{}1", ALIAS_PREFIX)),
+            ..Default::default()
+        };
+        if anonymous && !self.anonymous_allowed(&post) {
+            return Err(InvalidParameter("本板块不允许匿名发帖").into());
+        }
+
+        let post = post::ActiveModel {
+            post_id: NotSet,
+            ..post.into_active_model()
+        };
+        let txn = self.db_conn.get_db().begin().await?;
+        let post = post.insert(&txn).await?;
+        PostRepository::refresh_thread_stats(&txn, &[post.post_id]).await?;
+        PostSubscriptionRepository::subscribe_if_absent(
+            &txn,
+            post.post_id,
+            user_id,
+            SubscriptionMode::Watch.as_str(),
+        )
+        .await?;
+
+        // 添加到搜索引擎
+        self.search_engine_service
+            .enqueue_post(&txn, post.post_id)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        let comment = "POST 发表帖子";
+        self.log_service
+            .log_post(post.post_id, user_id, ip_addr, comment)
+            .await;
+
+        if let Err(e) = self
+            .read_state_service
+            .mark_read(user_id, &[(post.post_id, post.post_id)])
+            .await
+        {
+            warn!("记录阅读位置失败：{}", e);
+        }
+
+        self.event_service
+            .publish(ForumEvent::NewPost {
+                board_ids: Self::thread_board_ids(&post),
+                post: Self::event_post(&post),
+            })
+            .await;
+
+        self.notify_mentions(user_id, &post, None).await?;
+
+        // 课程板块中公开的主题帖作为课程公告通知所有选课学生，人数较多，在后台发送
+        if board.location == PostLocation::Course && visibility == PostVisibility::Public {
+            let service = self.clone();
+            let post = post.clone();
+            let course = board.course.as_ref().unwrap();
+            let course_name = course
+                .course_short_name
+                .clone()
+                .or_else(|| course.course_full_name.clone())
+                .unwrap_or_default();
+            tokio::spawn(async move {
+                if let Err(e) = service.notify_announcement(&post, &course_name).await {
+                    warn!("发送课程公告{}的通知失败：{}", post.post_id, e);
+                }
+            });
+        }
+
+        Ok(post.post_id)
+    }
+
+    /// 添加回复
+    async fn add_reply(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        father_post_id: i32,
+        content: &str,
+        format: ContentFormat,
+        anonymous: bool,
+    ) -> Result<(), ApiError> {
+        let father_post = self
+            .post_repository
+            .get_post_without_content(father_post_id)
+            .await?
+            .ok_or(InvalidParameter("不存在的回帖对象"))?;
+
+        let root = self.get_thread_root(father_post_id).await?;
+        if father_post.post_is_del.as_deref() != Some("0")
+            || root.post_is_del.as_deref() != Some("0")
+        {
+            return Err(InvalidParameter("该帖子已删除，不能回复").into());
+        }
+        if root.post_status.as_deref() == Some(PostStatus::Closed.as_str()) {
+            return Err(InvalidParameter("该帖子已关闭，不能回复").into());
+        }
+        if anonymous && !self.anonymous_allowed(&root) {
+            return Err(InvalidParameter("本板块不允许匿名发帖").into());
+        }
+        if !self.viewer(user_id).await?.can_see_post(&root) {
+            return Err(AuthError::PermissionDenied("您无权回复此帖子").into());
+        }
+
+        let post_term = father_post.post_term;
+        let post_course_code = father_post.post_course_code;
+        let post_hw_id = father_post.post_hw_id;
+        let post_week = father_post.post_week;
+        let post_chapter = father_post.post_chapter;
+
+        let post_title = None;
+        let post_sender_no = Some(user_id.into());
+        let post_answer_id = Some(father_post_id);
+        let (post_content, post_source) = self.render_content(content, format);
+        let post_content = Some(post_content);
+        let post_format = Some(format.as_str().into());
+        let post_date = Some(Local::now().naive_local());
+
+        let post_type: String;
+        if user_id == father_post.post_sender_no.as_ref().unwrap() {
+            post_type = "Answer".into();
+        } else {
+            post_type = "QuestionsAdditional".into();
+        }
+        let post_type = Some(post_type);
+
+        let new_post = post::Model {
+            post_term,
+            post_course_code,
+            post_hw_id,
+            post_week,
+            post_chapter,
+            post_answer_id,
+            post_type,
+            post_sender_no,
+            post_title,
+            post_content,
+            post_format,
+            post_source,
+            post_date,
+            post_active_date: post_date,
+            post_anonymous: Some(if anonymous { "1" } else { "0" }.into()),
+            post_visibility: root.post_visibility.clone(),
+            post_private_sno: root.post_private_sno.clone(),
+            ..Default::default()
+        };
+
+        let txn = self.db_conn.get_db().begin().await?;
+        let post_alias = match anonymous {
+            true => Some(PostRepository::thread_alias(&txn, root.post_id, user_id).await?),
+            false => None,
+        };
+        let new_post = post::ActiveModel {
+            post_id: NotSet,
+            ..post::Model {
+                post_alias,
+                ..new_post
+            }
+            .into_active_model()
+        };
+        let new_post = new_post.insert(&txn).await?;
+        PostSubscriptionRepository::subscribe_if_absent(
+            &txn,
+            root.post_id,
+            user_id,
+            SubscriptionMode::Watch.as_str(),
+        )
+        .await?;
+        PostRepository::refresh_thread_stats(&txn, &[root.post_id]).await?;
+        self.search_engine_service
+            .enqueue_post(&txn, new_post.post_id)
+            .await?;
+
+        // 他人回帖后，待回答的主题帖变为已回答
+        if root.post_status.as_deref() == Some(PostStatus::Open.as_str())
+            && root.post_sender_no.as_deref() != Some(user_id)
+        {
+            post::ActiveModel {
+                post_id: Set(root.post_id),
+                post_status: Set(Some(PostStatus::Answered.as_str().into())),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await?;
+            self.search_engine_service
+                .enqueue_post(&txn, root.post_id)
+                .await?;
+        }
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        let comment = format!("REPLY 回复{}", father_post_id);
+        self.log_service
+            .log_post(new_post.post_id, user_id, ip_addr, &comment)
+            .await;
+
+        // 自己的回帖不算未读
+        if let Err(e) = self
+            .read_state_service
+            .mark_read(user_id, &[(root.post_id, new_post.post_id)])
+            .await
+        {
+            warn!("记录阅读位置失败：{}", e);
+        }
+
+        self.event_service
+            .publish(ForumEvent::NewReply {
+                board_ids: Self::thread_board_ids(&root),
+                root_id: root.post_id,
+                post: Self::event_post(&new_post),
+            })
+            .await;
+
+        // 发送通知：被回复者（未屏蔽时）收到回复通知，其余关注者收到主题帖的新回帖通知
+        let ntf_content = format!(
+            "{}",
+            HtmlCleaner::html_to_text(new_post.post_content.as_ref().unwrap()).abbreviate(35)
+        );
+        let subscriptions = self
+            .post_subscription_repository
+            .get_by_post(root.post_id)
+            .await?;
+        let mode_of = |receiver: &str| {
+            subscriptions
+                .iter()
+                .find(|s| s.sub_sno == receiver)
+                .map(|s| s.sub_mode.as_str())
+        };
+
+        let actor = Self::notification_actor(&new_post);
+        let father_sender = father_post.post_sender_no.unwrap_or_default();
+        if father_sender != user_id
+            && mode_of(&father_sender) != Some(SubscriptionMode::Mute.as_str())
+        {
+            let notification = notification::Model {
+                ntf_type: "REPLY".to_string(),
+                ntf_title: "收到新回复".to_string(),
+                ntf_content: ntf_content.clone(),
+                ntf_receiver: father_sender.clone(),
+                ..Self::post_notification(&root, new_post.post_id, actor)
+            };
+
+            self.notification_service
+                .send_notification(notification)
+                .await?;
+        }
+
+        let watchers = subscriptions.iter().filter(|s| {
+            s.sub_mode == SubscriptionMode::Watch.as_str()
+                && s.sub_sno != user_id
+                && s.sub_sno != father_sender
+        });
+        for watcher in watchers {
+            let notification = notification::Model {
+                ntf_type: "WATCH".to_string(),
+                ntf_title: "关注的帖子有新回复".to_string(),
+                ntf_content: format!(
+                    "{}: {}",
+                    root.post_title.as_deref().unwrap_or_default(),
+                    ntf_content
+                ),
+                ntf_receiver: watcher.sub_sno.clone(),
+                ..Self::post_notification(&root, new_post.post_id, actor)
+            };
+
+            self.notification_service
+                .send_notification(notification)
+                .await?;
+        }
+
+        self.notify_mentions(user_id, &new_post, None).await?;
+
+        Ok(())
+    }
+
+    /// 编辑帖子
+    async fn edit_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        new_title: Option<&str>,
+        new_content: Option<&str>,
+        format: Option<ContentFormat>,
+    ) -> Result<(), ApiError> {
+        if format.is_some() && new_content.is_none() {
+            return Err(InvalidParameter("修改格式时需要同时提交内容").into());
+        }
+        if new_title.is_none() && new_content.is_none() {
+            return Err(InvalidParameter("没有需要修改的内容").into());
+        }
+
+        let old_post = Entity::find_by_id(post_id)
+            .one(self.db_conn.get_db())
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if old_post.post_is_del.as_deref() != Some("0") {
+            return Err(InvalidParameter("该帖子已删除，不能编辑").into());
+        }
+        if new_title.is_some() && old_post.post_answer_id.is_some() {
+            return Err(InvalidParameter("回帖没有标题").into());
+        }
+        let revision = Self::revision_of(&old_post, user_id, ip_addr, "EDIT");
+
+        let format =
+            format.unwrap_or_else(|| ContentFormat::from_column(old_post.post_format.as_deref()));
+        let new_content = new_content.map(|content| self.render_content(content, format));
+
+        let mut post = old_post.clone().into_active_model();
+        if let Some(new_title) = new_title {
+            post.post_title = Set(Some(new_title.to_string()));
+        }
+        if let Some((new_content, new_source)) = &new_content {
+            post.post_content = Set(Some(new_content.clone()));
+            post.post_format = Set(Some(format.as_str().into()));
+            post.post_source = Set(new_source.clone());
+        }
+
+        let txn = self.db_conn.get_db().begin().await?;
+        PostRevisionRepository::add(&txn, revision).await?;
+        post.save(&txn).await?;
+        self.search_engine_service
+            .enqueue_post(&txn, post_id)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        let comment = match new_title {
+            Some(_) => "EDIT 进行了编辑，修改了标题",
+            None => "EDIT 进行了编辑",
+        };
+        self.log_service
+            .log_post(post_id, user_id, ip_addr, comment)
+            .await;
+
+        // 只通知编辑后新提及的用户
+        if let Some((new_content, _)) = new_content {
+            let new_post = post::Model {
+                post_content: Some(new_content),
+                ..old_post.clone()
+            };
+            self.notify_mentions(user_id, &new_post, old_post.post_content.as_deref())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 将主题帖连同所有回帖移动到另一个板块
+    async fn move_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        board_id: &str,
+    ) -> Result<(), ApiError> {
+        let post = self
+            .post_repository
+            .get_post_without_content(post_id)
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if post.post_answer_id.is_some() {
+            return Err(InvalidParameter("只能移动主题帖").into());
+        }
+
+        let board = self.board_service.parse_id_and_fetch(board_id).await?;
+        // 课程板块的主题帖按公告展示，不能通过移动发布公告
+        if board.location == PostLocation::Course {
+            return Err(InvalidParameter("不能移动到课程公告板块").into());
+        }
+        let course = board
+            .course
+            .as_ref()
+            .ok_or(InvalidParameter("目标板块的课程不存在"))?;
+        let (post_hw_id, post_week, post_chapter) =
+            Self::post_location(&board).ok_or(InvalidParameter("不能移动到该板块"))?;
+
+        let posts = self.post_repository.get_posts_recursively(post_id).await?;
+
+        // 按移动后的位置重新检查匿名规则
+        let moved = post::Model {
+            post_term: Some(course.course_term.clone()),
+            post_course_code: course.course_code.clone(),
+            post_hw_id: Some(post_hw_id),
+            post_week: Some(post_week),
+            post_chapter: Some(post_chapter),
+            ..post.clone()
+        };
+        if posts
+            .iter()
+            .any(|p| p.post_anonymous.as_deref() == Some("1"))
+            && !self.anonymous_allowed(&moved)
+        {
+            return Err(InvalidParameter("目标板块不允许匿名发帖").into());
+        }
+
+        // 私密提问移动后提问者仍需能看到
+        if PostVisibility::from_column(post.post_visibility.as_deref()) == PostVisibility::Private {
+            let asker = post.post_private_sno.as_deref().unwrap_or_default();
+            let in_course = self
+                .course_service
+                .get_user_course_codes(asker)
+                .await?
+                .into_iter()
+                .any(|(term, code)| {
+                    moved.post_term.as_ref() == Some(&term)
+                        && moved.post_course_code.as_ref() == Some(&code)
+                });
+            if !in_course {
+                return Err(InvalidParameter("私密提问的提问者不在目标课程中").into());
+            }
+        }
+
+        let post_ids: Vec<_> = posts.into_iter().map(|p| p.post_id).collect();
+
+        let txn = self.db_conn.get_db().begin().await?;
+        Entity::update_many()
+            .col_expr(Cols::PostTerm, Expr::value(course.course_term.clone()))
+            .col_expr(
+                Cols::PostCourseCode,
+                Expr::value(course.course_code.clone()),
+            )
+            .col_expr(Cols::PostHwId, Expr::value(post_hw_id))
+            .col_expr(Cols::PostWeek, Expr::value(post_week))
+            .col_expr(Cols::PostChapter, Expr::value(post_chapter))
+            .filter(Cols::PostId.is_in(post_ids.clone()))
+            .exec(&txn)
+            .await?;
+        self.search_engine_service
+            .enqueue_posts(&txn, &post_ids)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        for &id in &post_ids {
+            let comment = if id == post_id {
+                format!("MOVE 移动到{}", board_id)
+            } else {
+                format!("MOVE 随帖子{}移动到{}", post_id, board_id)
+            };
+            self.log_service
+                .log_post(id, user_id, ip_addr, &comment)
+                .await;
+        }
+
+        // 发送通知
+        let author = post.post_sender_no.clone().unwrap_or_default();
+        if author != user_id {
+            let course_name = course.course_full_name.clone().unwrap_or_default();
+            let notification = notification::Model {
+                ntf_type: "MOVE".to_string(),
+                ntf_title: "帖子被移动".to_string(),
+                ntf_content: format!(
+                    "您的帖子“{}”已被移动到{}的其他板块",
+                    post.post_title.as_deref().unwrap_or_default(),
+                    course_name
+                ),
+                ntf_receiver: author,
+                ntf_board_id: Some(board_id.to_string()),
+                ..Self::post_notification(&post, post_id, Some(user_id))
+            };
+
+            self.notification_service
+                .send_notification(notification)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 设置帖子标签
+    async fn set_post_tag(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        tag: &Vec<i32>,
+    ) -> Result<(), ApiError> {
+        let mut post = Entity::find_by_id(post_id)
+            .one(self.db_conn.get_db())
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?
+            .into_active_model();
+
+        let mut tags_ref = [
+            &mut post.post_tag_01,
+            &mut post.post_tag_02,
+            &mut post.post_tag_03,
+            &mut post.post_tag_04,
+            &mut post.post_tag_05,
+            &mut post.post_tag_06,
+            &mut post.post_tag_07,
+            &mut post.post_tag_08,
+            &mut post.post_tag_09,
+            &mut post.post_tag_10,
+        ];
+        let tags_len = tags_ref.len();
+
+        tags_ref
+            .iter_mut()
+            .for_each(|tag_ref| **tag_ref = Set(Some("0".to_string())));
+        tag.iter()
+            .filter(|&&t| t >= 0 && t < (tags_len as i32).clone())
+            .for_each(|&i| *tags_ref[i as usize] = Set(Some("1".to_string())));
+
+        let txn = self.db_conn.get_db().begin().await?;
+        post.save(&txn).await?;
+        self.search_engine_service
+            .enqueue_post(&txn, post_id)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        let comment = format!("TAG 设置了新标签: {:?}", tag);
+        self.log_service
+            .log_post(post_id, user_id, ip_addr, &comment)
+            .await;
+
+        Ok(())
+    }
+
+    /// 设置帖子优先级
+    async fn set_post_priority(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        priority: i32,
+    ) -> Result<(), ApiError> {
+        let mut post = Entity::find_by_id(post_id)
+            .one(self.db_conn.get_db())
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?
+            .into_active_model();
+
+        post.post_priority = Set(Some(priority.to_string()));
+
+        let txn = self.db_conn.get_db().begin().await?;
+        post.save(&txn).await?;
+        self.search_engine_service
+            .enqueue_post(&txn, post_id)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        let comment = format!("PRIORITY 新优先级为：{}", priority);
+        self.log_service
+            .log_post(post_id, user_id, ip_addr, &comment)
+            .await;
+
+        Ok(())
+    }
+
+    /// 删除帖子，连同所有回帖一起隐藏，可以恢复
+    async fn delete_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+    ) -> Result<(), ApiError> {
+        // 已经删除的回帖保留原删除批次，恢复本帖时不会一并恢复
+        let post_ids: Vec<_> = self
+            .post_repository
+            .get_posts_recursively(post_id)
+            .await?
+            .into_iter()
+            .filter(|p| p.post_is_del.as_deref() == Some("0"))
+            .map(|p| p.post_id)
+            .collect();
+        if post_ids.is_empty() {
+            return Ok(());
+        }
+        let root = self.get_thread_root(post_id).await?;
+
+        let del_date = Local::now().naive_local();
+        let del_batch = format!("{}-{}", post_id, random_token());
+
+        let txn = self.db_conn.get_db().begin().await?;
+        Entity::update_many()
+            .col_expr(Cols::PostIsDel, Expr::value("1"))
+            .col_expr(Cols::PostDelDate, Expr::value(del_date))
+            .col_expr(Cols::PostDelBatch, Expr::value(del_batch))
+            .filter(Cols::PostId.is_in(post_ids.clone()))
+            .exec(&txn)
+            .await?;
+        PostRepository::refresh_thread_stats(&txn, &[root.post_id]).await?;
+        self.search_engine_service
+            .enqueue_posts(&txn, &post_ids)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        for id in post_ids {
+            let comment = if id == post_id {
+                String::from("DELETE")
+            } else {
+                format!("DELETE 随帖子{}删除", post_id)
+            };
+            self.log_service
+                .log_post(id, user_id, ip_addr, &comment)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// 恢复被删除的帖子，以及同一次删除的所有回帖
+    async fn restore_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+    ) -> Result<(), ApiError> {
+        let post = self
+            .post_repository
+            .get_post_without_content(post_id)
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if post.post_is_del.as_deref() != Some("1") {
+            return Err(InvalidParameter("帖子未被删除").into());
+        }
+
+        if let Some(parent_id) = post.post_answer_id {
+            let parent = self
+                .post_repository
+                .get_post_without_content(parent_id)
+                .await?;
+            if parent.is_some_and(|p| p.post_is_del.as_deref() == Some("1")) {
+                return Err(InvalidParameter("所回复的帖子已被删除，请先恢复该帖子").into());
+            }
+        }
+
+        let post_ids: Vec<_> = self
+            .post_repository
+            .get_posts_recursively(post_id)
+            .await?
+            .into_iter()
+            .filter(|p| {
+                p.post_is_del.as_deref() == Some("1") && p.post_del_batch == post.post_del_batch
+            })
+            .map(|p| p.post_id)
+            .collect();
+        let root = self.get_thread_root(post_id).await?;
+
+        let txn = self.db_conn.get_db().begin().await?;
+        Entity::update_many()
+            .col_expr(Cols::PostIsDel, Expr::value("0"))
+            .col_expr(
+                Cols::PostDelDate,
+                Expr::value(Option::<NaiveDateTime>::None),
+            )
+            .col_expr(Cols::PostDelBatch, Expr::value(Option::<String>::None))
+            .filter(Cols::PostId.is_in(post_ids.clone()))
+            .exec(&txn)
+            .await?;
+        PostRepository::refresh_thread_stats(&txn, &[root.post_id]).await?;
+        self.search_engine_service
+            .enqueue_posts(&txn, &post_ids)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        for id in post_ids {
+            let comment = if id == post_id {
+                String::from("RESTORE")
+            } else {
+                format!("RESTORE 随帖子{}恢复", post_id)
+            };
+            self.log_service
+                .log_post(id, user_id, ip_addr, &comment)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// 彻底删除超过保留期限的已删除帖子，返回删除的数量
+    async fn purge_deleted_posts(&self, user_id: &str, ip_addr: &IpAddr) -> Result<u64, ApiError> {
+        let before = Local::now().naive_local()
+            - chrono::Duration::days(self.app_config.post.deleted_retention_days);
+        let post_ids = self
+            .post_repository
+            .get_deleted_post_ids_before(before)
+            .await?;
+        if post_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let txn = self.db_conn.get_db().begin().await?;
+        let res = Entity::delete_many()
+            .filter(Cols::PostId.is_in(post_ids.clone()))
+            .exec(&txn)
+            .await?;
+        PostRevisionRepository::delete_by_posts(&txn, &post_ids).await?;
+        PostClaimRepository::delete_by_posts(&txn, &post_ids).await?;
+        PostSubscriptionRepository::delete_by_posts(&txn, &post_ids).await?;
+        AnnouncementReadRepository::delete_by_posts(&txn, &post_ids).await?;
+        NotificationRepository::delete_by_posts(&txn, &post_ids).await?;
+        NotificationPendingRepository::delete_by_posts(&txn, &post_ids).await?;
+        let readers: Vec<_> = PostReadRepository::delete_by_posts(&txn, &post_ids)
+            .await?
+            .into_iter()
+            .map(|record| record.read_sno)
+            .collect();
+        self.search_engine_service
+            .enqueue_posts(&txn, &post_ids)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        if let Err(e) = self
+            .read_state_service
+            .forget_threads(&post_ids, &readers)
+            .await
+        {
+            warn!("清除已删除帖子的阅读记录缓存失败：{}", e);
+        }
+
+        // 记录日志
+        for id in post_ids {
+            self.log_service
+                .log_post(id, user_id, ip_addr, "PURGE")
+                .await;
+        }
+
+        Ok(res.rows_affected)
+    }
+
+    /// 获取帖子的修改历史（不含内容）
+    async fn list_post_revisions(
+        &self,
+        user_id: &str,
+        post_id: i32,
+    ) -> Result<Vec<post_revision::Model>, ApiError> {
+        let mut revisions = self.post_revision_repository.list_by_post(post_id).await?;
+
+        // 匿名帖子的发帖人自己修改的版本，以匿名称呼代替修改人学号
+        let post = self
+            .post_repository
+            .get_post_without_content(post_id)
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        let anonymous = post.post_anonymous.as_deref() == Some("1");
+        let viewer = self.viewer(user_id).await?;
+        if !viewer.can_see_sender(anonymous, post.post_sender_no.as_deref()) {
+            for revision in &mut revisions {
+                if post.post_sender_no.as_deref() == Some(revision.rev_op_no.as_str()) {
+                    revision.rev_op_no = post.post_alias.clone().unwrap_or_default();
+                }
+            }
+        }
+
+        Ok(revisions)
+    }
+
+    /// 比较帖子的两个版本，`to_rev_id`为空时与当前版本比较
+    async fn diff_post_revisions(
+        &self,
+        post_id: i32,
+        from_rev_id: i32,
+        to_rev_id: Option<i32>,
+    ) -> Result<PostRevisionDiff, ApiError> {
+        let from = self.get_revision(post_id, from_rev_id).await?;
+        let (to_title, to_content) = match to_rev_id {
+            Some(rev_id) => {
+                let to = self.get_revision(post_id, rev_id).await?;
+                (to.rev_title, to.rev_content)
+            }
+            None => {
+                let post = Entity::find_by_id(post_id)
+                    .one(self.db_conn.get_db())
+                    .await?
+                    .ok_or(InvalidParameter("帖子不存在"))?;
+                (post.post_title, post.post_content)
+            }
+        };
+
+        let text = |content: Option<String>| {
+            content
+                .map(|c| HtmlCleaner::html_to_text(&c))
+                .unwrap_or_default()
+        };
+        let title_diff = TextDiffer::diff(
+            from.rev_title.as_deref().unwrap_or_default(),
+            to_title.as_deref().unwrap_or_default(),
+        );
+        let content_diff = TextDiffer::diff(&text(from.rev_content), &text(to_content));
+
+        Ok(PostRevisionDiff {
+            from_rev_id,
+            to_rev_id,
+            title_diff: title_diff.into_iter().map(Into::into).collect(),
+            content_diff: content_diff.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// 将帖子回滚到某个历史版本，当前版本会先保存为历史版本
+    async fn rollback_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+        rev_id: i32,
+    ) -> Result<(), ApiError> {
+        let target = self.get_revision(post_id, rev_id).await?;
+        let old_post = Entity::find_by_id(post_id)
+            .one(self.db_conn.get_db())
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        let revision = Self::revision_of(&old_post, user_id, ip_addr, "ROLLBACK");
+
+        let mut post = old_post.into_active_model();
+        post.post_title = Set(target.rev_title);
+        // 历史版本可能早于内容清理，回滚时同样需要清理
+        post.post_content = Set(target
+            .rev_content
+            .map(|content| self.html_sanitizer.sanitize(&content)));
+        post.post_format = Set(target.rev_format);
+        post.post_source = Set(target.rev_source);
+
+        let txn = self.db_conn.get_db().begin().await?;
+        PostRevisionRepository::add(&txn, revision).await?;
+        post.save(&txn).await?;
+        self.search_engine_service
+            .enqueue_post(&txn, post_id)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
+
+        // 记录日志
+        let comment = format!("ROLLBACK 回滚到历史版本{}", rev_id);
+        self.log_service
+            .log_post(post_id, user_id, ip_addr, &comment)
+            .await;
+
+        Ok(())
+    }
+
+    /// 采纳回帖作为主题帖的答案，仅提问者或助教可以操作
+    async fn accept_answer(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        answer_id: i32,
+        is_staff: bool,
+    ) -> Result<(), ApiError> {
+        let answer = self
+            .post_repository
+            .get_post_without_content(answer_id)
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if answer.post_answer_id.is_none() {
+            return Err(InvalidParameter("只能采纳回帖").into());
+        }
+        if answer.post_is_del.as_deref() != Some("0") {
+            return Err(InvalidParameter("不能采纳已删除的回帖").into());
+        }
 
-        let new_post = new_post.insert(self.db_conn.get_db()).await?;
+        let root = self.get_thread_root(answer_id).await?;
+        if !is_staff && root.post_sender_no.as_deref() != Some(user_id) {
+            return Err(AuthError::PermissionDenied("只有提问者或助教可以采纳回帖").into());
+        }
+        if root.post_status.as_deref() == Some(PostStatus::Closed.as_str()) {
+            return Err(InvalidParameter("该帖子已关闭").into());
+        }
+
+        let txn = self.db_conn.get_db().begin().await?;
+        post::ActiveModel {
+            post_id: Set(root.post_id),
+            post_status: Set(Some(PostStatus::Resolved.as_str().into())),
+            post_accepted_id: Set(Some(answer_id)),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
         self.search_engine_service
-            .add_post(new_post.post_id)
+            .enqueue_post(&txn, root.post_id)
             .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
 
         // 记录日志
-        let comment = format!("REPLY 回复{}", father_post_id);
+        let comment = format!("ACCEPT 采纳回帖{}", answer_id);
         self.log_service
-            .log_post(new_post.post_id, user_id, ip_addr, &comment)
+            .log_post(root.post_id, user_id, ip_addr, &comment)
             .await;
 
         // 发送通知
-        if father_post.post_sender_no.as_ref().unwrap() != user_id {
-            let ntf_title = "收到新回复".to_string();
-            let ntf_content = format!(
-                "{}",
-                HtmlCleaner::html_to_text(new_post.post_content.as_ref().unwrap()).abbreviate(35)
-            );
-            let ntf_type = "REPLY".to_string();
-            let ntf_receiver = father_post.post_sender_no.unwrap().clone();
-
+        let answerer = answer.post_sender_no.unwrap_or_default();
+        if answerer != user_id {
+            // 匿名提问者采纳时不透露提问者
+            let actor = match root.post_sender_no.as_deref() == Some(user_id) {
+                true => Self::notification_actor(&root),
+                false => Some(user_id),
+            };
             let notification = notification::Model {
-                ntf_id: 0,
-                ntf_type,
-                ntf_title,
-                ntf_content,
-                ntf_receiver,
-                ntf_datetime: Default::default(),
-                ntf_read: false,
+                ntf_type: "ACCEPT".to_string(),
+                ntf_title: "回帖被采纳".to_string(),
+                ntf_content: format!(
+                    "您在“{}”中的回帖被采纳为答案",
+                    root.post_title.as_deref().unwrap_or_default()
+                ),
+                ntf_receiver: answerer,
+                ..Self::post_notification(&root, answer_id, actor)
             };
 
             self.notification_service
@@ -654,130 +2468,404 @@ impl PostServiceTrait for PostService {
         Ok(())
     }
 
-    /// 编辑帖子
-    async fn edit_post(
+    /// 取消采纳主题帖的答案，仅提问者或助教可以操作
+    async fn unaccept_answer(
         &self,
         user_id: &str,
         ip_addr: &IpAddr,
         post_id: i32,
-        new_content: &str,
+        is_staff: bool,
     ) -> Result<(), ApiError> {
-        let mut post = Entity::find_by_id(post_id)
-            .one(self.db_conn.get_db())
-            .await?
-            .ok_or(InvalidParameter("帖子不存在"))?
-            .into_active_model();
+        let root = self.get_thread_root(post_id).await?;
+        if !is_staff && root.post_sender_no.as_deref() != Some(user_id) {
+            return Err(AuthError::PermissionDenied("只有提问者或助教可以取消采纳").into());
+        }
+        if root.post_accepted_id.is_none() {
+            return Err(InvalidParameter("该帖子没有采纳的回帖").into());
+        }
+        if root.post_status.as_deref() == Some(PostStatus::Closed.as_str()) {
+            return Err(InvalidParameter("该帖子已关闭").into());
+        }
 
-        post.post_content = Set(Some(new_content.to_string()));
-        post.save(self.db_conn.get_db()).await?;
+        let status = self
+            .derive_thread_status(&post::Model {
+                post_accepted_id: None,
+                ..root.clone()
+            })
+            .await?;
+
+        let txn = self.db_conn.get_db().begin().await?;
+        post::ActiveModel {
+            post_id: Set(root.post_id),
+            post_status: Set(Some(status.as_str().into())),
+            post_accepted_id: Set(None),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+        self.search_engine_service
+            .enqueue_post(&txn, root.post_id)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
 
         // 记录日志
-        let comment = "EDIT 进行了编辑";
         self.log_service
-            .log_post(post_id, user_id, ip_addr, comment)
+            .log_post(root.post_id, user_id, ip_addr, "UNACCEPT 取消采纳")
             .await;
 
-        self.search_engine_service.add_post(post_id).await?;
-
         Ok(())
     }
 
-    /// 设置帖子标签
-    async fn set_post_tag(
+    /// 关闭或重新开放主题帖，关闭后不能再回帖
+    async fn set_post_closed(
         &self,
         user_id: &str,
         ip_addr: &IpAddr,
         post_id: i32,
-        tag: &Vec<i32>,
+        closed: bool,
     ) -> Result<(), ApiError> {
-        let mut post = Entity::find_by_id(post_id)
-            .one(self.db_conn.get_db())
+        let root = self
+            .post_repository
+            .get_post_without_content(post_id)
             .await?
-            .ok_or(InvalidParameter("帖子不存在"))?
-            .into_active_model();
-
-        let mut tags_ref = [
-            &mut post.post_tag_01,
-            &mut post.post_tag_02,
-            &mut post.post_tag_03,
-            &mut post.post_tag_04,
-            &mut post.post_tag_05,
-            &mut post.post_tag_06,
-            &mut post.post_tag_07,
-            &mut post.post_tag_08,
-            &mut post.post_tag_09,
-            &mut post.post_tag_10,
-        ];
-        let tags_len = tags_ref.len();
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if root.post_answer_id.is_some() {
+            return Err(InvalidParameter("只能关闭主题帖").into());
+        }
 
-        tags_ref
-            .iter_mut()
-            .for_each(|tag_ref| **tag_ref = Set(Some("0".to_string())));
-        tag.iter()
-            .filter(|&&t| t >= 0 && t < (tags_len as i32).clone())
-            .for_each(|&i| *tags_ref[i as usize] = Set(Some("1".to_string())));
+        let is_closed = root.post_status.as_deref() == Some(PostStatus::Closed.as_str());
+        if is_closed == closed {
+            return Ok(());
+        }
+        let status = match closed {
+            true => PostStatus::Closed,
+            false => self.derive_thread_status(&root).await?,
+        };
 
-        post.save(self.db_conn.get_db()).await?;
+        let txn = self.db_conn.get_db().begin().await?;
+        post::ActiveModel {
+            post_id: Set(post_id),
+            post_status: Set(Some(status.as_str().into())),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+        self.search_engine_service
+            .enqueue_post(&txn, post_id)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
 
         // 记录日志
-        let comment = format!("TAG 设置了新标签: {:?}", tag);
+        let comment = match closed {
+            true => "CLOSE 关闭帖子",
+            false => "REOPEN 重新开放帖子",
+        };
         self.log_service
-            .log_post(post_id, user_id, ip_addr, &comment)
+            .log_post(post_id, user_id, ip_addr, comment)
             .await;
 
         Ok(())
     }
 
-    /// 设置帖子优先级
-    async fn set_post_priority(
+    /// 公开私密提问及其所有回帖
+    async fn publish_post(
         &self,
         user_id: &str,
         ip_addr: &IpAddr,
         post_id: i32,
-        priority: i32,
+        anonymize: bool,
     ) -> Result<(), ApiError> {
-        let mut post = Entity::find_by_id(post_id)
-            .one(self.db_conn.get_db())
+        let root = self
+            .post_repository
+            .get_post_without_content(post_id)
             .await?
-            .ok_or(InvalidParameter("帖子不存在"))?
-            .into_active_model();
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if root.post_answer_id.is_some() {
+            return Err(InvalidParameter("只能公开主题帖").into());
+        }
+        if PostVisibility::from_column(root.post_visibility.as_deref()) == PostVisibility::Public {
+            return Err(InvalidParameter("该帖子已公开").into());
+        }
 
-        post.post_priority = Set(Some(priority.to_string()));
-        post.save(self.db_conn.get_db()).await?;
+        let post_ids: Vec<_> = self
+            .post_repository
+            .get_posts_recursively(post_id)
+            .await?
+            .iter()
+            .map(|p| p.post_id)
+            .collect();
+        let asker = root.post_sender_no.unwrap_or_default();
+
+        let txn = self.db_conn.get_db().begin().await?;
+        Entity::update_many()
+            .col_expr(
+                Cols::PostVisibility,
+                Expr::value(PostVisibility::Public.as_str()),
+            )
+            .col_expr(Cols::PostPrivateSno, Expr::value(Option::<String>::None))
+            .filter(Cols::PostId.is_in(post_ids.clone()))
+            .exec(&txn)
+            .await?;
+        if anonymize {
+            let alias = PostRepository::thread_alias(&txn, post_id, &asker).await?;
+            Entity::update_many()
+                .col_expr(Cols::PostAnonymous, Expr::value("1"))
+                .col_expr(Cols::PostAlias, Expr::value(alias))
+                .filter(Cols::PostId.is_in(post_ids.clone()))
+                .filter(Cols::PostSenderNo.eq(asker.as_str()))
+                .exec(&txn)
+                .await?;
+        }
+        self.search_engine_service
+            .enqueue_posts(&txn, &post_ids)
+            .await?;
+        txn.commit().await?;
+        self.search_engine_service.notify_pending();
 
         // 记录日志
-        let comment = format!("PRIORITY 新优先级为：{}", priority);
+        let comment = match anonymize {
+            true => "PUBLISH 匿名公开私密提问",
+            false => "PUBLISH 公开私密提问",
+        };
         self.log_service
-            .log_post(post_id, user_id, ip_addr, &comment)
+            .log_post(post_id, user_id, ip_addr, comment)
             .await;
 
         Ok(())
     }
 
-    /// 删除帖子
-    async fn delete_post(
+    async fn get_announcement_receipt(
+        &self,
+        post_id: i32,
+    ) -> Result<AnnouncementReceipt, ApiError> {
+        let post = self
+            .post_repository
+            .get_post_without_content(post_id)
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if !Self::is_announcement(&post) {
+            return Err(InvalidParameter("该帖子不是课程公告").into());
+        }
+
+        let enrolled = self
+            .student_info_repository
+            .get_enrolled_students(
+                post.post_term.as_deref().unwrap_or_default(),
+                post.post_course_code.as_deref().unwrap_or_default(),
+            )
+            .await?;
+        let read_dates: HashMap<_, _> = self
+            .announcement_read_repository
+            .get_by_post(post_id)
+            .await?
+            .into_iter()
+            .map(|record| (record.ar_sno, record.ar_date))
+            .collect();
+
+        let enrolled_count = enrolled.len() as u64;
+        let (mut read, unread): (Vec<_>, Vec<_>) = enrolled
+            .into_iter()
+            .map(|student| AnnouncementReader {
+                read_date: read_dates.get(&student.stu_no).copied(),
+                ..student
+            })
+            .partition(|student| student.read_date.is_some());
+        read.sort_by_key(|student| student.read_date);
+
+        Ok(AnnouncementReceipt {
+            post_id,
+            enrolled_count,
+            read_count: read.len() as u64,
+            read,
+            unread,
+        })
+    }
+
+    /// 获取用户有权查看的课程中没有助教回帖的提问，可以限定于某个板块所属的课程
+    async fn get_unanswered_questions(
+        &self,
+        user_id: &str,
+        board_id: Option<&str>,
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<UnansweredQuestions, ApiError> {
+        let mut courses = self.course_service.get_user_course_codes(user_id).await?;
+        if let Some(board_id) = board_id {
+            let board = self.board_service.parse_id(board_id)?;
+            let course = board.course.as_ref().unwrap();
+            let key = (
+                course.course_term.clone(),
+                course.course_code.clone().unwrap_or_default(),
+            );
+            if !courses.contains(&key) {
+                return Err(AuthError::PermissionDenied("您无权查看本板块").into());
+            }
+            courses = vec![key];
+        }
+
+        let staff_level = self.app_config.permission.ta;
+        let total_count = self
+            .post_repository
+            .get_unanswered_questions_count(&courses, staff_level)
+            .await?;
+        let posts = self
+            .post_repository
+            .get_unanswered_questions(
+                &courses,
+                staff_level,
+                page_size,
+                page_size * page_index.saturating_sub(1),
+            )
+            .await?;
+
+        let post_ids: Vec<_> = posts.iter().map(|p| p.post_id).collect();
+        let mut claims = self.post_claim_repository.get_many(&post_ids).await?;
+        let questions = posts
+            .into_iter()
+            .map(|post| {
+                let claim = claims
+                    .iter()
+                    .position(|c| c.claim_post_id == post.post_id)
+                    .map(|i| claims.swap_remove(i));
+                UnansweredQuestion {
+                    post: post::Model {
+                        post_content: None,
+                        ..post
+                    },
+                    claim,
+                }
+            })
+            .collect();
+
+        Ok(UnansweredQuestions {
+            total_count,
+            questions,
+        })
+    }
+
+    /// 认领主题帖，已被他人认领时失败
+    async fn claim_post(
         &self,
         user_id: &str,
         ip_addr: &IpAddr,
         post_id: i32,
     ) -> Result<(), ApiError> {
-        let res = Entity::delete_by_id(post_id)
-            .exec(self.db_conn.get_db())
-            .await?;
+        let post = self
+            .post_repository
+            .get_post_without_content(post_id)
+            .await?
+            .ok_or(InvalidParameter("帖子不存在"))?;
+        if post.post_answer_id.is_some() {
+            return Err(InvalidParameter("只能认领主题帖").into());
+        }
 
-        if res.rows_affected > 0 {
-            // 记录日志
-            let comment = "DELETE";
-            self.log_service
-                .log_post(post_id, user_id, ip_addr, comment)
-                .await;
+        if !self.post_claim_repository.claim(post_id, user_id).await? {
+            let claim = self.post_claim_repository.get(post_id).await?;
+            if claim.is_some_and(|c| c.claim_sno != user_id) {
+                return Err(InvalidParameter("该帖子已被其他助教认领").into());
+            }
+            return Ok(());
+        }
+
+        // 记录日志
+        self.log_service
+            .log_post(post_id, user_id, ip_addr, "CLAIM 认领")
+            .await;
+
+        Ok(())
+    }
+
+    /// 取消自己对主题帖的认领
+    async fn unclaim_post(
+        &self,
+        user_id: &str,
+        ip_addr: &IpAddr,
+        post_id: i32,
+    ) -> Result<(), ApiError> {
+        if !self.post_claim_repository.unclaim(post_id, user_id).await? {
+            return Err(InvalidParameter("您没有认领该帖子").into());
         }
 
+        // 记录日志
+        self.log_service
+            .log_post(post_id, user_id, ip_addr, "UNCLAIM 取消认领")
+            .await;
+
+        Ok(())
+    }
+
+    /// 关注或屏蔽帖子所在的主题帖
+    async fn subscribe_post(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        mode: SubscriptionMode,
+    ) -> Result<(), ApiError> {
+        let root = self.get_thread_root(post_id).await?;
+        Ok(self
+            .post_subscription_repository
+            .set(root.post_id, user_id, mode.as_str())
+            .await?)
+    }
+
+    /// 取消对帖子所在主题帖的订阅
+    async fn unsubscribe_post(&self, user_id: &str, post_id: i32) -> Result<(), ApiError> {
+        let root = self.get_thread_root(post_id).await?;
+        self.post_subscription_repository
+            .remove(root.post_id, user_id)
+            .await?;
         Ok(())
     }
 
+    /// 获取用户订阅的主题帖
+    async fn get_user_subscriptions(
+        &self,
+        user_id: &str,
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<SubscribedPosts, ApiError> {
+        let total_count = self
+            .post_subscription_repository
+            .count_by_user(user_id)
+            .await?;
+        let subscriptions = self
+            .post_subscription_repository
+            .get_by_user(user_id, page_size, page_size * page_index.saturating_sub(1))
+            .await?;
+
+        let post_ids: Vec<_> = subscriptions.iter().map(|s| s.sub_post_id).collect();
+        let posts = self
+            .post_repository
+            .get_posts_without_content(&post_ids)
+            .await?;
+        let subscriptions = subscriptions
+            .into_iter()
+            .map(|subscription| SubscribedPost {
+                post: posts
+                    .iter()
+                    .find(|p| p.post_id == subscription.sub_post_id)
+                    .cloned(),
+                subscription,
+            })
+            .collect();
+
+        let mut result = SubscribedPosts {
+            total_count,
+            subscriptions,
+        };
+        result.redact(&self.viewer(user_id).await?);
+        Ok(result)
+    }
+
     /// 查询帖子，包括所有回帖及回帖的回帖
-    async fn get_post(&self, post_id: i32, with_hidden: bool) -> Result<GetPostsResult, ApiError> {
+    async fn get_post(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        with_hidden: bool,
+    ) -> Result<GetPostsResult, ApiError> {
         let mut posts = self.post_repository.get_posts_recursively(post_id).await?;
 
         if !with_hidden {
@@ -787,15 +2875,318 @@ impl PostServiceTrait for PostService {
                 .collect();
         }
 
+        if let Some(root) = posts.iter().find(|p| p.post_id == post_id) {
+            self.mark_thread_read(user_id, root, posts.iter()).await;
+        }
+
+        posts.redact(&self.viewer(user_id).await?);
         Ok(GetPostsResult { posts })
     }
 
+    /// 以树形结构查询帖子及其回帖，每个帖子的子回帖分页返回
+    async fn get_post_tree(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        with_hidden: bool,
+        page_size: u64,
+        after: Option<i32>,
+        max_depth: u32,
+    ) -> Result<PostTreeNode, ApiError> {
+        let posts = self.post_repository.get_posts_recursively(post_id).await?;
+        let (mut by_id, replies) = Self::group_replies(posts, with_hidden);
+        let post = by_id
+            .remove(&post_id)
+            .ok_or(InvalidParameter("帖子不存在"))?;
+
+        let mut tree = Self::build_tree(post, 0, &replies, page_size as usize, after, max_depth);
+
+        // 只按本次返回的帖子记录阅读位置
+        let mut returned = vec![&tree.post];
+        let mut pending: Vec<_> = tree.children.iter().collect();
+        while let Some(node) = pending.pop() {
+            returned.push(&node.post);
+            pending.extend(node.children.iter());
+        }
+        self.mark_thread_read(user_id, &tree.post, returned.into_iter())
+            .await;
+
+        tree.redact(&self.viewer(user_id).await?);
+        Ok(tree)
+    }
+
+    /// 查询从主题帖到某个回帖的路径，以及该回帖前后的相邻回帖与第一页子回帖
+    async fn get_post_context(
+        &self,
+        user_id: &str,
+        post_id: i32,
+        with_hidden: bool,
+        siblings: u64,
+        page_size: u64,
+    ) -> Result<PostTreeContext, ApiError> {
+        let root_id = self.get_thread_root(post_id).await?.post_id;
+        let posts = self.post_repository.get_posts_recursively(root_id).await?;
+        let (mut by_id, replies) = Self::group_replies(posts, with_hidden);
+
+        // 从定位的回帖向上找到主题帖，路径上的帖子不显示时视为帖子不存在
+        let mut path = vec![];
+        let mut current = Some(post_id);
+        while let Some(id) = current {
+            let post = by_id.remove(&id).ok_or(InvalidParameter("帖子不存在"))?;
+            current = post.post_answer_id;
+            path.push(post);
+        }
+        path.reverse();
+
+        let focus_depth = (path.len() - 1) as u32;
+        let focus = path.pop().unwrap();
+        let mut node = Self::build_tree(
+            focus,
+            focus_depth,
+            &replies,
+            page_size as usize,
+            None,
+            focus_depth + 1,
+        );
+
+        while let Some(parent) = path.pop() {
+            let depth = path.len() as u32;
+            let children = replies.get(&parent.post_id).map_or(&[][..], Vec::as_slice);
+            let index = children
+                .iter()
+                .position(|p| p.post_id == node.post.post_id)
+                .unwrap_or_default();
+
+            // 定位回帖的父帖子返回相邻回帖，更上层只返回路径上的帖子
+            let (start, end) = match depth + 1 == focus_depth {
+                true => (
+                    index.saturating_sub(siblings as usize),
+                    (index + siblings as usize + 1).min(children.len()),
+                ),
+                false => (index, index + 1),
+            };
+
+            let mut parent_node = Self::tree_leaf(parent, depth, &replies);
+            for (i, child) in children.iter().enumerate().take(end).skip(start) {
+                parent_node.children.push(match i == index {
+                    true => std::mem::replace(
+                        &mut node,
+                        Self::tree_leaf(child.clone(), depth + 1, &replies),
+                    ),
+                    false => Self::tree_leaf(child.clone(), depth + 1, &replies),
+                });
+            }
+            if end < children.len() {
+                parent_node.next_cursor = Some(children[end - 1].post_id);
+            }
+            node = parent_node;
+        }
+
+        let mut context = PostTreeContext {
+            focus_id: post_id,
+            root: node,
+        };
+        context.redact(&self.viewer(user_id).await?);
+        Ok(context)
+    }
+
     /// 查询帖子的父帖子
-    async fn get_parent_post(&self, post_id: i32) -> Result<Option<i32>, ApiError> {
+    async fn get_parent_post(&self, user_id: &str, post_id: i32) -> Result<Option<i32>, ApiError> {
+        if !self.ensure_query_post_permission(user_id, post_id).await? {
+            return Err(AuthError::PermissionDenied("您无权查看此帖子").into());
+        }
+
         self.post_repository
             .get_parent_post_recursively(post_id)
             .await
             .map(|p| p.map(|p| p.post_id))
             .map_err(Into::into)
     }
+
+    /// 在用户有权查看的课程中全文搜索帖子
+    async fn search_posts(
+        &self,
+        user_id: &str,
+        query: &str,
+        board_id: Option<&str>,
+        show_hidden: bool,
+        filters: PostSearchFilters,
+        sort: PostSearchSort,
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<PostSearchResult, ApiError> {
+        let page_size = page_size as usize;
+        let page_index = page_index as usize;
+
+        let mut conditions = vec![match board_id {
+            Some(board_id) => {
+                if !self
+                    .ensure_query_board_permission(user_id, board_id)
+                    .await?
+                {
+                    return Err(AuthError::PermissionDenied("您无权查看本板块").into());
+                }
+                Self::board_search_filter(&self.board_service.parse_id(board_id)?)
+            }
+            None => {
+                let course_codes = self.course_service.get_user_course_codes(user_id).await?;
+                if course_codes.is_empty() {
+                    return Ok(PostSearchResult::empty(page_size, page_index));
+                }
+                Self::permission_search_filter(SearchFilter::Any(
+                    course_codes
+                        .iter()
+                        .map(|(term, code)| Self::course_search_filter(term, code))
+                        .collect(),
+                ))
+            }
+        }];
+
+        if !show_hidden {
+            conditions.push(SearchFilter::eq("postIsDel", "0"));
+        }
+
+        let viewer = self.viewer(user_id).await?;
+        conditions.extend(Self::visibility_search_filter(&viewer));
+
+        // 按发帖人搜索时，学生只能搜到他人的非匿名帖子
+        if filters
+            .author
+            .as_deref()
+            .is_some_and(|author| !viewer.is_staff && author != user_id)
+        {
+            conditions.push(SearchFilter::eq("postAnonymous", "0"));
+        }
+        conditions.extend(self.structured_search_filter(filters).await?);
+
+        let mut result = self
+            .search_engine_service
+            .search_posts(
+                query,
+                SearchFilter::All(conditions),
+                sort,
+                page_size,
+                page_index,
+            )
+            .await?;
+        result.redact(&viewer);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::search::embedded::EmbeddedBackend;
+    use crate::search::{IndexSlot, SearchRequest};
+
+    const ASKER: &str = "2150001";
+    const OTHER: &str = "2150002";
+    const STAFF: &str = "1000001";
+
+    fn student(user_id: &str) -> Viewer<'_> {
+        Viewer {
+            user_id,
+            is_staff: false,
+        }
+    }
+
+    fn staff() -> Viewer<'static> {
+        Viewer {
+            user_id: STAFF,
+            is_staff: true,
+        }
+    }
+
+    /// 课程中的帖子，`private_sno`不为空时为该学生的私密提问及其回帖
+    fn post(post_id: i32, answer_id: Option<i32>, private_sno: Option<&str>) -> post::Model {
+        let visibility = match private_sno {
+            Some(_) => PostVisibility::Private,
+            None => PostVisibility::Public,
+        };
+        post::Model {
+            post_id,
+            post_term: Some("2023/2024/1".into()),
+            post_course_code: Some("100001".into()),
+            post_answer_id: answer_id,
+            post_title: Some("作业提问".into()),
+            post_content: Some("作业提问".into()),
+            post_sender_no: Some(private_sno.unwrap_or(OTHER).into()),
+            post_date: chrono::NaiveDate::from_ymd_opt(2024, 3, 1)
+                .and_then(|date| date.and_hms_opt(8, post_id as u32, 0)),
+            post_is_del: Some("0".into()),
+            post_anonymous: Some("0".into()),
+            post_visibility: Some(visibility.as_str().into()),
+            post_private_sno: private_sno.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    /// 以该用户的身份搜索，返回命中的帖子id
+    async fn search_ids(backend: &EmbeddedBackend, viewer: Viewer<'_>) -> Vec<i32> {
+        let filter = PostService::visibility_search_filter(&viewer);
+        let result = backend
+            .search_posts(SearchRequest {
+                query: "作业",
+                filter: SearchFilter::All(filter.into_iter().collect()),
+                sort: PostSearchSort::Oldest,
+                facets: &[],
+                page_size: 10,
+                page_index: 1,
+            })
+            .await
+            .unwrap();
+        result.hits.iter().map(|hit| hit.post.post_id).collect()
+    }
+
+    #[test]
+    fn test_private_post_query_permission() {
+        // 获取帖子与父帖子都通过`ensure_query_post_permission`按帖子本身检查，回帖与主题帖的可见范围相同
+        let courses = [("2023/2024/1".to_string(), "100001".to_string())];
+        let can_query = |post: &post::Model, viewer: Viewer| {
+            PostService::can_query_post(post, &courses, &viewer)
+        };
+        let thread = [post(1, None, Some(ASKER)), post(2, Some(1), Some(ASKER))];
+        for post in &thread {
+            assert!(!can_query(post, student(OTHER)));
+            assert!(can_query(post, student(ASKER)));
+            assert!(can_query(post, staff()));
+        }
+        assert!(can_query(&post(3, None, None), student(OTHER)));
+
+        // 不在用户课程中的帖子，提问者与助教也不能查询
+        let other_course = post::Model {
+            post_course_code: Some("100002".into()),
+            ..post(4, None, Some(ASKER))
+        };
+        assert!(!can_query(&other_course, student(ASKER)));
+        assert!(!can_query(&other_course, staff()));
+        assert!(!PostService::can_query_post(
+            &post(3, None, None),
+            &[],
+            &student(OTHER)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_private_post_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = EmbeddedBackend::open(dir.path().to_str().unwrap()).unwrap();
+        backend
+            .upsert_posts(
+                IndexSlot::Live,
+                vec![
+                    post(1, None, None),
+                    post(2, None, Some(ASKER)),
+                    post(3, Some(2), Some(ASKER)),
+                    post(4, None, Some(OTHER)),
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(search_ids(&backend, student(OTHER)).await, vec![1, 4]);
+        assert_eq!(search_ids(&backend, student(ASKER)).await, vec![1, 2, 3]);
+        assert_eq!(search_ids(&backend, staff()).await, vec![1, 2, 3, 4]);
+    }
 }
@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Local;
+use fred::interfaces::{HashesInterface, KeysInterface, LuaInterface, SetsInterface};
+use log::warn;
+use once_cell::sync::OnceCell;
+use tokio::time::interval;
+
+use crate::config::database::Db;
+use crate::config::redis::{Redis, RedisTrait};
+use crate::config::AppConfig;
+use crate::entity::post_read;
+use crate::error::proc_error::ProcessError;
+use crate::repository::post_read_repo::{PostReadRepository, PostReadRepositoryTrait};
+
+/// 用户阅读记录的Hash键前缀，字段为主题帖id，值为已读到的帖子id
+const READ_KEY_PREFIX: &str = "post-read-";
+
+/// 尚未写入数据库的阅读记录，成员为`学号:主题帖id`
+const READ_DIRTY_KEY: &str = "post-read-dirty";
+
+/// 逐个比较已读位置，只在向后推进时写入并加入待写入队列，返回推进的主题帖数
+///
+/// `KEYS`为阅读记录键与待写入队列键，`ARGV`为缓存秒数、学号，之后依次为主题帖id与帖子id
+const ADVANCE_READ_SCRIPT: &str = r#"
+local advanced = 0
+for i = 3, #ARGV, 2 do
+    local current = tonumber(redis.call("hget", KEYS[1], ARGV[i]))
+    if not current or current < tonumber(ARGV[i + 1]) then
+        redis.call("hset", KEYS[1], ARGV[i], ARGV[i + 1])
+        redis.call("sadd", KEYS[2], ARGV[2] .. ":" .. ARGV[i])
+        advanced = advanced + 1
+    end
+end
+if advanced > 0 then
+    redis.call("expire", KEYS[1], ARGV[1])
+end
+return advanced
+"#;
+
+/// 每批写入数据库的阅读记录数
+const FLUSH_BATCH_SIZE: usize = 500;
+
+fn read_key(user_id: &str) -> String {
+    format!("{}{}", READ_KEY_PREFIX, user_id)
+}
+
+#[async_trait]
+pub trait ReadStateServiceTrait {
+    /// 记录用户已读到主题帖中的某个帖子，参数为主题帖id与帖子id，已读位置只会向后推进
+    async fn mark_read(&self, user_id: &str, positions: &[(i32, i32)]) -> Result<(), ProcessError>;
+
+    /// 获取用户在这些主题帖中已读到的帖子id，没有阅读记录的主题帖不返回
+    async fn get_read_positions(
+        &self,
+        user_id: &str,
+        root_ids: &[i32],
+    ) -> Result<HashMap<i32, i32>, ProcessError>;
+
+    /// 清除这些主题帖在Redis中的阅读记录，用于彻底删除帖子后，`user_ids`为数据库中有阅读记录的用户
+    async fn forget_threads(
+        &self,
+        root_ids: &[i32],
+        user_ids: &[String],
+    ) -> Result<(), ProcessError>;
+}
+
+static SERVICE_RUNNER: OnceCell<Arc<ReadStateServiceRunner>> = OnceCell::new();
+
+/// 定期将Redis中的阅读记录写入数据库
+pub struct ReadStateServiceRunner {
+    redis: Arc<Redis>,
+    post_read_repository: PostReadRepository,
+    flush_interval: Duration,
+}
+
+impl ReadStateServiceRunner {
+    pub fn init(db_conn: &Arc<Db>, redis: &Arc<Redis>, app_config: &AppConfig) {
+        if SERVICE_RUNNER.get().is_none() {
+            let runner = Arc::new(ReadStateServiceRunner {
+                redis: Arc::clone(redis),
+                post_read_repository: PostReadRepository::new(db_conn),
+                flush_interval: Duration::from_secs(app_config.post.read_flush_secs.max(1)),
+            });
+            if SERVICE_RUNNER.set(runner.clone()).is_ok() {
+                runner.run();
+            }
+        }
+    }
+
+    fn run(&self) {
+        let redis = Arc::clone(&self.redis);
+        let repo = self.post_read_repository.clone();
+        let flush_interval = self.flush_interval;
+        tokio::spawn(async move {
+            let mut interval = interval(flush_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::flush(&redis, &repo).await {
+                    warn!("写入阅读记录失败：{}", e);
+                }
+            }
+        });
+    }
+
+    async fn flush(redis: &Redis, repo: &PostReadRepository) -> Result<(), ProcessError> {
+        let pool = redis.get_pool();
+        loop {
+            let members: Vec<String> = pool.spop(READ_DIRTY_KEY, Some(FLUSH_BATCH_SIZE)).await?;
+            if members.is_empty() {
+                return Ok(());
+            }
+            let batch_full = members.len() == FLUSH_BATCH_SIZE;
+
+            let mut by_user: HashMap<&str, Vec<i32>> = HashMap::new();
+            for member in &members {
+                if let Some((user_id, post_id)) = member.rsplit_once(':') {
+                    if let Ok(post_id) = post_id.parse() {
+                        by_user.entry(user_id).or_default().push(post_id);
+                    }
+                }
+            }
+
+            let now = Local::now().naive_local();
+            let mut records = vec![];
+            for (user_id, post_ids) in by_user {
+                let fields: Vec<String> = post_ids.iter().map(ToString::to_string).collect();
+                let last_ids: Vec<Option<i32>> = pool.hmget(read_key(user_id), fields).await?;
+                for (post_id, last_id) in post_ids.into_iter().zip(last_ids) {
+                    if let Some(last_id) = last_id {
+                        records.push(post_read::Model {
+                            read_sno: user_id.to_string(),
+                            read_post_id: post_id,
+                            read_last_id: last_id,
+                            read_date: now,
+                        });
+                    }
+                }
+            }
+
+            if let Err(e) = repo.upsert_many(records).await {
+                // 放回队列，下次重试
+                pool.sadd::<(), _, _>(READ_DIRTY_KEY, members).await?;
+                return Err(e.into());
+            }
+            if !batch_full {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ReadStateService {
+    redis: Arc<Redis>,
+    post_read_repository: PostReadRepository,
+    cache_ttl: i64,
+}
+
+impl ReadStateService {
+    pub fn new(db_conn: &Arc<Db>, redis: &Arc<Redis>, app_config: &AppConfig) -> Self {
+        ReadStateServiceRunner::init(db_conn, redis, app_config);
+        Self {
+            redis: Arc::clone(redis),
+            post_read_repository: PostReadRepository::new(db_conn),
+            cache_ttl: app_config.post.read_cache_days * 24 * 60 * 60,
+        }
+    }
+
+    /// 从Redis读取阅读记录，返回已读位置与缺失的主题帖
+    async fn get_cached_positions(
+        &self,
+        user_id: &str,
+        root_ids: &[i32],
+    ) -> Result<(HashMap<i32, i32>, Vec<i32>), ProcessError> {
+        let fields: Vec<String> = root_ids.iter().map(ToString::to_string).collect();
+        let last_ids: Vec<Option<i32>> = self
+            .redis
+            .get_pool()
+            .hmget(read_key(user_id), fields)
+            .await?;
+
+        let mut positions = HashMap::new();
+        let mut missing = vec![];
+        for (&root_id, last_id) in root_ids.iter().zip(last_ids) {
+            match last_id {
+                Some(last_id) => {
+                    positions.insert(root_id, last_id);
+                }
+                None => missing.push(root_id),
+            }
+        }
+        Ok((positions, missing))
+    }
+}
+
+#[async_trait]
+impl ReadStateServiceTrait for ReadStateService {
+    async fn mark_read(&self, user_id: &str, positions: &[(i32, i32)]) -> Result<(), ProcessError> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+
+        // 先从数据库加载缺失的阅读记录，再在Redis中原子地比较并写入，避免并发请求回退已读位置
+        let root_ids: Vec<_> = positions.iter().map(|&(root_id, _)| root_id).collect();
+        let current = self.get_read_positions(user_id, &root_ids).await?;
+        let mut args = vec![self.cache_ttl.to_string(), user_id.to_string()];
+        for (root_id, last_id) in positions {
+            if current.get(root_id).is_none_or(|c| c < last_id) {
+                args.push(root_id.to_string());
+                args.push(last_id.to_string());
+            }
+        }
+        if args.len() == 2 {
+            return Ok(());
+        }
+
+        self.redis
+            .get_pool()
+            .eval::<i64, _, _, _>(
+                ADVANCE_READ_SCRIPT,
+                vec![read_key(user_id), READ_DIRTY_KEY.to_string()],
+                args,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_read_positions(
+        &self,
+        user_id: &str,
+        root_ids: &[i32],
+    ) -> Result<HashMap<i32, i32>, ProcessError> {
+        if root_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        // Redis不可用时全部从数据库读取，最近一次写入前的阅读记录可能缺失
+        let (mut positions, missing) = match self.get_cached_positions(user_id, root_ids).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("读取阅读记录缓存失败：{}", e);
+                (HashMap::new(), root_ids.to_vec())
+            }
+        };
+        if missing.is_empty() {
+            return Ok(positions);
+        }
+
+        let records = self
+            .post_read_repository
+            .get_many(user_id, &missing)
+            .await?;
+        if !records.is_empty() {
+            let loaded: HashMap<String, i32> = records
+                .iter()
+                .map(|record| (record.read_post_id.to_string(), record.read_last_id))
+                .collect();
+            let pool = self.redis.get_pool();
+            let key = read_key(user_id);
+            if let Err(e) = pool.hset::<(), _, _>(&key, loaded).await {
+                warn!("缓存阅读记录失败：{}", e);
+            } else {
+                let _ = pool.expire::<(), _>(&key, self.cache_ttl).await;
+            }
+        }
+        positions.extend(
+            records
+                .into_iter()
+                .map(|record| (record.read_post_id, record.read_last_id)),
+        );
+
+        Ok(positions)
+    }
+
+    async fn forget_threads(
+        &self,
+        root_ids: &[i32],
+        user_ids: &[String],
+    ) -> Result<(), ProcessError> {
+        if root_ids.is_empty() {
+            return Ok(());
+        }
+
+        // 尚未写入数据库的阅读记录只在待写入队列中
+        let pool = self.redis.get_pool();
+        let roots: HashSet<i32> = root_ids.iter().copied().collect();
+        let dirty: Vec<String> = pool.smembers(READ_DIRTY_KEY).await?;
+        let dirty: Vec<String> = dirty
+            .into_iter()
+            .filter(|member| {
+                member
+                    .rsplit_once(':')
+                    .and_then(|(_, root_id)| root_id.parse().ok())
+                    .is_some_and(|root_id| roots.contains(&root_id))
+            })
+            .collect();
+        if !dirty.is_empty() {
+            pool.srem::<(), _, _>(READ_DIRTY_KEY, dirty.clone()).await?;
+        }
+
+        let mut users: HashSet<&str> = user_ids.iter().map(String::as_str).collect();
+        users.extend(
+            dirty
+                .iter()
+                .filter_map(|member| member.rsplit_once(':').map(|(user_id, _)| user_id)),
+        );
+        let fields: Vec<String> = root_ids.iter().map(ToString::to_string).collect();
+        for user_id in users {
+            pool.hdel::<(), _, _>(read_key(user_id), fields.clone())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
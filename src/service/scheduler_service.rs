@@ -0,0 +1,355 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Days, Local, NaiveDateTime};
+use forum_utils::cron_schedule::CronSchedule;
+use fred::interfaces::{KeysInterface, LuaInterface};
+use fred::prelude::Expiration;
+use fred::types::SetOptions;
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+
+use crate::config::database::Db;
+use crate::config::redis::{Redis, RedisTrait};
+use crate::config::scheduler::{JobConfig, JobKind, SchedulerConfig};
+use crate::config::AppConfig;
+use crate::dto::scheduled_job::{JobStatus, ScheduledJobInfo};
+use crate::error::api_error::ApiError;
+use crate::error::param_error::ParameterError::InvalidParameter;
+use crate::error::proc_error::ProcessError;
+use crate::repository::log_repo::{LogRepository, LogRepositoryTrait};
+use crate::repository::notification_repo::NotificationRepository;
+use crate::repository::scheduled_job_repo::{ScheduledJobRepository, ScheduledJobRepositoryTrait};
+use crate::search::SearchBackend;
+use crate::service::notification_service::{NotificationService, NotificationServiceTrait};
+use crate::service::search_engine_service::{
+    IndexJob, SearchEngineService, SearchEngineServiceTrait,
+};
+use crate::utils::random_utils::{random_token, random_u64};
+
+/// 任务运行锁的键前缀，多个实例之间同一任务只运行一次
+const JOB_LOCK_PREFIX: &str = "scheduled-job-lock-";
+
+/// 仅当锁仍由本次运行持有时才删除，避免删除过期后被其他实例获取的锁
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call("get", KEYS[1]) == ARGV[1] then
+    return redis.call("del", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// 任务的运行周期
+enum Schedule {
+    Cron(CronSchedule),
+    Interval(u64),
+}
+
+impl Schedule {
+    fn of(job: &JobConfig) -> Result<Self, String> {
+        match (&job.cron, job.interval_secs) {
+            (Some(cron), None) => CronSchedule::parse(cron).map(Schedule::Cron),
+            (None, Some(secs)) if secs > 0 => Ok(Schedule::Interval(secs)),
+            _ => Err("需要且只能设置cron或interval_secs之一".to_string()),
+        }
+    }
+
+    fn next_after(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Schedule::Cron(cron) => cron.next_after(now),
+            Schedule::Interval(secs) => Some(now + chrono::Duration::seconds(*secs as i64)),
+        }
+    }
+}
+
+/// 随机推迟的秒数，不超过`max_secs`
+fn jitter(max_secs: u64) -> u64 {
+    if max_secs == 0 {
+        return 0;
+    }
+    random_u64() % (max_secs + 1)
+}
+
+fn describe(job: &JobConfig) -> String {
+    match (&job.cron, job.interval_secs) {
+        (Some(cron), _) => cron.clone(),
+        (None, Some(secs)) => format!("every {}s", secs),
+        (None, None) => String::new(),
+    }
+}
+
+#[async_trait]
+pub trait SchedulerServiceTrait {
+    /// 配置中的所有定时任务及其运行记录
+    async fn list_jobs(&self) -> Result<Vec<ScheduledJobInfo>, ApiError>;
+
+    /// 立即在后台运行任务，不影响原有的运行计划，未启用的任务也可以手动运行
+    fn trigger_job(&self, name: &str) -> Result<(), ApiError>;
+}
+
+static SERVICE_RUNNER: OnceCell<Arc<SchedulerServiceRunner>> = OnceCell::new();
+
+/// 按配置的周期运行定时任务，并记录每次运行的结果
+pub struct SchedulerServiceRunner {
+    redis: Arc<Redis>,
+    scheduled_job_repository: ScheduledJobRepository,
+    notification_repository: NotificationRepository,
+    notification_service: NotificationService,
+    log_repository: LogRepository,
+    search_engine_service: SearchEngineService,
+    config: SchedulerConfig,
+
+    /// 本实例中正在运行的任务
+    running: Mutex<HashSet<String>>,
+}
+
+impl SchedulerServiceRunner {
+    fn init(
+        db_conn: &Arc<Db>,
+        redis: &Arc<Redis>,
+        app_config: &AppConfig,
+        search_backend: &Arc<dyn SearchBackend>,
+    ) -> Arc<Self> {
+        SERVICE_RUNNER
+            .get_or_init(|| {
+                let runner = Arc::new(SchedulerServiceRunner {
+                    redis: Arc::clone(redis),
+                    scheduled_job_repository: ScheduledJobRepository::new(db_conn),
+                    notification_repository: NotificationRepository::new(db_conn),
+                    notification_service: NotificationService::new(db_conn, redis, app_config),
+                    log_repository: LogRepository::new(db_conn),
+                    search_engine_service: SearchEngineService::new(search_backend, db_conn),
+                    config: app_config.scheduler.clone(),
+                    running: Mutex::new(HashSet::new()),
+                });
+                if runner.config.enabled {
+                    runner.run();
+                }
+                runner
+            })
+            .clone()
+    }
+
+    fn run(self: &Arc<Self>) {
+        for job in self.config.jobs.iter().filter(|job| job.enabled) {
+            let schedule = match Schedule::of(job) {
+                Ok(schedule) => schedule,
+                Err(e) => {
+                    warn!("定时任务{}的运行周期无效：{}", job.name, e);
+                    continue;
+                }
+            };
+
+            let runner = Arc::clone(self);
+            let job = job.clone();
+            tokio::spawn(async move {
+                loop {
+                    let now = Local::now().naive_local();
+                    let Some(next_run) = schedule.next_after(now) else {
+                        warn!("定时任务{}没有下一次运行时间", job.name);
+                        return;
+                    };
+                    let next_run =
+                        next_run + chrono::Duration::seconds(jitter(job.jitter_secs) as i64);
+                    if let Err(e) = runner
+                        .scheduled_job_repository
+                        .set_next_run(&job.name, JobStatus::Idle.as_str(), Some(next_run))
+                        .await
+                    {
+                        warn!("记录定时任务{}的运行计划失败：{}", job.name, e);
+                    }
+
+                    tokio::time::sleep((next_run - now).to_std().unwrap_or_default()).await;
+                    match runner.run_job(&job).await {
+                        Ok(true) => {}
+                        Ok(false) => info!("定时任务{}正在其他实例上运行，本次跳过", job.name),
+                        Err(e) => warn!("定时任务{}失败：{}", job.name, e),
+                    }
+                }
+            });
+        }
+    }
+
+    /// 运行一次任务，任务已在其他实例上运行时返回false
+    ///
+    /// 本实例中同一任务不会重叠运行，多个实例之间以Redis锁互斥
+    async fn run_job(&self, job: &JobConfig) -> Result<bool, ApiError> {
+        if !self.running.lock().insert(job.name.clone()) {
+            return Err(ProcessError::GeneralError("该任务正在运行").into());
+        }
+        let result = self.run_job_locked(job).await;
+        self.running.lock().remove(&job.name);
+        result
+    }
+
+    async fn run_job_locked(&self, job: &JobConfig) -> Result<bool, ApiError> {
+        let pool = self.redis.get_pool();
+        let lock_key = format!("{}{}", JOB_LOCK_PREFIX, job.name);
+        let lock_token = random_token();
+        let acquired: Option<String> = pool
+            .set(
+                &lock_key,
+                lock_token.as_str(),
+                Some(Expiration::EX(self.config.lock_secs.max(1) as i64)),
+                Some(SetOptions::NX),
+                false,
+            )
+            .await
+            .map_err(ProcessError::from)?;
+        if acquired.is_none() {
+            return Ok(false);
+        }
+
+        let repo = &self.scheduled_job_repository;
+        let started = Local::now().naive_local();
+        if let Err(e) = repo
+            .mark_started(&job.name, JobStatus::Running.as_str(), started)
+            .await
+        {
+            warn!("记录定时任务{}开始运行失败：{}", job.name, e);
+        }
+
+        let result = self.execute(job).await;
+        let (status, error) = match &result {
+            Ok(_) => (JobStatus::Succeeded, None),
+            Err(e) => (JobStatus::Failed, Some(e.to_string())),
+        };
+        if let Err(e) = repo
+            .mark_finished(
+                &job.name,
+                status.as_str(),
+                Local::now().naive_local(),
+                error,
+            )
+            .await
+        {
+            warn!("记录定时任务{}运行结果失败：{}", job.name, e);
+        }
+        match pool
+            .eval::<i64, _, _, _>(RELEASE_LOCK_SCRIPT, lock_key.as_str(), lock_token.as_str())
+            .await
+        {
+            Ok(0) => warn!(
+                "定时任务{}的运行锁已过期，运行时间可能超过了lock_secs",
+                job.name
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("释放定时任务{}的运行锁失败：{}", job.name, e),
+        }
+
+        result.map(|_| true)
+    }
+
+    async fn execute(&self, job: &JobConfig) -> Result<(), ApiError> {
+        let now = Local::now().naive_local();
+        match job.kind {
+            JobKind::NotificationRetention => {
+                let repo = &self.notification_repository;
+                repo.delete_all_by_date_time_before(
+                    now - Days::new(job.retention_days.unwrap_or(7)),
+                )
+                .await?;
+                repo.delete_all_by_date_time_before_and_read(
+                    now - Days::new(job.read_retention_days.unwrap_or(1)),
+                )
+                .await
+            }
+            JobKind::LogRetention => {
+                let before = now - Days::new(job.retention_days.unwrap_or(180));
+                let deleted = self.log_repository.delete_all_before(before).await?;
+                info!("清理了{}条{}之前的日志", deleted, before);
+                Ok(())
+            }
+            JobKind::NotificationDigest => {
+                let sent = self.notification_service.send_digests().await?;
+                if sent > 0 {
+                    info!("发送了{}条通知摘要", sent);
+                }
+                Ok(())
+            }
+            JobKind::SearchReconcile => self
+                .search_engine_service
+                .run_index_job(IndexJob::Reconcile)
+                .await
+                .map(|_| ())
+                .map_err(Into::into),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SchedulerService {
+    runner: Arc<SchedulerServiceRunner>,
+}
+
+impl SchedulerService {
+    pub fn new(
+        db_conn: &Arc<Db>,
+        redis: &Arc<Redis>,
+        app_config: &AppConfig,
+        search_backend: &Arc<dyn SearchBackend>,
+    ) -> Self {
+        Self {
+            runner: SchedulerServiceRunner::init(db_conn, redis, app_config, search_backend),
+        }
+    }
+}
+
+#[async_trait]
+impl SchedulerServiceTrait for SchedulerService {
+    async fn list_jobs(&self) -> Result<Vec<ScheduledJobInfo>, ApiError> {
+        let records = self.runner.scheduled_job_repository.get_all().await?;
+        let running = self.runner.running.lock().clone();
+
+        Ok(self
+            .runner
+            .config
+            .jobs
+            .iter()
+            .map(|job| {
+                let record = records.iter().find(|r| r.job_name == job.name);
+                let status = match running.contains(&job.name) {
+                    true => JobStatus::Running,
+                    false => JobStatus::from_column(record.map(|r| r.job_status.as_str())),
+                };
+                ScheduledJobInfo {
+                    name: job.name.clone(),
+                    kind: job.kind,
+                    schedule: describe(job),
+                    enabled: job.enabled && self.runner.config.enabled,
+                    status,
+                    last_start: record.and_then(|r| r.job_last_start),
+                    last_finish: record.and_then(|r| r.job_last_finish),
+                    next_run: record.and_then(|r| r.job_next_run),
+                    last_error: record.and_then(|r| r.job_last_error.clone()),
+                }
+            })
+            .collect())
+    }
+
+    fn trigger_job(&self, name: &str) -> Result<(), ApiError> {
+        let job = self
+            .runner
+            .config
+            .jobs
+            .iter()
+            .find(|job| job.name == name)
+            .cloned()
+            .ok_or(InvalidParameter("任务不存在"))?;
+        if self.runner.running.lock().contains(&job.name) {
+            return Err(ProcessError::GeneralError("该任务正在运行").into());
+        }
+
+        let runner = Arc::clone(&self.runner);
+        tokio::spawn(async move {
+            match runner.run_job(&job).await {
+                Ok(true) => info!("手动运行定时任务{}完成", job.name),
+                Ok(false) => info!("定时任务{}正在其他实例上运行，本次跳过", job.name),
+                Err(e) => warn!("手动运行定时任务{}失败：{}", job.name, e),
+            }
+        });
+        Ok(())
+    }
+}
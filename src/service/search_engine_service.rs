@@ -1,95 +1,314 @@
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use chrono::{Local, NaiveDateTime};
 use forum_utils::html_cleaner::HtmlCleaner;
-use once_cell::sync::OnceCell;
-use sea_orm::EntityTrait;
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use log::{info, warn};
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::RwLock;
+use sea_orm::{
+    ColumnTrait, DatabaseTransaction, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
 
 use crate::{
-    config::{
-        database::{DatabaseTrait, Db},
-        meili::Meili,
-        AppConfig,
-    },
-    entity::post,
+    config::database::{DatabaseTrait, Db},
+    dto::post_search::{PostSearchResult, PostSearchSort},
+    entity::{post, search_outbox},
     error::proc_error::ProcessError,
+    repository::{
+        search_outbox_repo::{SearchOutboxRepository, SearchOutboxRepositoryTrait},
+        search_rebuild_repo::{SearchRebuildRepository, SearchRebuildRepositoryTrait},
+    },
+    search::{IndexSlot, SearchBackend, SearchFilter, SearchRequest, FACET_ATTRIBUTES},
+    utils::random_utils::random_token,
 };
 
+/// 重建索引时每批读取的帖子数
+const REINDEX_BATCH_SIZE: u64 = 500;
+
+/// 对账时每批删除的文档数
+const RECONCILE_DELETE_BATCH_SIZE: usize = 1000;
+
+/// 索引维护任务
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IndexJob {
+    /// 从数据库全量重建索引，完成后原子地替换正式索引
+    Reindex,
+    /// 比对索引与数据库中的帖子id，删除多余文档并补齐缺失帖子
+    Reconcile,
+}
+
+/// 索引维护任务的进度
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexJobProgress {
+    /// 最近一次的任务类型
+    pub job: Option<IndexJob>,
+
+    /// 是否正在运行
+    pub running: bool,
+
+    /// 需要处理的帖子总数
+    pub total: u64,
+
+    /// 已处理的帖子数
+    pub processed: u64,
+
+    /// 对账: 从索引中删除的多余文档数
+    pub removed: u64,
+
+    /// 对账: 重新加入同步队列的缺失帖子数
+    pub enqueued: u64,
+
+    /// 开始时间
+    pub started_at: Option<NaiveDateTime>,
+
+    /// 结束时间
+    pub finished_at: Option<NaiveDateTime>,
+
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+static INDEX_JOB_PROGRESS: Lazy<RwLock<IndexJobProgress>> =
+    Lazy::new(|| RwLock::new(Default::default()));
+
+/// 将帖子转换为索引文档，正文替换为纯文本
+fn to_search_document(post: post::Model) -> post::Model {
+    post::Model {
+        post_content: Some(HtmlCleaner::html_to_text(
+            &post.post_content.unwrap_or_default(),
+        )),
+        ..post
+    }
+}
+
 #[async_trait]
 pub trait SearchEngineServiceTrait {
-    async fn add_post(&self, post_id: i32) -> Result<(), ProcessError>;
+    /// 在事务中将帖子加入同步队列，事务提交后需调用`notify_pending`
+    async fn enqueue_post(
+        &self,
+        txn: &DatabaseTransaction,
+        post_id: i32,
+    ) -> Result<(), ProcessError>;
+
+    /// 在事务中将多个帖子加入同步队列，事务提交后需调用`notify_pending`
+    async fn enqueue_posts(
+        &self,
+        txn: &DatabaseTransaction,
+        post_ids: &[i32],
+    ) -> Result<(), ProcessError>;
+
+    /// 唤醒同步任务处理队列
+    fn notify_pending(&self);
+
+    /// 获取同步队列的状态
+    async fn get_outbox_status(&self) -> Result<OutboxStatus, ProcessError>;
+
+    /// 在后台启动索引维护任务
+    fn spawn_index_job(&self, job: IndexJob) -> Result<(), ProcessError>;
+
+    /// 执行索引维护任务并等待完成
+    async fn run_index_job(&self, job: IndexJob) -> Result<IndexJobProgress, ProcessError>;
+
+    /// 获取索引维护任务的进度
+    fn get_index_job_progress(&self) -> IndexJobProgress;
+
+    /// 搜索帖子
+    ///
+    /// 权限限制由调用方通过`filter`传入
+    async fn search_posts(
+        &self,
+        query: &str,
+        filter: SearchFilter,
+        sort: PostSearchSort,
+        page_size: usize,
+        page_index: usize,
+    ) -> Result<PostSearchResult, ProcessError>;
 }
 
 static SERVICE_RUNNER: OnceCell<Arc<SearchEngineServiceRunner>> = OnceCell::new();
 
+/// 同步队列每批处理的条目数
+const OUTBOX_BATCH_SIZE: u64 = 50;
+
+/// 同步失败达到此次数后不再重试，等待管理员处理
+const OUTBOX_MAX_ATTEMPTS: i32 = 10;
+
+/// 领取条目后的处理租约，超时未完成的条目可由其他实例重新领取
+const OUTBOX_LEASE_SECS: i64 = 5 * 60;
+
+/// 没有唤醒信号时轮询队列的间隔
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 重试退避的基础间隔与上限（秒）
+const OUTBOX_BACKOFF_BASE_SECS: i64 = 5;
+const OUTBOX_BACKOFF_MAX_SECS: i64 = 60 * 60;
+
+/// 同步队列的状态
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxStatus {
+    /// 队列中的条目总数
+    pub depth: u64,
+
+    /// 曾经同步失败的条目数
+    pub failed_count: u64,
+
+    /// 已放弃重试的条目数
+    pub dead_count: u64,
+
+    /// 同步失败的条目（最多100条）
+    pub failed: Vec<search_outbox::Model>,
+}
+
 pub struct SearchEngineServiceRunner {
     db_conn: Arc<Db>,
-    meili_client: Arc<Meili>,
-    app_config: Arc<AppConfig>,
+    search_backend: Arc<dyn SearchBackend>,
+    outbox_repository: SearchOutboxRepository,
+    rebuild_repository: SearchRebuildRepository,
+
+    /// 领取同步队列条目时使用的实例标识
+    instance_id: String,
 
-    sender: UnboundedSender<i32>,
+    wakeup: Arc<Notify>,
 }
 
 impl SearchEngineServiceRunner {
-    pub fn new(meili_client: &Arc<Meili>, db_conn: &Arc<Db>, app_config: &Arc<AppConfig>) -> Self {
-        let (sender, receiver) = mpsc::unbounded_channel();
+    pub fn new(search_backend: &Arc<dyn SearchBackend>, db_conn: &Arc<Db>) -> Self {
+        let _self = Self::idle(search_backend, db_conn);
 
-        let _self = Self {
-            meili_client: Arc::clone(meili_client),
+        _self.run();
+
+        _self
+    }
+
+    /// 不启动同步任务，唤醒信号不会被处理
+    fn idle(search_backend: &Arc<dyn SearchBackend>, db_conn: &Arc<Db>) -> Self {
+        Self {
+            search_backend: Arc::clone(search_backend),
             db_conn: Arc::clone(db_conn),
-            app_config: Arc::clone(app_config),
-            sender,
-        };
+            outbox_repository: SearchOutboxRepository::new(db_conn),
+            rebuild_repository: SearchRebuildRepository::new(db_conn),
+            instance_id: random_token(),
+            wakeup: Arc::new(Notify::new()),
+        }
+    }
 
-        _self.run(receiver);
+    /// 唤醒同步任务处理队列
+    pub fn notify(&self) {
+        self.wakeup.notify_one();
+    }
 
-        _self
+    fn backoff(attempts: i32) -> chrono::Duration {
+        let secs = OUTBOX_BACKOFF_BASE_SECS
+            .saturating_mul(1 << attempts.clamp(0, 20))
+            .min(OUTBOX_BACKOFF_MAX_SECS);
+        chrono::Duration::seconds(secs)
     }
 
-    pub fn add_post(&self, id: i32) {
-        self.sender.send(id).unwrap();
+    /// 将单个帖子同步到搜索引擎，帖子不存在时从索引中删除
+    async fn sync_post(
+        db: &Db,
+        search_backend: &dyn SearchBackend,
+        rebuild_repo: &SearchRebuildRepository,
+        post_id: i32,
+    ) -> Result<(), ProcessError> {
+        match post::Entity::find_by_id(post_id).one(db.get_db()).await? {
+            // 已删除的帖子同样入库，由查询时根据权限过滤
+            Some(post) => {
+                search_backend
+                    .upsert_posts(IndexSlot::Live, vec![to_search_document(post)])
+                    .await?
+            }
+            None => {
+                search_backend
+                    .delete_posts(IndexSlot::Live, &[post_id])
+                    .await?
+            }
+        }
+
+        // 重建索引期间的修改需要在替换索引后重新同步，重建可能由其他实例执行
+        rebuild_repo.mark_dirty(post_id).await?;
+
+        Ok(())
     }
 
-    fn run(&self, mut receiver: UnboundedReceiver<i32>) {
+    fn run(&self) {
         let db = Arc::clone(&self.db_conn);
-        let meili_client = Arc::clone(&self.meili_client);
-        let config = Arc::clone(&self.app_config);
+        let search_backend = Arc::clone(&self.search_backend);
+        let repo = self.outbox_repository.clone();
+        let rebuild_repo = self.rebuild_repository.clone();
+        let owner = self.instance_id.clone();
+        let wakeup = Arc::clone(&self.wakeup);
         tokio::spawn(async move {
+            if let Err(e) = search_backend.prepare(IndexSlot::Live).await {
+                warn!("初始化搜索索引失败：{}", e);
+            }
+
             loop {
-                let post_id = receiver.recv().await;
-                if post_id.is_none() {
-                    // Sender已经被全部销毁，我也销毁
-                    break;
-                }
-                let post_id = post_id.unwrap();
-
-                let post = post::Entity::find_by_id(post_id).one(db.get_db()).await;
-                if let Ok(Some(post)) = post {
-                    if post.post_is_del.as_ref().unwrap() == "1" {
-                        let _ = meili_client
-                            .get_client()
-                            .index(&config.meili.index.post)
-                            .delete_document(post_id)
-                            .await;
-                        continue;
-                    }
+                let now = Local::now().naive_local();
+                let lease_until = now + chrono::Duration::seconds(OUTBOX_LEASE_SECS);
+                let entries = repo
+                    .claim_due(
+                        &owner,
+                        OUTBOX_MAX_ATTEMPTS,
+                        now,
+                        lease_until,
+                        OUTBOX_BATCH_SIZE,
+                    )
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("读取搜索引擎同步队列失败：{}", e);
+                        vec![]
+                    });
+                let batch_full = entries.len() as u64 == OUTBOX_BATCH_SIZE;
 
-                    let post = post::Model {
-                        post_content: Some(HtmlCleaner::html_to_text(
-                            &post.post_content.unwrap_or_default(),
-                        )),
-                        ..post
+                for entry in entries {
+                    let result = match Self::sync_post(
+                        &db,
+                        search_backend.as_ref(),
+                        &rebuild_repo,
+                        entry.outbox_post_id,
+                    )
+                    .await
+                    {
+                        Ok(()) => repo.delete_by_id(entry.outbox_id).await,
+                        Err(e) => {
+                            let attempts = entry.outbox_attempts + 1;
+                            warn!(
+                                "帖子{}同步到搜索引擎失败（第{}次）：{}",
+                                entry.outbox_post_id, attempts, e
+                            );
+                            repo.mark_failed(
+                                &owner,
+                                entry.outbox_id,
+                                attempts,
+                                Local::now().naive_local() + Self::backoff(attempts),
+                                &e.to_string(),
+                            )
+                            .await
+                        }
                     };
+                    if let Err(e) = result {
+                        warn!("更新搜索引擎同步队列失败：{}", e);
+                    }
+                }
 
-                    let _ = meili_client
-                        .get_client()
-                        .index(&config.meili.index.post)
-                        .add_documents(&[post], None)
-                        .await;
-                } // 做一些事情
-            } // 业务代码
+                if !batch_full {
+                    tokio::select! {
+                        _ = wakeup.notified() => {}
+                        _ = tokio::time::sleep(OUTBOX_POLL_INTERVAL) => {}
+                    }
+                }
+            }
         });
     }
 }
@@ -97,27 +316,252 @@ impl SearchEngineServiceRunner {
 #[derive(Clone)]
 pub struct SearchEngineService {
     runner: Arc<SearchEngineServiceRunner>,
+    db_conn: Arc<Db>,
+    outbox_repository: SearchOutboxRepository,
+    rebuild_repository: SearchRebuildRepository,
+    search_backend: Arc<dyn SearchBackend>,
 }
 
 impl SearchEngineService {
-    pub fn new(meili_client: &Arc<Meili>, db_conn: &Arc<Db>, app_config: &Arc<AppConfig>) -> Self {
+    pub fn new(search_backend: &Arc<dyn SearchBackend>, db_conn: &Arc<Db>) -> Self {
         let runner = SERVICE_RUNNER
-            .get_or_init(|| {
-                Arc::new(SearchEngineServiceRunner::new(
-                    meili_client,
-                    db_conn,
-                    app_config,
-                ))
-            })
+            .get_or_init(|| Arc::new(SearchEngineServiceRunner::new(search_backend, db_conn)))
             .clone();
-        Self { runner }
+        Self::with_runner(runner, search_backend, db_conn)
+    }
+
+    /// 不启动同步任务的服务，用于命令行维护任务
+    ///
+    /// 加入同步队列的帖子由正在运行的服务端处理，避免与服务端争用索引
+    pub fn without_worker(search_backend: &Arc<dyn SearchBackend>, db_conn: &Arc<Db>) -> Self {
+        let runner = Arc::new(SearchEngineServiceRunner::idle(search_backend, db_conn));
+        Self::with_runner(runner, search_backend, db_conn)
+    }
+
+    fn with_runner(
+        runner: Arc<SearchEngineServiceRunner>,
+        search_backend: &Arc<dyn SearchBackend>,
+        db_conn: &Arc<Db>,
+    ) -> Self {
+        Self {
+            runner,
+            db_conn: Arc::clone(db_conn),
+            outbox_repository: SearchOutboxRepository::new(db_conn),
+            rebuild_repository: SearchRebuildRepository::new(db_conn),
+            search_backend: Arc::clone(search_backend),
+        }
+    }
+
+    /// 标记任务开始，已有任务在运行时返回错误
+    fn begin_index_job(job: IndexJob) -> Result<(), ProcessError> {
+        let mut progress = INDEX_JOB_PROGRESS.write();
+        if progress.running {
+            return Err(ProcessError::GeneralError("已有索引维护任务正在运行"));
+        }
+
+        *progress = IndexJobProgress {
+            job: Some(job),
+            running: true,
+            started_at: Some(Local::now().naive_local()),
+            ..Default::default()
+        };
+        Ok(())
+    }
+
+    fn finish_index_job(result: &Result<(), ProcessError>) -> IndexJobProgress {
+        let mut progress = INDEX_JOB_PROGRESS.write();
+        progress.running = false;
+        progress.finished_at = Some(Local::now().naive_local());
+        progress.error = result.as_ref().err().map(ToString::to_string);
+        progress.clone()
+    }
+
+    async fn execute_index_job(&self, job: IndexJob) -> Result<(), ProcessError> {
+        match job {
+            IndexJob::Reindex => {
+                let rebuild_id = self
+                    .rebuild_repository
+                    .begin(Local::now().naive_local())
+                    .await?;
+                let result = self.rebuild_index().await;
+
+                // 替换后的索引缺少重建期间的修改，重新入队；入队失败不影响重建本身的结果
+                if let Err(e) = self.requeue_rebuild_dirty(rebuild_id).await {
+                    warn!("重建索引期间修改的帖子重新入队失败，请执行reconcile：{}", e);
+                }
+                self.runner.notify();
+
+                result
+            }
+            IndexJob::Reconcile => self.reconcile_index().await,
+        }
+    }
+
+    /// 关闭重建窗口，并将窗口内同步过的帖子重新加入同步队列
+    async fn requeue_rebuild_dirty(&self, rebuild_id: u64) -> Result<(), DbErr> {
+        let dirty = self
+            .rebuild_repository
+            .finish(rebuild_id, Local::now().naive_local())
+            .await?;
+        SearchOutboxRepository::enqueue_many(self.db_conn.get_db(), &dirty).await
+    }
+
+    /// 在临时索引中全量重建，完成后替换正式索引
+    async fn rebuild_index(&self) -> Result<(), ProcessError> {
+        let db = self.db_conn.get_db();
+        let backend = self.search_backend.as_ref();
+
+        backend.reset(IndexSlot::Rebuild).await?;
+
+        let total = post::Entity::find().count(db).await?;
+        INDEX_JOB_PROGRESS.write().total = total;
+
+        let mut last_id = 0;
+        loop {
+            let posts = post::Entity::find()
+                .filter(post::Column::PostId.gt(last_id))
+                .order_by_asc(post::Column::PostId)
+                .limit(REINDEX_BATCH_SIZE)
+                .all(db)
+                .await?;
+            if posts.is_empty() {
+                break;
+            }
+            last_id = posts.last().unwrap().post_id;
+
+            let count = posts.len() as u64;
+            let documents: Vec<_> = posts.into_iter().map(to_search_document).collect();
+            backend.upsert_posts(IndexSlot::Rebuild, documents).await?;
+
+            let mut progress = INDEX_JOB_PROGRESS.write();
+            progress.processed += count;
+            info!("重建搜索索引：{}/{}", progress.processed, progress.total);
+        }
+
+        backend.promote_rebuild().await
+    }
+
+    /// 比对索引与数据库中的帖子id并修复差异
+    async fn reconcile_index(&self) -> Result<(), ProcessError> {
+        let db = self.db_conn.get_db();
+        let backend = self.search_backend.as_ref();
+
+        let indexed_ids = backend.list_post_ids(IndexSlot::Live).await?;
+        let db_ids: HashSet<i32> = post::Entity::find()
+            .select_only()
+            .column(post::Column::PostId)
+            .into_tuple()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect();
+
+        INDEX_JOB_PROGRESS.write().total = indexed_ids.union(&db_ids).count() as u64;
+
+        // 数据库中已不存在的帖子
+        let orphaned: Vec<_> = indexed_ids.difference(&db_ids).cloned().collect();
+        for chunk in orphaned.chunks(RECONCILE_DELETE_BATCH_SIZE) {
+            backend.delete_posts(IndexSlot::Live, chunk).await?;
+        }
+
+        // 索引中缺失的帖子交给同步队列处理
+        let missing: Vec<_> = db_ids.difference(&indexed_ids).cloned().collect();
+        SearchOutboxRepository::enqueue_many(db, &missing).await?;
+        self.runner.notify();
+
+        let mut progress = INDEX_JOB_PROGRESS.write();
+        progress.processed = progress.total;
+        progress.removed = orphaned.len() as u64;
+        progress.enqueued = missing.len() as u64;
+        info!(
+            "搜索索引对账完成：删除{}个多余文档，补齐{}个缺失帖子",
+            progress.removed, progress.enqueued
+        );
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl SearchEngineServiceTrait for SearchEngineService {
-    async fn add_post(&self, post_id: i32) -> Result<(), ProcessError> {
-        self.runner.add_post(post_id);
+    async fn enqueue_post(
+        &self,
+        txn: &DatabaseTransaction,
+        post_id: i32,
+    ) -> Result<(), ProcessError> {
+        SearchOutboxRepository::enqueue(txn, post_id)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn enqueue_posts(
+        &self,
+        txn: &DatabaseTransaction,
+        post_ids: &[i32],
+    ) -> Result<(), ProcessError> {
+        SearchOutboxRepository::enqueue_many(txn, post_ids)
+            .await
+            .map_err(Into::into)
+    }
+
+    fn notify_pending(&self) {
+        self.runner.notify();
+    }
+
+    async fn get_outbox_status(&self) -> Result<OutboxStatus, ProcessError> {
+        let repo = &self.outbox_repository;
+
+        Ok(OutboxStatus {
+            depth: repo.count().await?,
+            failed_count: repo.count_failed().await?,
+            dead_count: repo.count_dead(OUTBOX_MAX_ATTEMPTS).await?,
+            failed: repo.find_failed(100).await?,
+        })
+    }
+
+    fn spawn_index_job(&self, job: IndexJob) -> Result<(), ProcessError> {
+        Self::begin_index_job(job)?;
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let result = service.execute_index_job(job).await;
+            if let Err(e) = &result {
+                warn!("索引维护任务{:?}失败：{}", job, e);
+            }
+            Self::finish_index_job(&result);
+        });
         Ok(())
     }
+
+    async fn run_index_job(&self, job: IndexJob) -> Result<IndexJobProgress, ProcessError> {
+        Self::begin_index_job(job)?;
+
+        let result = self.execute_index_job(job).await;
+        let progress = Self::finish_index_job(&result);
+        result.map(|_| progress)
+    }
+
+    fn get_index_job_progress(&self) -> IndexJobProgress {
+        INDEX_JOB_PROGRESS.read().clone()
+    }
+
+    async fn search_posts(
+        &self,
+        query: &str,
+        filter: SearchFilter,
+        sort: PostSearchSort,
+        page_size: usize,
+        page_index: usize,
+    ) -> Result<PostSearchResult, ProcessError> {
+        self.search_backend
+            .search_posts(SearchRequest {
+                query,
+                filter,
+                sort,
+                facets: FACET_ATTRIBUTES,
+                page_size,
+                page_index,
+            })
+            .await
+    }
 }
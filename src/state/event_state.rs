@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{database::Db, redis::Redis, AppConfig},
+    search::SearchBackend,
+    service::{event_service::EventService, post_service::PostService},
+};
+
+#[derive(Clone)]
+pub struct EventState {
+    pub post_service: PostService,
+    pub event_service: EventService,
+}
+
+impl EventState {
+    pub fn new(
+        db: &Arc<Db>,
+        redis: &Arc<Redis>,
+        app_config: &Arc<AppConfig>,
+        search_backend: &Arc<dyn SearchBackend>,
+    ) -> Self {
+        Self {
+            post_service: PostService::new(db, redis, app_config, search_backend),
+            event_service: EventService::new(redis),
+        }
+    }
+}
@@ -1,10 +1,13 @@
 pub mod auth_state;
 pub mod board_state;
 pub mod course_state;
+pub mod event_state;
 pub mod homework_state;
 pub mod limit_state;
 pub mod metadata_state;
 pub mod notification_state;
 pub mod post_state;
+pub mod scheduler_state;
+pub mod search_state;
 pub mod upload_state;
 pub mod user_state;
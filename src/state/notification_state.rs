@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use crate::{config::database::Db, service::notification_service::NotificationService};
+use crate::{
+    config::{database::Db, redis::Redis, AppConfig},
+    service::notification_service::NotificationService,
+};
 
 #[derive(Clone)]
 pub struct NotificationState {
@@ -8,9 +11,9 @@ pub struct NotificationState {
 }
 
 impl NotificationState {
-    pub fn new(db: &Arc<Db>) -> Self {
+    pub fn new(db: &Arc<Db>, redis: &Arc<Redis>, app_config: &Arc<AppConfig>) -> Self {
         Self {
-            notification_service: NotificationService::new(db),
+            notification_service: NotificationService::new(db, redis, app_config),
         }
     }
 }
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use crate::{
-    config::{database::Db, meili::Meili, AppConfig},
+    config::{database::Db, redis::Redis, AppConfig},
+    search::SearchBackend,
     service::post_service::PostService,
 };
 
@@ -11,9 +12,14 @@ pub struct PostState {
 }
 
 impl PostState {
-    pub fn new(db: &Arc<Db>, app_config: &Arc<AppConfig>, meili_client: &Arc<Meili>) -> Self {
+    pub fn new(
+        db: &Arc<Db>,
+        redis: &Arc<Redis>,
+        app_config: &Arc<AppConfig>,
+        search_backend: &Arc<dyn SearchBackend>,
+    ) -> Self {
         Self {
-            post_service: PostService::new(db, app_config, meili_client),
+            post_service: PostService::new(db, redis, app_config, search_backend),
         }
     }
 }
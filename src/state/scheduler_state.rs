@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{database::Db, redis::Redis, AppConfig},
+    search::SearchBackend,
+    service::scheduler_service::SchedulerService,
+};
+
+#[derive(Clone)]
+pub struct SchedulerState {
+    pub scheduler_service: SchedulerService,
+}
+
+impl SchedulerState {
+    pub fn new(
+        db: &Arc<Db>,
+        redis: &Arc<Redis>,
+        app_config: &Arc<AppConfig>,
+        search_backend: &Arc<dyn SearchBackend>,
+    ) -> Self {
+        Self {
+            scheduler_service: SchedulerService::new(db, redis, app_config, search_backend),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use std::sync::Arc;
+
+use crate::{
+    config::database::Db, search::SearchBackend,
+    service::search_engine_service::SearchEngineService,
+};
+
+#[derive(Clone)]
+pub struct SearchState {
+    pub search_engine_service: SearchEngineService,
+}
+
+impl SearchState {
+    pub fn new(db: &Arc<Db>, search_backend: &Arc<dyn SearchBackend>) -> Self {
+        Self {
+            search_engine_service: SearchEngineService::new(search_backend, db),
+        }
+    }
+}
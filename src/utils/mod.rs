@@ -1 +1,2 @@
+pub mod random_utils;
 pub mod string_utils;
@@ -0,0 +1,19 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 随机数，每次调用使用新的随机种子，不能用于密码学用途
+pub fn random_u64() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(nanos);
+    hasher.finish()
+}
+
+/// 随机的十六进制字符串，用于区分服务实例或锁的持有者
+pub fn random_token() -> String {
+    format!("{:016x}{:016x}", random_u64(), random_u64())
+}